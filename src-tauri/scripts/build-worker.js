@@ -0,0 +1,47 @@
+// Builds spice_worker next to the app and where tauri bundles it from, binaries/spice_worker-<target triple>,
+// see tauri.bundle.conf.json
+import { execFileSync } from "node:child_process";
+import { copyFileSync, mkdirSync } from "node:fs";
+import { dirname, join } from "node:path";
+import { fileURLToPath } from "node:url";
+
+const tauriDir = join(dirname(fileURLToPath(import.meta.url)), "..");
+const isRelease = process.argv.includes("--release");
+const extension = process.platform === "win32" ? ".exe" : "";
+
+const hostTriple = execFileSync("rustc", ["-vV"])
+  .toString()
+  .match(/^host: (\S+)$/m)[1];
+// Set by the tauri cli when building for another target
+const targetTriple = process.env.TAURI_ENV_TARGET_TRIPLE ?? hostTriple;
+const isCrossBuild = targetTriple !== hostTriple;
+
+const sidecarPath = join(
+  tauriDir,
+  "binaries",
+  `spice_worker-${targetTriple}${extension}`
+);
+
+execFileSync(
+  "cargo",
+  [
+    "build",
+    "--bin",
+    "spice_worker",
+    ...(isRelease ? ["--release"] : []),
+    ...(isCrossBuild ? ["--target", targetTriple] : [])
+  ],
+  { cwd: tauriDir, stdio: "inherit" }
+);
+
+mkdirSync(dirname(sidecarPath), { recursive: true });
+copyFileSync(
+  join(
+    tauriDir,
+    "target",
+    ...(isCrossBuild ? [targetTriple] : []),
+    isRelease ? "release" : "debug",
+    `spice_worker${extension}`
+  ),
+  sidecarPath
+);
@@ -0,0 +1,150 @@
+//! Simulator without the app: load a project, build its schematic and run its analyses,
+//! with everything the simulations produce going to a `ResultSink`
+
+use std::{
+    collections::HashMap,
+    fs::File,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use native_db::{db_type, Builder, Database};
+
+use crate::{
+    app_state::models::bjt::DATABASE_BJT_MODELS,
+    compat::{engine::Engine, spice::graphic_spice::engine::GraphicSpice},
+    init::models::init_models,
+    simulator::{
+        circuit::{
+            canvas::{CanvasEdge, CanvasNode},
+            schematic::Schematic,
+        },
+        decimation::DEFAULT_DISPLAY_POINTS_PER_SECOND,
+        estimate::estimate_simulations,
+        runner::start_simulations,
+        sharedlib::{get_pool_size, get_source_lib_path},
+        simulation::SimulationConfig,
+        simulator::Simulator,
+    },
+};
+
+pub use crate::simulator::{
+    estimate::{CostThresholds, CostWarning, SimulationEstimate, TimestepSuggestion},
+    external_sources::{ExternalSources, ExternalSourcesFactory},
+    runner::{SimulationRun, SimulationRunOptions},
+    settings::SpiceSettings,
+    simulator_error::SimulatorError,
+    sink::{ResultSink, SinkClosed},
+    worker::WorkerMode,
+};
+
+/// Canvas and analyses of a .gsp project
+#[derive(Clone)]
+pub struct Project {
+    pub nodes: Vec<CanvasNode>,
+    pub edges: Vec<CanvasEdge>,
+    pub config: HashMap<String, SimulationConfig>,
+}
+
+impl Project {
+    pub fn load(path: &Path, bjt_models: &Database<'static>) -> Result<Project, SimulatorError> {
+        let failed_to_load =
+            || SimulatorError::FailedToLoadGraphicSpiceFile(path.to_string_lossy().to_string());
+
+        let file = File::open(path).map_err(|_| failed_to_load())?;
+
+        let (nodes, edges, config) =
+            GraphicSpice::file_to_domain(file, bjt_models).map_err(|_| failed_to_load())?;
+
+        return Ok(Project {
+            nodes,
+            edges,
+            config,
+        });
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), SimulatorError> {
+        let file = File::create(path).map_err(|_| SimulatorError::FailedToSaveGraphicSpiceFile)?;
+
+        GraphicSpice::domain_to_file(
+            self.nodes.clone(),
+            self.edges.clone(),
+            self.config.clone(),
+            file,
+        )
+        .map_err(|_| SimulatorError::FailedToSaveGraphicSpiceFile)
+    }
+
+    pub fn build_schematic(&self) -> Result<Schematic, SimulatorError> {
+        Simulator::create_schematic_from_canvas(self.nodes.clone(), self.edges.clone())
+    }
+
+    /// Expected size of every analysis, see `estimate_simulations`
+    pub fn estimate(
+        &self,
+        thresholds: &CostThresholds,
+    ) -> Result<HashMap<String, SimulationEstimate>, SimulatorError> {
+        let schematic = self.build_schematic()?;
+
+        Ok(estimate_simulations(&schematic, &self.config, thresholds))
+    }
+
+    /// Runs every analysis of the project, see `run_analyses`
+    pub fn simulate(
+        &self,
+        options: SimulationRunOptions,
+        sink: Arc<dyn ResultSink>,
+    ) -> Result<SimulationRun, SimulatorError> {
+        let schematic = self.build_schematic()?;
+
+        Ok(run_analyses(schematic, self.config.clone(), options, sink))
+    }
+}
+
+/// BJT models projects refer to, kept in memory and optionally seeded from a models file
+pub fn create_bjt_models(seed_file: Option<&Path>) -> Result<Database<'static>, db_type::Error> {
+    let bjt_models = Builder::new().create_in_memory(&DATABASE_BJT_MODELS)?;
+
+    if let Some(seed_file) = seed_file {
+        init_models(seed_file, &bjt_models);
+    }
+
+    Ok(bjt_models)
+}
+
+/// Run options from the settings the app uses, thread copies of libngspice go to `lib_dir`.
+/// Without a resource dir only the selected and system libraries are considered
+pub fn get_run_options(
+    settings: &SpiceSettings,
+    resource_dir: Option<&Path>,
+    lib_dir: PathBuf,
+) -> Result<SimulationRunOptions, SimulatorError> {
+    let source_lib_path = get_source_lib_path(resource_dir, settings)?;
+
+    return Ok(SimulationRunOptions {
+        source_lib_path,
+        lib_dir,
+        pool_size: get_pool_size(settings),
+        worker_mode: settings.worker_mode,
+        cost_thresholds: settings.cost_thresholds.clone(),
+        external_sources: None,
+        display_points_per_second: Some(
+            settings
+                .display_points_per_second
+                .unwrap_or(DEFAULT_DISPLAY_POINTS_PER_SECOND),
+        ),
+        result_cache_dir: None,
+    });
+}
+
+/// Starts the analyses on a pool of simulation threads and returns right away.
+/// Data, statuses and results are sent to the sink as they come, analyses estimated
+/// over the max thresholds of the options are failed without running
+pub fn run_analyses(
+    schematic: Schematic,
+    config: HashMap<String, SimulationConfig>,
+    options: SimulationRunOptions,
+    sink: Arc<dyn ResultSink>,
+) -> SimulationRun {
+    start_simulations(schematic, config, options, sink)
+}
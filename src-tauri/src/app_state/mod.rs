@@ -1,13 +1,26 @@
 pub mod instance;
 pub mod models;
+pub mod settings;
 
-use std::sync::Mutex;
+use std::sync::{Arc, Mutex};
 
 use super::*;
 use instance::InstanceState;
 use native_db::Database;
+use settings::SpiceSettings;
+
+use crate::simulator::{
+    orchestrator::SimulationThreadOrchestrator, result_store::ResultStore, waveforms::WaveformStore,
+};
 
 pub struct AppState {
     pub bjt_models: Database<'static>,
     pub instance_state: Mutex<InstanceState>,
+    // Orchestrator of the ongoing simulate call, if any
+    pub simulation_orchestrator: Mutex<Option<Arc<Mutex<SimulationThreadOrchestrator>>>>,
+    // Every point of the last simulate call, kept after it is done for zooming in
+    pub simulation_waveforms: Mutex<Option<Arc<Mutex<WaveformStore>>>>,
+    // Vectors of the finished simulations of the last simulate call
+    pub simulation_results: Mutex<Option<Arc<Mutex<ResultStore>>>>,
+    pub spice_settings: Mutex<SpiceSettings>,
 }
@@ -0,0 +1,87 @@
+use std::path::PathBuf;
+
+use tauri::{AppHandle, Manager};
+
+use super::AppState;
+use crate::simulator::{
+    sharedlib::{detect_spice_version, get_source_lib_path, SpiceLibraryInfo, LIB_NAME},
+    simulator_error::SimulatorError,
+};
+
+// Kept with the simulator so the project API does not need tauri
+pub use crate::simulator::settings::SpiceSettings;
+
+// Bundled libngspice is looked up in the app resources
+pub fn get_app_resource_dir(app_handle: &AppHandle) -> Option<PathBuf> {
+    app_handle.path().resource_dir().ok()
+}
+
+// Thread copies are kept in the app cache
+pub fn get_app_lib_dir(app_handle: &AppHandle) -> Result<PathBuf, SimulatorError> {
+    app_handle
+        .path()
+        .app_cache_dir()
+        .map(|cache_dir| cache_dir.join("ngspice"))
+        .map_err(|_| SimulatorError::FailedToCopySpiceLibrary(LIB_NAME.to_owned()))
+}
+
+// Results of unchanged analyses are kept in the app cache
+pub fn get_app_result_cache_dir(app_handle: &AppHandle) -> Option<PathBuf> {
+    app_handle
+        .path()
+        .app_cache_dir()
+        .map(|cache_dir| cache_dir.join("results"))
+        .ok()
+}
+
+#[tauri::command]
+pub fn get_spice_settings(app_state: tauri::State<AppState>) -> SpiceSettings {
+    let settings_guard = app_state.spice_settings.lock().unwrap();
+    let settings = settings_guard.clone();
+    drop(settings_guard);
+
+    settings
+}
+
+// Checks the selected library can be loaded before keeping it
+#[tauri::command]
+pub fn set_spice_settings(
+    settings: SpiceSettings,
+    app_handle: tauri::AppHandle,
+) -> Result<SpiceLibraryInfo, SimulatorError> {
+    log::info!("Saving spice settings {:?}", settings);
+
+    let source_path = get_source_lib_path(get_app_resource_dir(&app_handle).as_deref(), &settings)?;
+    let library_info = detect_spice_version(&get_app_lib_dir(&app_handle)?, &source_path)?;
+
+    log::info!(
+        "Using ngspice-{} at {}",
+        library_info.version,
+        library_info.path
+    );
+
+    let app_state: tauri::State<AppState> = app_handle.state();
+    let mut settings_guard = app_state.spice_settings.lock().unwrap();
+    *settings_guard = settings.clone();
+    drop(settings_guard);
+
+    if let Ok(app_data_dir) = app_handle.path().app_data_dir() {
+        settings.save(&app_data_dir);
+    }
+
+    Ok(library_info)
+}
+
+#[tauri::command]
+pub fn get_spice_library_info(
+    app_handle: tauri::AppHandle,
+) -> Result<SpiceLibraryInfo, SimulatorError> {
+    let app_state: tauri::State<AppState> = app_handle.state();
+    let settings_guard = app_state.spice_settings.lock().unwrap();
+    let settings = settings_guard.clone();
+    drop(settings_guard);
+
+    let source_path = get_source_lib_path(get_app_resource_dir(&app_handle).as_deref(), &settings)?;
+
+    detect_spice_version(&get_app_lib_dir(&app_handle)?, &source_path)
+}
@@ -0,0 +1,6 @@
+// Runs the analyses of a .gsp project headlessly, see gspice::cli
+use std::{env, process::ExitCode};
+
+fn main() -> ExitCode {
+    gspice::cli::run(env::args().skip(1).collect())
+}
@@ -0,0 +1,22 @@
+// Runs one ngspice instance for the app in worker process mode, see simulator::worker
+use std::{env, ffi::OsString, process::ExitCode};
+
+use gspice::simulator::worker::server::run_worker;
+
+fn main() -> ExitCode {
+    let args: Vec<OsString> = env::args_os().collect();
+
+    let (Some(lib_path), Some(id)) = (args.get(1), args.get(2)) else {
+        eprintln!("Usage: spice_worker <libngspice path> <thread id>");
+        return ExitCode::FAILURE;
+    };
+
+    let Some(id) = id.to_str().and_then(|id| id.parse::<i32>().ok()) else {
+        eprintln!("Invalid thread id {:?}", id);
+        return ExitCode::FAILURE;
+    };
+
+    run_worker(lib_path, id);
+
+    ExitCode::SUCCESS
+}
@@ -0,0 +1,409 @@
+use std::{
+    collections::HashMap,
+    fs,
+    path::{Path, PathBuf},
+    process::ExitCode,
+    sync::{Arc, Mutex},
+};
+
+use crate::{
+    api::{
+        create_bjt_models, get_run_options, Project, ResultSink, SinkClosed, SpiceSettings,
+        WorkerMode,
+    },
+    simulator::{
+        resource_report::{ResourceReport, ResourceReportPayload},
+        simulation_data::{
+            ComputedData, SimulationData, SimulationDataPayload, SimulationSchema,
+            SimulationSchemaPayload,
+        },
+        simulation_result::{SimulationResult, SimulationResultPayload},
+        simulation_status::{SimulationStatus, SimulationStatusPayload},
+        sparameters::expand_sparameter_configs,
+        stability::expand_stability_configs,
+    },
+};
+
+const USAGE: &str = "Usage: gspice_cli <project.gsp> [options]
+
+Options:
+    --out <dir>             Directory results are written to, defaults to ./results
+    --format <csv|json>     Format of the streamed vectors, defaults to csv
+    --lib <path>            libngspice to use instead of the system one
+    --threads <n>           Simulation threads, defaults to one per core
+    --worker-process        Run ngspice in worker processes
+    --max-points <n>        Refuse analyses estimated over n points
+    --bjt-models <file>     Seed file with the BJT models used by the project";
+
+// Project could not be simulated at all, as opposed to a failed simulation
+const SETUP_FAILURE: u8 = 2;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum OutputFormat {
+    Csv,
+    Json,
+}
+
+#[derive(Debug)]
+struct CliOptions {
+    project: PathBuf,
+    out_dir: PathBuf,
+    format: OutputFormat,
+    bjt_models: Option<PathBuf>,
+    settings: SpiceSettings,
+}
+
+impl CliOptions {
+    fn parse(args: Vec<String>) -> Result<CliOptions, String> {
+        let mut args = args.into_iter();
+
+        let mut project: Option<PathBuf> = None;
+        let mut out_dir = PathBuf::from("results");
+        let mut format = OutputFormat::Csv;
+        let mut bjt_models: Option<PathBuf> = None;
+        let mut settings = SpiceSettings::default();
+
+        while let Some(arg) = args.next() {
+            let mut value = |name: &str| {
+                args.next()
+                    .ok_or_else(|| format!("Missing value for {}", name))
+            };
+
+            match arg.as_str() {
+                "--out" => out_dir = PathBuf::from(value("--out")?),
+                "--format" => {
+                    format = match value("--format")?.as_str() {
+                        "csv" => OutputFormat::Csv,
+                        "json" => OutputFormat::Json,
+                        other => return Err(format!("Unknown format {}", other)),
+                    }
+                }
+                "--lib" => settings.library_path = Some(value("--lib")?),
+                "--threads" => {
+                    let threads = value("--threads")?;
+                    settings.pool_size = Some(
+                        threads
+                            .parse()
+                            .map_err(|_| format!("Invalid thread count {}", threads))?,
+                    );
+                }
+                "--worker-process" => settings.worker_mode = WorkerMode::Process,
+                "--max-points" => {
+                    let max_points = value("--max-points")?;
+                    settings.cost_thresholds.max_points = Some(
+                        max_points
+                            .parse()
+                            .map_err(|_| format!("Invalid point count {}", max_points))?,
+                    );
+                }
+                "--bjt-models" => bjt_models = Some(PathBuf::from(value("--bjt-models")?)),
+                "-h" | "--help" => return Err(USAGE.to_owned()),
+
+                other if project.is_none() && !other.starts_with("--") => {
+                    project = Some(PathBuf::from(other))
+                }
+                other => return Err(format!("Unknown argument {}", other)),
+            }
+        }
+
+        Ok(CliOptions {
+            project: project.ok_or_else(|| USAGE.to_owned())?,
+            out_dir,
+            format,
+            bjt_models,
+            settings,
+        })
+    }
+}
+
+// Keeps everything the simulations produce until they are all done
+#[derive(Default)]
+struct CollectingSink {
+    // Last schema of each run, its points carry the values only
+    schemas: Mutex<HashMap<String, SimulationSchema>>,
+    data: Mutex<HashMap<String, Vec<SimulationData>>>,
+    statuses: Mutex<HashMap<String, SimulationStatus>>,
+    results: Mutex<HashMap<String, SimulationResult>>,
+    reports: Mutex<HashMap<String, ResourceReport>>,
+}
+
+impl ResultSink for CollectingSink {
+    fn send_schema(&self, payload: SimulationSchemaPayload) -> Result<(), SinkClosed> {
+        let mut schemas = self.schemas.lock().unwrap();
+        schemas.insert(payload.id, payload.schema);
+
+        Ok(())
+    }
+
+    fn send_data(&self, payload: SimulationDataPayload) -> Result<(), SinkClosed> {
+        let mut data = self.data.lock().unwrap();
+        data.entry(payload.id).or_default().extend(payload.data);
+
+        Ok(())
+    }
+
+    fn send_status(&self, payload: SimulationStatusPayload) -> Result<(), SinkClosed> {
+        if let SimulationStatus::Progress { .. } = payload.status {
+            return Ok(());
+        }
+
+        println!("{}: {:?}", payload.id, payload.status);
+
+        let mut statuses = self.statuses.lock().unwrap();
+        statuses.insert(payload.id, payload.status);
+
+        Ok(())
+    }
+
+    fn send_result(&self, payload: SimulationResultPayload) -> Result<(), SinkClosed> {
+        let mut results = self.results.lock().unwrap();
+        results.insert(payload.id, payload.result);
+
+        Ok(())
+    }
+
+    fn send_report(&self, payload: ResourceReportPayload) -> Result<(), SinkClosed> {
+        println!(
+            "{}: {} points in {} ms",
+            payload.id, payload.report.points, payload.report.run_time
+        );
+
+        let mut reports = self.reports.lock().unwrap();
+        reports.insert(payload.id, payload.report);
+
+        Ok(())
+    }
+}
+
+impl CollectingSink {
+    // Runs of stability and S-parameter analyses report failures under the analysis id
+    fn has_succeeded(&self, run_id: &str) -> bool {
+        let simulation_id = run_id.split('#').next().unwrap_or(run_id);
+        let statuses = self.statuses.lock().unwrap();
+
+        match (statuses.get(run_id), statuses.get(simulation_id)) {
+            (_, Some(SimulationStatus::Failed(_))) => false,
+            (Some(SimulationStatus::Ready), _) => true,
+            _ => false,
+        }
+    }
+}
+
+fn get_file_name(id: &str) -> String {
+    id.chars()
+        .map(|c| match c {
+            'a'..='z' | 'A'..='Z' | '0'..='9' | '-' | '_' => c,
+            _ => '_',
+        })
+        .collect()
+}
+
+// One row per point, complex vectors take a real and an imaginary column
+fn data_to_csv(schema: &SimulationSchema, data: &[SimulationData]) -> String {
+    if data.is_empty() {
+        return String::default();
+    }
+
+    let header: Vec<String> = schema
+        .vectors
+        .iter()
+        .flat_map(|vector| match vector.is_complex {
+            true => vec![
+                format!("{} (re)", vector.name),
+                format!("{} (im)", vector.name),
+            ],
+            false => vec![vector.name.clone()],
+        })
+        .collect();
+
+    let mut csv = header.join(",");
+    csv.push('\n');
+
+    for point in data {
+        let row: Vec<String> = schema
+            .vectors
+            .iter()
+            .zip(point.get_values())
+            .flat_map(|(vector, (c_real, c_imag))| match vector.is_complex {
+                true => vec![c_real.to_string(), c_imag.to_string()],
+                false => vec![c_real.to_string()],
+            })
+            .collect();
+
+        csv.push_str(&row.join(","));
+        csv.push('\n');
+    }
+
+    csv
+}
+
+fn write_outputs(
+    sink: &CollectingSink,
+    out_dir: &Path,
+    format: OutputFormat,
+) -> Result<(), String> {
+    fs::create_dir_all(out_dir).map_err(|error| format!("{}: {}", out_dir.display(), error))?;
+
+    let write = |file_name: String, content: String| {
+        let path = out_dir.join(file_name);
+        fs::write(&path, content).map_err(|error| format!("{}: {}", path.display(), error))
+    };
+
+    let schemas = sink.schemas.lock().unwrap();
+    let data = sink.data.lock().unwrap();
+    let results = sink.results.lock().unwrap();
+    let reports = sink.reports.lock().unwrap();
+
+    match format {
+        OutputFormat::Csv => {
+            for (id, points) in data.iter() {
+                let Some(schema) = schemas.get(id) else {
+                    continue;
+                };
+
+                write(
+                    format!("{}.csv", get_file_name(id)),
+                    data_to_csv(schema, points),
+                )?;
+            }
+        }
+
+        // Points are written with the names and flags of their vectors
+        OutputFormat::Json => {
+            for (id, points) in data.iter() {
+                let Some(schema) = schemas.get(id) else {
+                    continue;
+                };
+
+                let named_points: Vec<Vec<ComputedData>> = points
+                    .iter()
+                    .map(|point| schema.name_values(point))
+                    .collect();
+
+                write(
+                    format!("{}.json", get_file_name(id)),
+                    serde_json::to_string_pretty(&named_points).unwrap(),
+                )?;
+            }
+        }
+    }
+
+    // Computed results are structured, they are always written as JSON
+    for (id, result) in results.iter() {
+        write(
+            format!("{}.result.json", get_file_name(id)),
+            serde_json::to_string_pretty(result).unwrap(),
+        )?;
+    }
+
+    for (id, report) in reports.iter() {
+        write(
+            format!("{}.report.json", get_file_name(id)),
+            serde_json::to_string_pretty(report).unwrap(),
+        )?;
+    }
+
+    Ok(())
+}
+
+fn load_project(options: &CliOptions) -> Result<Project, String> {
+    let bjt_models = create_bjt_models(options.bjt_models.as_deref())
+        .map_err(|error| format!("Failed to create the BJT models database: {:?}", error))?;
+
+    Project::load(&options.project, &bjt_models)
+        .map_err(|_| format!("Failed to parse {}", options.project.display()))
+}
+
+/// Simulates a .gsp project without the app, exits with 1 when a simulation failed
+pub fn run(args: Vec<String>) -> ExitCode {
+    let options = match CliOptions::parse(args) {
+        Ok(options) => options,
+        Err(message) => {
+            eprintln!("{}", message);
+            return ExitCode::from(SETUP_FAILURE);
+        }
+    };
+
+    let mut project = match load_project(&options) {
+        Ok(project) => project,
+        Err(message) => {
+            eprintln!("{}", message);
+            return ExitCode::from(SETUP_FAILURE);
+        }
+    };
+
+    if project.config.is_empty() {
+        eprintln!("{} has no analyses", options.project.display());
+        return ExitCode::from(SETUP_FAILURE);
+    }
+
+    // Nobody is there to resume a transient stopped at a breakpoint
+    for (_, config) in project.config.iter_mut() {
+        config.set_breakpoints(None);
+    }
+
+    let lib_dir = std::env::temp_dir().join("gspice").join("ngspice");
+
+    let mut run_options = match get_run_options(&options.settings, None, lib_dir) {
+        Ok(run_options) => run_options,
+        Err(error) => {
+            eprintln!("Failed to find ngspice: {:?}", error);
+            return ExitCode::from(SETUP_FAILURE);
+        }
+    };
+
+    // Result files get every point, nothing is displayed
+    run_options.display_points_per_second = None;
+
+    let run_ids: Vec<String> =
+        expand_sparameter_configs(expand_stability_configs(project.config.clone()))
+            .into_keys()
+            .collect();
+
+    // Refused analyses are reported as failed by the run itself
+    if let Ok(estimates) = project.estimate(&run_options.cost_thresholds) {
+        for (id, estimate) in estimates.iter() {
+            for warning in estimate.warnings.iter() {
+                eprintln!("{}: {:?}", id, warning);
+            }
+
+            if let (Some(suggestion), false) = (&estimate.suggestion, estimate.warnings.is_empty())
+            {
+                eprintln!(
+                    "{}: suggested tstep {} and tmax {}",
+                    id, suggestion.tstep, suggestion.tmax
+                );
+            }
+        }
+    }
+
+    let sink = Arc::new(CollectingSink::default());
+
+    match project.simulate(run_options, Arc::clone(&sink) as Arc<dyn ResultSink>) {
+        Ok(run) => run.wait(),
+        Err(error) => {
+            eprintln!("Invalid schematic: {:?}", error);
+            return ExitCode::from(SETUP_FAILURE);
+        }
+    }
+
+    if let Err(message) = write_outputs(&sink, &options.out_dir, options.format) {
+        eprintln!("Failed to write results: {}", message);
+        return ExitCode::FAILURE;
+    }
+
+    let failed_ids: Vec<&String> = run_ids
+        .iter()
+        .filter(|run_id| !sink.has_succeeded(run_id))
+        .collect();
+
+    if !failed_ids.is_empty() {
+        for run_id in failed_ids {
+            eprintln!("{} failed", run_id);
+        }
+
+        return ExitCode::FAILURE;
+    }
+
+    ExitCode::SUCCESS
+}
@@ -1,9 +1,7 @@
 use std::{collections::HashMap, fs::File, path::PathBuf};
 
+use native_db::Database;
 use serde::Serialize;
-use tauri::State;
-
-use crate::app_state::AppState;
 
 use super::{
     circuit::canvas::{CanvasEdge, CanvasNode},
@@ -18,7 +16,7 @@ use super::{
 pub trait Engine {
     fn file_to_domain(
         file: File,
-        state: State<AppState>,
+        bjt_models: &Database<'static>,
     ) -> Result<
         (
             Vec<CanvasNode>,
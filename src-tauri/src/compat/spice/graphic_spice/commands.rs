@@ -137,7 +137,9 @@ pub fn open_graphic_spice(app_handle: &tauri::AppHandle) {
 
                 let state = inner_app_handle.state::<AppState>();
 
-                if let Ok((nodes, edges, config)) = GraphicSpice::file_to_domain(file, state) {
+                if let Ok((nodes, edges, config)) =
+                    GraphicSpice::file_to_domain(file, &state.bjt_models)
+                {
                     if inner_app_handle
                         .emit(
                             "open_file",
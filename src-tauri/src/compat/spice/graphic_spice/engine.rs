@@ -5,15 +5,15 @@ use std::{
     io::{BufRead, BufReader, Write},
 };
 
-use tauri::State;
+use native_db::Database;
 
 use crate::{
-    app_state::{models::bjt::get_bjt_model, AppState},
+    app_state::models::bjt::get_bjt_model,
     common::numbers::position::Position,
     compat::{
         circuit::canvas::{CanvasEdge, CanvasNode, NodeData, SmallSignalConfig, TimeDomainConfig},
         engine::Engine,
-        simulation::SimulationConfig,
+        simulation::{SimulationConfig, SimulationLimits},
     },
 };
 
@@ -150,6 +150,16 @@ pub enum Directives {
         id: String,
         config: SimulationConfig,
     },
+
+    SimLimits {
+        id: String,
+        limits: SimulationLimits,
+    },
+
+    SimBreakpoints {
+        id: String,
+        breakpoints: Vec<String>,
+    },
 }
 
 pub struct GraphicSpice {}
@@ -223,6 +233,18 @@ impl GraphicSpice {
                         format!("SIM {} {}\n", id, config.values_to_string()).as_bytes(),
                     )?;
                 }
+
+                Directives::SimLimits { id, limits } => {
+                    writer.write_all(
+                        format!("LIMITS {} {}\n", id, limits.values_to_string()).as_bytes(),
+                    )?;
+                }
+
+                Directives::SimBreakpoints { id, breakpoints } => {
+                    writer.write_all(
+                        format!("BREAKPOINTS {} {}\n", id, breakpoints.join(" ")).as_bytes(),
+                    )?;
+                }
             }
         }
 
@@ -379,10 +401,29 @@ impl GraphicSpice {
         }
 
         for (id, config) in config {
+            let maybe_limits = config.get_limits();
+            let maybe_breakpoints = config
+                .get_breakpoints()
+                .filter(|breakpoints| !breakpoints.is_empty());
+
             directives.push(Directives::SimConfig {
                 id: id.to_owned(),
                 config,
             });
+
+            if let Some(limits) = maybe_limits {
+                directives.push(Directives::SimLimits {
+                    id: id.to_owned(),
+                    limits,
+                });
+            }
+
+            if let Some(breakpoints) = maybe_breakpoints {
+                directives.push(Directives::SimBreakpoints {
+                    id: id.to_owned(),
+                    breakpoints,
+                });
+            }
         }
 
         directives
@@ -485,6 +526,35 @@ impl GraphicSpice {
                         }
                     }
 
+                    limits if line.starts_with("LIMITS") => {
+                        let trimmed = limits.replace("LIMITS ", "");
+
+                        if let Some((id, limits)) = trimmed.split_once(" ") {
+                            directives.push(Directives::SimLimits {
+                                id: id.to_owned(),
+                                limits: SimulationLimits::from_string(limits).map_err(|_| ())?,
+                            });
+                        } else {
+                            return Err(());
+                        }
+                    }
+
+                    breakpoints if line.starts_with("BREAKPOINTS") => {
+                        let trimmed = breakpoints.replace("BREAKPOINTS ", "");
+
+                        if let Some((id, breakpoints)) = trimmed.split_once(" ") {
+                            directives.push(Directives::SimBreakpoints {
+                                id: id.to_owned(),
+                                breakpoints: breakpoints
+                                    .split_whitespace()
+                                    .map(|breakpoint| breakpoint.to_owned())
+                                    .collect(),
+                            });
+                        } else {
+                            return Err(());
+                        }
+                    }
+
                     simconfig if line.starts_with("SIM") => {
                         let trimmed = simconfig.replace("SIM ", "");
 
@@ -499,7 +569,7 @@ impl GraphicSpice {
                             } else if config == "OP" {
                                 let simconfig_directive = Directives::SimConfig {
                                     id: id.to_owned(),
-                                    config: SimulationConfig::Op {},
+                                    config: SimulationConfig::Op { limits: None },
                                 };
 
                                 directives.push(simconfig_directive);
@@ -526,7 +596,7 @@ pub struct NodeMapper {}
 impl NodeMapper {
     fn from_directives(
         directives: Vec<Directives>,
-        state: State<AppState>,
+        bjt_models: &Database<'static>,
     ) -> (
         Vec<CanvasNode>,
         Vec<CanvasEdge>,
@@ -535,8 +605,8 @@ impl NodeMapper {
         let mut nodes = Vec::<CanvasNode>::default();
         let mut edges = Vec::<CanvasEdge>::default();
         let mut config_map = HashMap::<String, SimulationConfig>::default();
-
-        let bjt_models = &state.bjt_models;
+        let mut limits_map = HashMap::<String, SimulationLimits>::default();
+        let mut breakpoints_map = HashMap::<String, Vec<String>>::default();
 
         for directive in directives {
             match directive {
@@ -706,6 +776,27 @@ impl NodeMapper {
                 Directives::SimConfig { id, config } => {
                     config_map.insert(id.to_string(), config);
                 }
+
+                Directives::SimLimits { id, limits } => {
+                    limits_map.insert(id.to_string(), limits);
+                }
+
+                Directives::SimBreakpoints { id, breakpoints } => {
+                    breakpoints_map.insert(id.to_string(), breakpoints);
+                }
+            }
+        }
+
+        // Limits are kept apart from the positional SIM values
+        for (id, limits) in limits_map {
+            if let Some(config) = config_map.get_mut(&id) {
+                config.set_limits(Some(limits));
+            }
+        }
+
+        for (id, breakpoints) in breakpoints_map {
+            if let Some(config) = config_map.get_mut(&id) {
+                config.set_breakpoints(Some(breakpoints));
             }
         }
 
@@ -716,7 +807,7 @@ impl NodeMapper {
 impl Engine for GraphicSpice {
     fn file_to_domain(
         file: std::fs::File,
-        state: tauri::State<crate::app_state::AppState>,
+        bjt_models: &Database<'static>,
     ) -> Result<
         (
             Vec<CanvasNode>,
@@ -727,7 +818,7 @@ impl Engine for GraphicSpice {
     > {
         let directives = GraphicSpice::parse_directives(file)?;
 
-        let (nodes, edges, config) = NodeMapper::from_directives(directives, state);
+        let (nodes, edges, config) = NodeMapper::from_directives(directives, bjt_models);
 
         Ok((nodes, edges, config))
     }
@@ -22,7 +22,8 @@ pub fn open_lt_spice(app_handle: &tauri::AppHandle) {
 
                 let state = inner_app_handle.state::<AppState>();
 
-                if let Ok((nodes, edges, config)) = LTSpice::file_to_domain(file, state) {
+                if let Ok((nodes, edges, config)) = LTSpice::file_to_domain(file, &state.bjt_models)
+                {
                     inner_app_handle
                         .emit(
                             "open_file",
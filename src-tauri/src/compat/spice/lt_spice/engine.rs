@@ -5,7 +5,7 @@ use std::{
 };
 
 use crate::{
-    app_state::{models::bjt::get_bjt_model, AppState},
+    app_state::models::bjt::get_bjt_model,
     common::numbers::position::Position,
     compat::{
         circuit::{
@@ -17,7 +17,7 @@ use crate::{
     },
 };
 
-use tauri::{utils::config::parse, State};
+use native_db::Database;
 use uuid::Uuid;
 
 use super::constants::{
@@ -245,10 +245,8 @@ pub struct NodeMapper {}
 impl NodeMapper {
     fn from_directives(
         directives: Vec<Directives>,
-        state: State<AppState>,
+        bjt_models: &Database<'static>,
     ) -> (Vec<CanvasNode>, Vec<CanvasEdge>) {
-        let bjt_models = &state.bjt_models;
-
         // To avoid creating a double edge
         let mut connection_tracker = ConnectionTracker::new();
 
@@ -1369,7 +1367,7 @@ impl LTSpice {
 impl Engine for LTSpice {
     fn file_to_domain(
         file: File,
-        state: State<AppState>,
+        bjt_models: &Database<'static>,
     ) -> Result<
         (
             Vec<CanvasNode>,
@@ -1382,7 +1380,7 @@ impl Engine for LTSpice {
 
         let directives = LTSpice::parse_directives(file)?;
 
-        let (nodes, edges) = NodeMapper::from_directives(directives, state);
+        let (nodes, edges) = NodeMapper::from_directives(directives, bjt_models);
 
         Ok((nodes, edges, config))
     }
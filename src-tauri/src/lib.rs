@@ -1,4 +1,6 @@
+pub mod api;
 pub mod app_state;
+pub mod cli;
 pub mod common;
 pub mod compat;
 pub mod init;
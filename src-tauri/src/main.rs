@@ -9,7 +9,10 @@ use gspice::compat::spice::{
     lt_spice::commands::open_lt_spice,
 };
 use gspice::{
-    app_state::{instance::InstanceState, models::bjt::DATABASE_BJT_MODELS, AppState},
+    app_state::{
+        instance::InstanceState, models::bjt::DATABASE_BJT_MODELS, settings::SpiceSettings,
+        AppState,
+    },
     init::models::init_models,
 };
 
@@ -127,11 +130,19 @@ fn main() {
                 db
             };
 
+            // ----------------- SETTINGS SETUP -------------------
+
+            let spice_settings = SpiceSettings::load(&app_data_dir);
+
             // ----------------- END SETUP -------------------
 
             app.manage(AppState {
                 bjt_models: bjt_models_db,
                 instance_state: Mutex::new(InstanceState::NotSaved),
+                simulation_orchestrator: Mutex::new(None),
+                simulation_waveforms: Mutex::new(None),
+                simulation_results: Mutex::new(None),
+                spice_settings: Mutex::new(spice_settings),
             });
 
             Ok(())
@@ -262,7 +273,25 @@ fn main() {
         })
         .invoke_handler(tauri::generate_handler![
             gspice::simulator::commands::simulate,
+            gspice::simulator::commands::estimate_simulations,
+            gspice::simulator::commands::get_simulation_range,
+            gspice::simulator::commands::get_result_plots,
+            gspice::simulator::commands::get_result_vector,
+            gspice::simulator::commands::export_results_csv,
+            gspice::simulator::commands::export_results_json,
+            gspice::simulator::commands::clear_result_cache,
+            gspice::simulator::commands::list_runs,
+            gspice::simulator::commands::load_run,
+            gspice::simulator::commands::compare_runs,
+            gspice::simulator::commands::export_touchstone,
+            gspice::simulator::commands::cancel_simulation,
+            gspice::simulator::commands::pause_simulation,
+            gspice::simulator::commands::resume_simulation,
+            gspice::simulator::commands::step_simulation,
             gspice::app_state::models::bjt::load_bjt_models,
+            gspice::app_state::settings::get_spice_settings,
+            gspice::app_state::settings::set_spice_settings,
+            gspice::app_state::settings::get_spice_library_info,
             gspice::app_state::models::bjt::save_bjt_model,
             gspice::compat::commands::parse_bjt_model_directive,
             gspice::compat::spice::graphic_spice::commands::save_graphic_spice_from_domain,
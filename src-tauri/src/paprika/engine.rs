@@ -12,16 +12,17 @@ pub struct SpiceEngine {
     pub init_sync: RawSymbol<NgSpiceInitSync>,
     pub command: RawSymbol<NgSpiceCommand>,
     pub get_vec_info: RawSymbol<NgSpiceVecInfo>,
-    // b"ngCM_Input_Path\0";
-    // b"ngGet_Evt_NodeInfo\0";
-    // b"ngSpice_AllEvtNodes\0";
-    // b"ngSpice_Init_Evt\0";
-    // b"ngSpice_Circ\0";
+    // Only exported by libraries built with XSPICE
+    pub cm_input_path: Option<RawSymbol<NgCmInputPath>>,
+    pub get_evt_node_info: Option<RawSymbol<NgSpiceGetEvtNodeInfo>>,
+    pub get_all_evt_nodes: Option<RawSymbol<NgSpiceAllEvtNodes>>,
+    pub init_evt: Option<RawSymbol<NgSpiceInitEvt>>,
+    pub circ: RawSymbol<NgSpiceCirc>,
     pub get_cur_plot: RawSymbol<NgSpiceCurPlot>,
     pub get_all_plots: RawSymbol<NgSpiceAllPlots>,
     pub get_all_vecs: RawSymbol<NgSpiceAllVecs>,
     pub is_running: RawSymbol<NgSpiceRunning>,
-    // b"ngSpice_SetBkpt\0";
+    pub set_bkpt: RawSymbol<NgSpiceSetBkpt>,
 }
 
 impl SpiceEngine {
@@ -39,26 +40,46 @@ impl SpiceEngine {
         Ok(libloading::Symbol::<T>::into_raw(symbol))
     }
 
+    unsafe fn get_optional_symbol<T>(lib: &Library, symbol_name: &[u8]) -> Option<RawSymbol<T>> {
+        SpiceEngine::get_symbol::<T>(lib, symbol_name).ok()
+    }
+
     // Get symbols in  the same order as they appear in sharedspice.h
     pub unsafe fn new(lib: &Library) -> Result<SpiceEngine, SpiceEngineError> {
         let init = SpiceEngine::get_symbol::<NgSpiceInit>(lib, b"ngSpice_Init\0")?;
         let init_sync = SpiceEngine::get_symbol::<NgSpiceInitSync>(lib, b"ngSpice_Init_Sync\0")?;
         let command = SpiceEngine::get_symbol::<NgSpiceCommand>(lib, b"ngSpice_Command\0")?;
         let get_vec_info = SpiceEngine::get_symbol::<NgSpiceVecInfo>(lib, b"ngGet_Vec_Info\0")?;
+        let cm_input_path =
+            SpiceEngine::get_optional_symbol::<NgCmInputPath>(lib, b"ngCM_Input_Path\0");
+        let get_evt_node_info =
+            SpiceEngine::get_optional_symbol::<NgSpiceGetEvtNodeInfo>(lib, b"ngGet_Evt_NodeInfo\0");
+        let get_all_evt_nodes =
+            SpiceEngine::get_optional_symbol::<NgSpiceAllEvtNodes>(lib, b"ngSpice_AllEvtNodes\0");
+        let init_evt =
+            SpiceEngine::get_optional_symbol::<NgSpiceInitEvt>(lib, b"ngSpice_Init_Evt\0");
+        let circ = SpiceEngine::get_symbol::<NgSpiceCirc>(lib, b"ngSpice_Circ\0")?;
         let get_cur_plot = SpiceEngine::get_symbol::<NgSpiceCurPlot>(lib, b"ngSpice_CurPlot\0")?;
         let get_all_plots = SpiceEngine::get_symbol::<NgSpiceAllPlots>(lib, b"ngSpice_AllPlots\0")?;
         let get_all_vecs = SpiceEngine::get_symbol::<NgSpiceAllVecs>(lib, b"ngSpice_AllVecs\0")?;
         let is_running = SpiceEngine::get_symbol::<NgSpiceRunning>(lib, b"ngSpice_running\0")?;
+        let set_bkpt = SpiceEngine::get_symbol::<NgSpiceSetBkpt>(lib, b"ngSpice_SetBkpt\0")?;
 
         Ok(SpiceEngine {
             init,
             init_sync,
             command,
             get_vec_info,
+            cm_input_path,
+            get_evt_node_info,
+            get_all_evt_nodes,
+            init_evt,
+            circ,
             get_cur_plot,
             get_all_plots,
             get_all_vecs,
             is_running,
+            set_bkpt,
         })
     }
 }
@@ -41,29 +41,28 @@ pub type NgSpiceInit = extern "C" fn(
     *const c_void,
 ) -> c_int;
 
+// GetSyncData: time, delta to change, old delta, redostep, id, location and user data
+pub type NgGetSyncData = unsafe extern "C" fn(
+    c_double,
+    *mut c_double,
+    c_double,
+    c_int,
+    c_int,
+    c_int,
+    *const c_void,
+) -> c_int;
+
 pub type NgSpiceInitSync = extern "C" fn(
-    // GetVSRCData
+    // GetVSRCData, the value is written to the first pointer
     Option<
-        unsafe extern "C" fn(
-            *const c_double,
-            c_double,
-            *const c_char,
-            c_int,
-            *const c_void,
-        ) -> c_int,
+        unsafe extern "C" fn(*mut c_double, c_double, *const c_char, c_int, *const c_void) -> c_int,
     >,
-    // GetISRCData
+    // GetISRCData, the value is written to the first pointer
     Option<
-        unsafe extern "C" fn(
-            *const c_double,
-            c_double,
-            *const c_char,
-            c_int,
-            *const c_void,
-        ) -> c_int,
+        unsafe extern "C" fn(*mut c_double, c_double, *const c_char, c_int, *const c_void) -> c_int,
     >,
     // GetSyncData
-    Option<unsafe extern "C" fn(c_double, *const c_double, c_double, c_int, *const c_void) -> c_int>,
+    Option<NgGetSyncData>,
 
     *const c_int,
 
@@ -83,3 +82,52 @@ pub type NgSpiceAllPlots = extern "C" fn() -> *const *const c_char;
 pub type NgSpiceAllVecs = extern "C" fn(*const c_char) -> *const *const c_char;
 
 pub type NgSpiceRunning = extern "C" fn() -> bool;
+
+// Takes a NULL terminated array of netlist lines, the last one being .end
+// Returns 1 if the circuit could not be loaded
+pub type NgSpiceCirc = extern "C" fn(*const *const c_char) -> c_int;
+
+// Stops the transient at the given time, returns false if no circuit is loaded
+pub type NgSpiceSetBkpt = extern "C" fn(c_double) -> bool;
+
+/*
+Event nodes and their callbacks only exist when ngspice is built with XSPICE
+*/
+pub type NgCmInputPath = extern "C" fn(*const c_char) -> c_int;
+
+pub type NgSpiceGetEvtNodeInfo = extern "C" fn(*const c_char) -> *const ng_types::NgEvtSharedData;
+
+pub type NgSpiceAllEvtNodes = extern "C" fn() -> *const *const c_char;
+
+pub type NgSpiceInitEvt = extern "C" fn(
+    // SendEvtData
+    // Callback function for sending the new value of an event node
+    // (node index, step, value, string value, struct value, struct size, mode, id, user)
+    Option<
+        unsafe extern "C" fn(
+            c_int,
+            c_double,
+            c_double,
+            *const c_char,
+            *const c_void,
+            c_int,
+            c_int,
+            c_int,
+            *const c_void,
+        ) -> c_int,
+    >,
+    // SendInitEvtData
+    // Callback function for sending every event node before the simulation starts
+    // (node index, max node index, node name, node type, id, user)
+    Option<
+        unsafe extern "C" fn(
+            c_int,
+            c_int,
+            *const c_char,
+            *const c_char,
+            c_int,
+            *const c_void,
+        ) -> c_int,
+    >,
+    *const c_void,
+) -> c_int;
@@ -5,16 +5,39 @@ use std::ffi::CStr;
 #[derive(Copy, Clone, Debug)]
 #[repr(C)]
 pub struct NgEvtData {
-    dcop: c_double,
-    step: c_int,
+    dcop: c_int,
+    step: c_double,
     node_value: *const c_char,
 }
+impl NgEvtData {
+    pub unsafe fn to_pk(self) -> PkEvtData {
+        PkEvtData {
+            dcop: self.dcop != 0,
+            step: self.step,
+            node_value: CStr::from_ptr(self.node_value)
+                .to_str()
+                .unwrap()
+                .to_string(),
+        }
+    }
+}
 #[derive(Copy, Clone, Debug)]
 #[repr(C)]
 pub struct NgEvtSharedData {
-    evt_dect: *const NgEvtData,
+    evt_dect: *const *const NgEvtData,
     num_steps: c_int,
 }
+impl NgEvtSharedData {
+    pub unsafe fn to_pk(self) -> PkEvtSharedData {
+        let evt_slice = std::slice::from_raw_parts(self.evt_dect, self.num_steps as usize);
+        // create native PkEvtData for every step
+        let mut steps = Vec::<PkEvtData>::with_capacity(self.num_steps as usize);
+        for item in evt_slice.iter() {
+            steps.push((*(*item)).to_pk());
+        }
+        PkEvtSharedData { steps }
+    }
+}
 #[derive(Copy, Clone, Debug)]
 #[repr(C)]
 pub struct NgComplex {
@@ -33,8 +56,10 @@ pub struct NgVectorinfo {
 }
 impl NgVectorinfo {
     pub unsafe fn to_pk(self) -> PkVectorinfo {
-        let (real, comp) = match self.v_type {
-            1 => {
+        // v_type is the kind of vector, like time or voltage, ngspice only
+        // sets the data pointer of the kind of values it holds
+        let (real, comp) = match (self.v_realdata.is_null(), self.v_compdata.is_null()) {
+            (false, _) => {
                 // real
                 let cvec = std::slice::from_raw_parts(self.v_realdata, self.v_length as usize);
                 // create vec containing 'count' number of PkVecvalues
@@ -46,7 +71,7 @@ impl NgVectorinfo {
                 }
                 (Some(vec), None)
             } // real
-            2 => {
+            (true, false) => {
                 // complex
                 let cvec = std::slice::from_raw_parts(self.v_compdata, self.v_length as usize);
                 // create vec containing 'count' number of PkVecvalues
@@ -216,12 +241,25 @@ pub struct PkVecinfo {
     pub pdvec: usize,
     pub pdvecscale: usize,
 }
+#[derive(Clone, Debug)]
+/// Struct known as evt_data in Ngspice User's Manual
+pub struct PkEvtData {
+    pub dcop: bool,
+    pub step: f64,
+    pub node_value: String,
+}
+#[derive(Clone, Debug)]
+/// Struct known as evt_shared_data in Ngspice User's Manual
+pub struct PkEvtSharedData {
+    pub steps: Vec<PkEvtData>,
+}
 #[derive(Copy, Clone, Debug)]
 pub struct PkComplex {
     pub real: f64,
     pub imag: f64,
 }
-#[derive(Clone, Debug)]
+// Sent as is by the worker processes
+#[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
 pub struct PkVectorinfo {
     pub name: String,
     pub stype: i32,
@@ -230,3 +268,28 @@ pub struct PkVectorinfo {
     pub compdata: Option<Vec<Complex64>>,
     pub length: i32,
 }
+// Vectors as ngspice hands them over, for the tests of the modules that store them
+#[cfg(test)]
+impl PkVectorinfo {
+    pub fn from_real(name: &str, values: Vec<f64>) -> PkVectorinfo {
+        PkVectorinfo {
+            name: name.to_owned(),
+            stype: 0,
+            flag: 0,
+            length: values.len() as i32,
+            realdata: Some(values),
+            compdata: None,
+        }
+    }
+
+    pub fn from_complex(name: &str, values: Vec<Complex64>) -> PkVectorinfo {
+        PkVectorinfo {
+            name: name.to_owned(),
+            stype: 0,
+            flag: 0,
+            length: values.len() as i32,
+            realdata: None,
+            compdata: Some(values),
+        }
+    }
+}
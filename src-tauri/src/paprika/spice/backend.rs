@@ -0,0 +1,51 @@
+use super::{manager::SpiceManager, ngspice::types::PkVectorinfo, spice::Spice};
+
+/// What a simulation thread needs from ngspice. Implemented by the shared library
+/// through `Spice` and by `FakeSpice`, which replays canned callbacks
+pub trait SpiceBackend {
+    /// Returns true when ngspice reports an error
+    fn command(&self, cmdstr: &str) -> bool;
+    /// Loads the netlist in one go, returns true when ngspice reports an error
+    fn circ(&self, lines: &[&str]) -> bool;
+    /// Stops the transient at the given time, returns false without a loaded circuit
+    fn set_breakpoint(&self, time: f64) -> bool;
+    fn get_vec_info(&self, vecname: &str) -> Option<PkVectorinfo>;
+    fn get_cur_plot(&self) -> String;
+    fn get_all_plots(&self) -> Vec<String>;
+    fn get_all_vecs(&self, plotname: &str) -> Vec<String>;
+    fn is_running(&self) -> bool;
+}
+
+impl<Manager: SpiceManager> SpiceBackend for Spice<Manager> {
+    fn command(&self, cmdstr: &str) -> bool {
+        Spice::command(self, cmdstr)
+    }
+
+    fn circ(&self, lines: &[&str]) -> bool {
+        Spice::circ(self, lines)
+    }
+
+    fn set_breakpoint(&self, time: f64) -> bool {
+        Spice::set_breakpoint(self, time)
+    }
+
+    fn get_vec_info(&self, vecname: &str) -> Option<PkVectorinfo> {
+        Spice::get_vec_info(self, vecname)
+    }
+
+    fn get_cur_plot(&self) -> String {
+        Spice::get_cur_plot(self)
+    }
+
+    fn get_all_plots(&self) -> Vec<String> {
+        Spice::get_all_plots(self)
+    }
+
+    fn get_all_vecs(&self, plotname: &str) -> Vec<String> {
+        Spice::get_all_vecs(self, plotname)
+    }
+
+    fn is_running(&self) -> bool {
+        Spice::is_running(self)
+    }
+}
@@ -85,3 +85,108 @@ pub unsafe extern "C" fn cbw_bgthread_running<Manager: SpiceManager>(
     }
     0
 }
+pub unsafe extern "C" fn cbw_send_evt_data<Manager: SpiceManager>(
+    node_index: c_int,
+    step: c_double,
+    dvalue: c_double,
+    svalue: *const c_char,
+    _pcvalue: *const c_void,
+    _plen: c_int,
+    mode: c_int,
+    id: c_int,
+    user: *const c_void,
+) -> c_int {
+    unsafe {
+        <Manager>::cb_send_evt_data(
+            &mut *(user as *mut Manager),
+            node_index,
+            step,
+            dvalue,
+            std::ffi::CStr::from_ptr(svalue)
+                .to_str()
+                .unwrap()
+                .to_owned(),
+            mode,
+            id,
+        );
+    }
+    0
+}
+pub unsafe extern "C" fn cbw_send_init_evt_data<Manager: SpiceManager>(
+    node_index: c_int,
+    max_index: c_int,
+    name: *const c_char,
+    node_type: *const c_char,
+    id: c_int,
+    user: *const c_void,
+) -> c_int {
+    unsafe {
+        <Manager>::cb_send_init_evt_data(
+            &mut *(user as *mut Manager),
+            node_index,
+            max_index,
+            std::ffi::CStr::from_ptr(name).to_str().unwrap().to_owned(),
+            std::ffi::CStr::from_ptr(node_type)
+                .to_str()
+                .unwrap()
+                .to_owned(),
+            id,
+        );
+    }
+    0
+}
+pub unsafe extern "C" fn cbw_get_vsrc_data<Manager: SpiceManager>(
+    value: *mut c_double,
+    time: c_double,
+    name: *const c_char,
+    id: c_int,
+    user: *const c_void,
+) -> c_int {
+    unsafe {
+        *value = <Manager>::cb_get_vsrc_data(
+            &mut *(user as *mut Manager),
+            time,
+            std::ffi::CStr::from_ptr(name).to_str().unwrap().to_owned(),
+            id,
+        );
+    }
+    0
+}
+pub unsafe extern "C" fn cbw_get_isrc_data<Manager: SpiceManager>(
+    value: *mut c_double,
+    time: c_double,
+    name: *const c_char,
+    id: c_int,
+    user: *const c_void,
+) -> c_int {
+    unsafe {
+        *value = <Manager>::cb_get_isrc_data(
+            &mut *(user as *mut Manager),
+            time,
+            std::ffi::CStr::from_ptr(name).to_str().unwrap().to_owned(),
+            id,
+        );
+    }
+    0
+}
+pub unsafe extern "C" fn cbw_get_sync_data<Manager: SpiceManager>(
+    time: c_double,
+    delta: *mut c_double,
+    old_delta: c_double,
+    redo_step: c_int,
+    id: c_int,
+    location: c_int,
+    user: *const c_void,
+) -> c_int {
+    unsafe {
+        <Manager>::cb_get_sync_data(
+            &mut *(user as *mut Manager),
+            time,
+            &mut *delta,
+            old_delta,
+            redo_step != 0,
+            location,
+            id,
+        )
+    }
+}
@@ -0,0 +1,308 @@
+use std::{
+    sync::{Arc, Mutex},
+    thread,
+    time::Duration,
+};
+
+use super::{backend::SpiceBackend, manager::SpiceManager, ngspice::types::*};
+
+/// A callback ngspice would make, replayed by `FakeSpice` in order
+#[derive(Debug, Clone)]
+pub enum FakeEvent {
+    SendChar(String),
+    SendStat(String),
+    SendInitData(PkVecinfoall),
+    SendData(PkVecvaluesall),
+    ControlledExit {
+        status: i32,
+        is_immediate: bool,
+        is_quit: bool,
+    },
+    // Leaves room for bg_halt to come in between two callbacks
+    Wait(Duration),
+}
+
+/// Plot left behind once the replay is done, read back like ngspice's vectors
+#[derive(Debug, Clone)]
+pub struct FakePlot {
+    pub name: String,
+    pub vectors: Vec<PkVectorinfo>,
+}
+
+/// What a `FakeSpice` does on every bg_run, the last plot is the current one
+#[derive(Debug, Clone, Default)]
+pub struct FakeScript {
+    pub events: Vec<FakeEvent>,
+    pub plots: Vec<FakePlot>,
+    // Printed to stderr when a circuit is loaded, which then fails
+    pub circuit_errors: Vec<String>,
+}
+
+#[derive(Default)]
+struct FakeState {
+    // Next event to replay, kept across bg_halt so bg_resume carries on
+    position: usize,
+    is_running: bool,
+    halt_requested: bool,
+    commands: Vec<String>,
+    circuits: Vec<Vec<String>>,
+    // Like ngspice, the replay stops once a data point gets to one of them
+    breakpoints: Vec<f64>,
+    replay_handle: Option<thread::JoinHandle<()>>,
+}
+
+/// In-process stand-in for ngspice, calls the manager back the way the shared library
+/// does without loading it. Commands are only recorded, bg_run, bg_halt and bg_resume
+/// drive the replay of the script on a background thread
+pub struct FakeSpice<Manager: SpiceManager + Send + 'static> {
+    manager: Arc<Mutex<Manager>>,
+    id: i32,
+    script: Arc<FakeScript>,
+    state: Arc<Mutex<FakeState>>,
+}
+
+impl<Manager: SpiceManager + Send + 'static> FakeSpice<Manager> {
+    pub fn init(script: FakeScript, manager: Manager, id: i32) -> FakeSpice<Manager> {
+        FakeSpice {
+            manager: Arc::new(Mutex::new(manager)),
+            id,
+            script: Arc::new(script),
+            state: Arc::new(Mutex::new(FakeState::default())),
+        }
+    }
+
+    /// Every command received so far, in order
+    pub fn get_commands(&self) -> Vec<String> {
+        self.state.lock().unwrap().commands.clone()
+    }
+
+    /// Every netlist loaded so far, in order
+    pub fn get_circuits(&self) -> Vec<Vec<String>> {
+        self.state.lock().unwrap().circuits.clone()
+    }
+
+    fn replay(
+        manager: Arc<Mutex<Manager>>,
+        id: i32,
+        script: Arc<FakeScript>,
+        state: Arc<Mutex<FakeState>>,
+    ) {
+        manager.lock().unwrap().cb_bgt_state(false, id);
+
+        loop {
+            let mut state_guard = state.lock().unwrap();
+
+            if state_guard.halt_requested {
+                state_guard.halt_requested = false;
+                state_guard.is_running = false;
+                drop(state_guard);
+                break;
+            }
+
+            let Some(event) = script.events.get(state_guard.position).cloned() else {
+                state_guard.position = 0;
+                state_guard.is_running = false;
+                drop(state_guard);
+                break;
+            };
+
+            state_guard.position += 1;
+            drop(state_guard);
+
+            // Time of the point about to be sent, to check it against the breakpoints
+            let maybe_time = match &event {
+                FakeEvent::SendData(pkvecvaluesall) => pkvecvaluesall
+                    .vecsa
+                    .iter()
+                    .find(|vecvalues| vecvalues.is_scale)
+                    .map(|vecvalues| vecvalues.creal),
+                _ => None,
+            };
+
+            let mut manager_guard = manager.lock().unwrap();
+
+            match event {
+                FakeEvent::SendChar(msg) => manager_guard.cb_send_char(msg, id),
+                FakeEvent::SendStat(msg) => manager_guard.cb_send_stat(msg, id),
+                FakeEvent::SendInitData(pkvecinfoall) => {
+                    manager_guard.cb_send_init(pkvecinfoall, id)
+                }
+                FakeEvent::SendData(pkvecvaluesall) => {
+                    manager_guard.cb_send_data(pkvecvaluesall, 1, id)
+                }
+                FakeEvent::ControlledExit {
+                    status,
+                    is_immediate,
+                    is_quit,
+                } => manager_guard.cb_ctrldexit(status, is_immediate, is_quit, id),
+                FakeEvent::Wait(duration) => {
+                    drop(manager_guard);
+                    thread::sleep(duration);
+                    continue;
+                }
+            }
+
+            drop(manager_guard);
+
+            if let Some(time) = maybe_time {
+                let mut state_guard = state.lock().unwrap();
+                let breakpoint_count = state_guard.breakpoints.len();
+                state_guard
+                    .breakpoints
+                    .retain(|breakpoint| *breakpoint > time);
+
+                if state_guard.breakpoints.len() < breakpoint_count {
+                    state_guard.is_running = false;
+                    drop(state_guard);
+                    break;
+                }
+
+                drop(state_guard);
+            }
+        }
+
+        manager.lock().unwrap().cb_bgt_state(true, id);
+    }
+
+    fn start_replay(&self, from_start: bool) {
+        let mut state_guard = self.state.lock().unwrap();
+
+        if state_guard.is_running {
+            drop(state_guard);
+            return;
+        }
+
+        if from_start {
+            state_guard.position = 0;
+        }
+
+        state_guard.is_running = true;
+
+        let manager = Arc::clone(&self.manager);
+        let script = Arc::clone(&self.script);
+        let state = Arc::clone(&self.state);
+        let id = self.id;
+
+        state_guard.replay_handle = Some(thread::spawn(move || {
+            FakeSpice::replay(manager, id, script, state)
+        }));
+        drop(state_guard);
+    }
+
+    // Like ngspice, only returns once the background thread stopped
+    fn halt_replay(&self) {
+        let mut state_guard = self.state.lock().unwrap();
+
+        if state_guard.is_running {
+            state_guard.halt_requested = true;
+        }
+
+        let maybe_handle = state_guard.replay_handle.take();
+        drop(state_guard);
+
+        if let Some(handle) = maybe_handle {
+            if let Err(_) = handle.join() {
+                log::error!("Fake spice {}: replay thread panicked", self.id);
+            }
+        }
+    }
+}
+
+impl<Manager: SpiceManager + Send + 'static> SpiceBackend for FakeSpice<Manager> {
+    fn command(&self, cmdstr: &str) -> bool {
+        let mut state_guard = self.state.lock().unwrap();
+        state_guard.commands.push(cmdstr.to_owned());
+        drop(state_guard);
+
+        match cmdstr {
+            "bg_run" => self.start_replay(true),
+            "bg_resume" => self.start_replay(false),
+            "bg_halt" => self.halt_replay(),
+            _ => {}
+        }
+
+        false
+    }
+
+    fn circ(&self, lines: &[&str]) -> bool {
+        let mut state_guard = self.state.lock().unwrap();
+        state_guard
+            .circuits
+            .push(lines.iter().map(|line| line.to_string()).collect());
+        drop(state_guard);
+
+        let mut manager_guard = self.manager.lock().unwrap();
+        for error in self.script.circuit_errors.iter() {
+            manager_guard.cb_send_char(format!("stderr {}", error), self.id);
+        }
+        drop(manager_guard);
+
+        !self.script.circuit_errors.is_empty()
+    }
+
+    fn set_breakpoint(&self, time: f64) -> bool {
+        let mut state_guard = self.state.lock().unwrap();
+        state_guard.breakpoints.push(time);
+        drop(state_guard);
+
+        true
+    }
+
+    fn get_vec_info(&self, vecname: &str) -> Option<PkVectorinfo> {
+        // Vectors can be asked for as plot.vector or relative to the current plot
+        let (plot_name, vector_name) = match vecname.split_once('.') {
+            Some((plot_name, vector_name)) => (plot_name.to_owned(), vector_name),
+            None => (self.get_cur_plot(), vecname),
+        };
+
+        self.script
+            .plots
+            .iter()
+            .find(|plot| plot.name == plot_name)?
+            .vectors
+            .iter()
+            .find(|vector| vector.name == vector_name)
+            .cloned()
+    }
+
+    fn get_cur_plot(&self) -> String {
+        self.script
+            .plots
+            .last()
+            .map(|plot| plot.name.clone())
+            .unwrap_or_else(|| "const".to_owned())
+    }
+
+    fn get_all_plots(&self) -> Vec<String> {
+        self.script
+            .plots
+            .iter()
+            .rev()
+            .map(|plot| plot.name.clone())
+            .collect()
+    }
+
+    fn get_all_vecs(&self, plotname: &str) -> Vec<String> {
+        self.script
+            .plots
+            .iter()
+            .find(|plot| plot.name == plotname)
+            .map(|plot| {
+                plot.vectors
+                    .iter()
+                    .map(|vector| vector.name.clone())
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    fn is_running(&self) -> bool {
+        self.state.lock().unwrap().is_running
+    }
+}
+
+impl<Manager: SpiceManager + Send + 'static> Drop for FakeSpice<Manager> {
+    fn drop(&mut self) {
+        self.halt_replay();
+    }
+}
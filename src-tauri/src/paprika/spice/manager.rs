@@ -13,4 +13,53 @@ pub trait SpiceManager {
     fn cb_send_init(&mut self, pkvecinfoall: PkVecinfoall, id: i32);
     /// Callback known as BGThreadRunning in Ngspice User's Manual
     fn cb_bgt_state(&mut self, is_fin: bool, id: i32);
+    /// Callback known as SendEvtData in Ngspice User's Manual, XSPICE only
+    fn cb_send_evt_data(
+        &mut self,
+        _node_index: i32,
+        _step: f64,
+        _value: f64,
+        _svalue: String,
+        _mode: i32,
+        _id: i32,
+    ) {
+    }
+    /// Callback known as SendInitEvtData in Ngspice User's Manual, XSPICE only
+    fn cb_send_init_evt_data(
+        &mut self,
+        _node_index: i32,
+        _max_index: i32,
+        _name: String,
+        _node_type: String,
+        _id: i32,
+    ) {
+    }
+    /// Callback known as GetVSRCData in Ngspice User's Manual, asked at every time point
+    /// for the value of a voltage source declared `external`
+    fn cb_get_vsrc_data(&mut self, _time: f64, _name: String, _id: i32) -> f64 {
+        0.0
+    }
+    /// Callback known as GetISRCData in Ngspice User's Manual, asked at every time point
+    /// for the value of a current source declared `external`
+    fn cb_get_isrc_data(&mut self, _time: f64, _name: String, _id: i32) -> f64 {
+        0.0
+    }
+    /// Callback known as GetSyncData in Ngspice User's Manual, may shorten the next
+    /// transient step through delta. Only registered when `is_synchronized` is true
+    fn cb_get_sync_data(
+        &mut self,
+        _time: f64,
+        _delta: &mut f64,
+        _old_delta: f64,
+        _redo_step: bool,
+        _location: i32,
+        _id: i32,
+    ) -> i32 {
+        0
+    }
+    /// Registering GetSyncData replaces ngspice's own timestep control, so it is
+    /// left out unless the manager asks for it
+    fn is_synchronized(&self) -> bool {
+        false
+    }
 }
@@ -1,4 +1,6 @@
+pub mod backend;
 pub mod callbacks;
+pub mod fake;
 pub mod manager;
 pub mod spice;
 
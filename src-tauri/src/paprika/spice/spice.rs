@@ -2,7 +2,7 @@ use super::{
     callbacks,
     engine::{SpiceEngine, SpiceEngineError},
     manager::SpiceManager,
-    ngspice::types::*,
+    ngspice::{commands::NgGetSyncData, types::*},
     utils::string::c_strings_to_vec_string,
 };
 use libc::*;
@@ -53,14 +53,27 @@ impl<Manager: SpiceManager> Spice<Manager> {
                 &*thread_safe_manager as *const _ as *const c_void,
             );
 
+            let sync_callback: Option<NgGetSyncData> = match thread_safe_manager.is_synchronized() {
+                true => Some(callbacks::cbw_get_sync_data::<Manager>),
+                false => None,
+            };
+
             (engine.init_sync)(
-                None,
-                None,
-                None,
+                Some(callbacks::cbw_get_vsrc_data::<Manager>),
+                Some(callbacks::cbw_get_isrc_data::<Manager>),
+                sync_callback,
                 &*thread_safe_id as *const _ as *const c_int,
                 &*thread_safe_manager as *const _ as *const c_void,
             );
 
+            if let Some(init_evt) = &engine.init_evt {
+                (init_evt)(
+                    Some(callbacks::cbw_send_evt_data::<Manager>),
+                    Some(callbacks::cbw_send_init_evt_data::<Manager>),
+                    &*thread_safe_manager as *const _ as *const c_void,
+                );
+            }
+
             Ok((
                 Spice {
                     engine,
@@ -82,16 +95,88 @@ impl<Manager: SpiceManager> Spice<Manager> {
         ret != 0
     }
 
+    // Loads the whole netlist at once, returns true if ngspice reports an error
+    pub fn circ(&self, lines: &[&str]) -> bool {
+        let clines: Vec<std::ffi::CString> = lines
+            .iter()
+            .map(|line| std::ffi::CString::new(*line).unwrap())
+            .collect();
+
+        // ngspice reads lines until the NULL pointer
+        let mut pclines: Vec<*const c_char> = clines.iter().map(|line| line.as_ptr()).collect();
+        pclines.push(std::ptr::null());
+
+        let ret: i32 = (self.engine.circ)(pclines.as_ptr());
+
+        ret != 0
+    }
+
+    // Returns false if no circuit is loaded
+    pub fn set_breakpoint(&self, time: f64) -> bool {
+        (self.engine.set_bkpt)(time)
+    }
+
+    // Returns true if ngspice reports an error, or the library has no XSPICE
+    pub fn cm_input_path(&self, path: &str) -> bool {
+        let Some(cm_input_path) = &self.engine.cm_input_path else {
+            return true;
+        };
+
+        let cpath = std::ffi::CString::new(path).unwrap();
+        let ret: i32 = (cm_input_path)(cpath.as_ptr());
+
+        ret != 0
+    }
+
+    pub fn get_evt_node_info(&self, nodename: &str) -> Option<PkEvtSharedData> {
+        let get_evt_node_info = self.engine.get_evt_node_info.as_ref()?;
+
+        unsafe {
+            let cnodename = std::ffi::CString::new(nodename).unwrap();
+            let pevtshareddata = (get_evt_node_info)(cnodename.as_ptr());
+
+            // Returns NULL if the event node is not found
+            if pevtshareddata.is_null() {
+                return None;
+            }
+
+            Some((*pevtshareddata).to_pk())
+        }
+    }
+
+    pub fn get_all_evt_nodes(&self) -> Vec<String> {
+        let Some(get_all_evt_nodes) = &self.engine.get_all_evt_nodes else {
+            return Vec::default();
+        };
+
+        unsafe {
+            let ppcstr = (get_all_evt_nodes)();
+
+            // Returns NULL without a circuit
+            if ppcstr.is_null() {
+                return Vec::default();
+            }
+
+            c_strings_to_vec_string(ppcstr)
+        }
+    }
+
     pub fn clear_control_structures(&self) -> bool {
         let result = (self.engine.command)(std::ptr::null());
         result != 0
     }
 
-    pub fn get_vec_info(&self, vecname: &str) -> PkVectorinfo {
+    pub fn get_vec_info(&self, vecname: &str) -> Option<PkVectorinfo> {
         unsafe {
             let cvecname = std::ffi::CString::new(vecname).unwrap();
             let pvectorinfo = (self.engine.get_vec_info)(cvecname.as_ptr());
-            (*pvectorinfo).to_pk()
+
+            // Returns NULL if the vector is not found in the current plot
+            if pvectorinfo.is_null() {
+                return None;
+            }
+
+            Some((*pvectorinfo).to_pk())
         }
     }
 
@@ -112,9 +197,10 @@ impl<Manager: SpiceManager> Spice<Manager> {
         }
     }
 
-    pub fn get_all_vecs(&self) -> Vec<String> {
+    pub fn get_all_vecs(&self, plotname: &str) -> Vec<String> {
         unsafe {
-            let ppcstr = (self.engine.get_all_plots)();
+            let cplotname = std::ffi::CString::new(plotname).unwrap();
+            let ppcstr = (self.engine.get_all_vecs)(cplotname.as_ptr());
             c_strings_to_vec_string(ppcstr)
         }
     }
@@ -54,6 +54,10 @@ pub enum TimeDomainConfig {
         carrier_frequency: Option<String>,
         delay: Option<String>,
     },
+    // Asked to the external sources of the run at every time point, value is the DC one
+    External {
+        value: Option<String>,
+    },
 }
 
 impl TimeDomainConfig {
@@ -101,6 +105,16 @@ impl TimeDomainConfig {
                 formatted
             }
 
+            TimeDomainConfig::External { value } => {
+                let mut formatted = format!("EXTERNAL");
+
+                if let Some(value) = value {
+                    formatted.push_str(&format!(" {}", value));
+                }
+
+                formatted
+            }
+
             TimeDomainConfig::Exp {
                 initial_value,
                 final_value,
@@ -344,6 +358,15 @@ impl TimeDomainConfig {
                 })
             }
 
+            "external" => {
+                let mut parts = value.split_whitespace();
+                let value = parts.next();
+
+                Some(TimeDomainConfig::External {
+                    value: value.map(|s| s.to_string()),
+                })
+            }
+
             _ => None,
         }
     }
@@ -353,11 +376,10 @@ impl TimeDomainConfig {
             return None;
         }
 
-        if let Some((kind, attributes)) = value.split_once(" ") {
-            return TimeDomainConfig::from_space_separated_string(&kind, &attributes);
-        }
+        // Sources without a value are written as their kind alone
+        let (kind, attributes) = value.split_once(" ").unwrap_or((value, ""));
 
-        None
+        TimeDomainConfig::from_space_separated_string(&kind, &attributes)
     }
 
     pub fn from_asc_value_string(value: &str) -> Option<TimeDomainConfig> {
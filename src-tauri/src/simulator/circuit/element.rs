@@ -58,6 +58,10 @@ pub enum TimeDomainConfig {
         carrier_frequency: Option<Unit>,
         delay: Option<Unit>,
     },
+    // Value asked to the external sources of the run, DC value for the operating point
+    External {
+        value: Option<Unit>,
+    },
 }
 
 impl TimeDomainConfig {
@@ -76,6 +80,15 @@ impl TimeDomainConfig {
                 Ok(TimeDomainConfig::Dc { value: None })
             }
 
+            CanvasTimeDomainConfig::External { value } => {
+                let value = value
+                    .map(|value| Unit::from(value))
+                    .transpose()
+                    .map_err(|error| SimulatorError::UnitError(error))?;
+
+                Ok(TimeDomainConfig::External { value })
+            }
+
             CanvasTimeDomainConfig::Pulse {
                 initial_value,
                 final_value,
@@ -557,6 +570,13 @@ impl TimeDomainConfig {
 
                 formatted
             }
+            TimeDomainConfig::External { value } => {
+                let value = value
+                    .as_ref()
+                    .map_or("0".to_owned(), |value| value.format());
+
+                format!("DC({}) EXTERNAL", value)
+            }
             TimeDomainConfig::Pulse {
                 initial_value,
                 final_value,
@@ -751,6 +771,10 @@ pub struct SmallSignalConfig {
 }
 
 impl SmallSignalConfig {
+    pub fn new(amplitude: Unit, phase: Option<Unit>) -> SmallSignalConfig {
+        SmallSignalConfig { amplitude, phase }
+    }
+
     pub fn from_canvas(
         small_signal_config: CanvasSmallSignalConfig,
     ) -> Result<SmallSignalConfig, SimulatorError> {
@@ -1262,6 +1286,102 @@ pub enum Element {
 }
 
 impl Element {
+    pub fn name(&self) -> &str {
+        match self {
+            Element::R(name, ..)
+            | Element::C(name, ..)
+            | Element::L(name, ..)
+            | Element::V(name, ..)
+            | Element::I(name, ..)
+            | Element::E(name, ..)
+            | Element::F(name, ..)
+            | Element::G(name, ..)
+            | Element::H(name, ..)
+            | Element::Q(name, ..) => name,
+        }
+    }
+
+    pub fn nodes(&self) -> Vec<&String> {
+        match self {
+            Element::R(_, _, n1, n2, ..)
+            | Element::C(_, _, n1, n2, ..)
+            | Element::L(_, _, n1, n2, ..)
+            | Element::V(_, _, _, n1, n2, ..)
+            | Element::I(_, _, _, n1, n2, ..)
+            | Element::F(_, _, n1, n2, ..)
+            | Element::H(_, _, n1, n2, ..) => vec![n1, n2],
+            Element::E(_, _, n1, n2, cn1, cn2, ..) | Element::G(_, _, n1, n2, cn1, cn2, ..) => {
+                vec![n1, n2, cn1, cn2]
+            }
+            Element::Q(_, c_node, b_node, e_node, ..) => vec![c_node, b_node, e_node],
+        }
+    }
+
+    // Moves every port of the element connected to node `from` to node `to`
+    pub fn reconnect(&self, from: &str, to: &str) -> Element {
+        let swap = |node: String| if node == from { to.to_owned() } else { node };
+
+        match self.clone() {
+            Element::R(name, value, n1, n2, position) => {
+                Element::R(name, value, swap(n1), swap(n2), position)
+            }
+            Element::C(name, value, n1, n2, position) => {
+                Element::C(name, value, swap(n1), swap(n2), position)
+            }
+            Element::L(name, value, n1, n2, position) => {
+                Element::L(name, value, swap(n1), swap(n2), position)
+            }
+            Element::V(name, time_domain, small_signal, n1, n2, position) => Element::V(
+                name,
+                time_domain,
+                small_signal,
+                swap(n1),
+                swap(n2),
+                position,
+            ),
+            Element::I(name, time_domain, small_signal, n1, n2, position) => Element::I(
+                name,
+                time_domain,
+                small_signal,
+                swap(n1),
+                swap(n2),
+                position,
+            ),
+            Element::E(name, value, n1, n2, cn1, cn2, position) => Element::E(
+                name,
+                value,
+                swap(n1),
+                swap(n2),
+                swap(cn1),
+                swap(cn2),
+                position,
+            ),
+            Element::F(name, value, n1, n2, src, position) => {
+                Element::F(name, value, swap(n1), swap(n2), src, position)
+            }
+            Element::G(name, value, n1, n2, cn1, cn2, position) => Element::G(
+                name,
+                value,
+                swap(n1),
+                swap(n2),
+                swap(cn1),
+                swap(cn2),
+                position,
+            ),
+            Element::H(name, value, n1, n2, src, position) => {
+                Element::H(name, value, swap(n1), swap(n2), src, position)
+            }
+            Element::Q(name, c_node, b_node, e_node, model, position) => Element::Q(
+                name,
+                swap(c_node),
+                swap(b_node),
+                swap(e_node),
+                model,
+                position,
+            ),
+        }
+    }
+
     fn replace_ground_alias(nodes: &[&str], ground_alias: &HashSet<String>) -> Vec<String> {
         nodes
             .into_iter()
@@ -1,13 +1,24 @@
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 
-use crate::simulator::{simulation::Simulation, simulator_error::SimulatorError};
+use crate::{
+    common::numbers::position::Position,
+    simulator::{
+        simulation::{LoopInjection, SParameterPort, Simulation},
+        simulator_error::SimulatorError,
+        sparameters::{get_port_name, get_port_source_node},
+        stability::{LOOP_BREAK_NODE, LOOP_PROBE_NAME},
+        unit_of_magnitude::UnitOfMagnitude as Unit,
+    },
+};
 
-use super::element::Element;
+use super::element::{Element, SmallSignalConfig, TimeDomainConfig};
 
 #[derive(Clone)]
 pub struct Schematic {
     elements: Vec<Element>,
     ground_alias: HashSet<String>,
+    // Canvas nodes behind each element and node name, lowercase like ngspice prints them
+    canvas_ids: HashMap<String, Vec<String>>,
 }
 
 impl Schematic {
@@ -15,6 +26,7 @@ impl Schematic {
         Self {
             elements: Vec::default(),
             ground_alias: HashSet::default(),
+            canvas_ids: HashMap::default(),
         }
     }
 
@@ -26,6 +38,29 @@ impl Schematic {
         self.ground_alias.insert(alias.to_owned());
     }
 
+    pub fn insert_canvas_id(&mut self, spice_name: &str, canvas_id: &str) {
+        let canvas_ids = self
+            .canvas_ids
+            .entry(spice_name.to_lowercase())
+            .or_default();
+
+        if !canvas_ids.iter().any(|id| id == canvas_id) {
+            canvas_ids.push(canvas_id.to_owned());
+        }
+    }
+
+    pub fn get_canvas_ids(&self) -> &HashMap<String, Vec<String>> {
+        &self.canvas_ids
+    }
+
+    pub fn elements(&self) -> &Vec<Element> {
+        &self.elements
+    }
+
+    pub fn is_ground(&self, node: &str) -> bool {
+        self.ground_alias.contains(node)
+    }
+
     fn get_netlist_header() -> String {
         format!("Graphic Spice Netlist\n")
     }
@@ -39,7 +74,142 @@ impl Schematic {
         format!(".end\n")
     }
 
+    // Breaks the loop at node, in front of the given element, and inserts the probe
+    // sources used for Middlebrook's double injection:
+    // Vprobe loop_break node (voltage injection) and Iprobe gnd loop_break (current injection)
+    fn with_loop_probe(
+        &self,
+        element_name: &str,
+        node: &str,
+        injection: &LoopInjection,
+    ) -> Result<Schematic, SimulatorError> {
+        if self.ground_alias.contains(node) {
+            return Err(SimulatorError::InvalidLoopBreak(node.to_owned()));
+        }
+
+        let mut schematic = self.clone();
+
+        let element = schematic
+            .elements
+            .iter_mut()
+            .find(|element| element.name() == element_name)
+            .ok_or(SimulatorError::InvalidLoopBreak(element_name.to_owned()))?;
+
+        if !element
+            .nodes()
+            .iter()
+            .any(|element_node| *element_node == node)
+        {
+            return Err(SimulatorError::InvalidLoopBreak(element_name.to_owned()));
+        }
+
+        *element = element.reconnect(node, LOOP_BREAK_NODE);
+
+        let (voltage_amplitude, current_amplitude) = match injection {
+            LoopInjection::Voltage => (1.0, 0.0),
+            LoopInjection::Current => (0.0, 1.0),
+        };
+
+        let probe_position = Position { x: 0, y: 0 };
+
+        schematic.insert(Element::V(
+            LOOP_PROBE_NAME.to_owned(),
+            Some(TimeDomainConfig::Dc {
+                value: Some(Unit::Base(0.0)),
+            }),
+            Some(SmallSignalConfig::new(Unit::Base(voltage_amplitude), None)),
+            LOOP_BREAK_NODE.to_owned(),
+            node.to_owned(),
+            probe_position,
+        ));
+
+        schematic.insert(Element::I(
+            LOOP_PROBE_NAME.to_owned(),
+            Some(TimeDomainConfig::Dc {
+                value: Some(Unit::Base(0.0)),
+            }),
+            Some(SmallSignalConfig::new(Unit::Base(current_amplitude), None)),
+            "gnd".to_owned(),
+            LOOP_BREAK_NODE.to_owned(),
+            probe_position,
+        ));
+
+        Ok(schematic)
+    }
+
+    // Terminates every port in its reference impedance, only the excited port is driven:
+    // Vspk spk_src reference DC 0 AC 1|0 and Rspk spk_src node Z0
+    fn with_sparameter_ports(
+        &self,
+        ports: &[SParameterPort],
+        excitation: usize,
+    ) -> Result<Schematic, SimulatorError> {
+        let mut schematic = self.clone();
+
+        for (index, port) in ports.iter().enumerate() {
+            let is_connected = self
+                .elements
+                .iter()
+                .any(|element| element.nodes().iter().any(|node| **node == port.node));
+
+            if !is_connected || self.ground_alias.contains(&port.node) {
+                return Err(SimulatorError::InvalidPort(port.node.to_owned()));
+            }
+
+            let port_position = Position { x: 0, y: 0 };
+
+            schematic.insert(Element::V(
+                get_port_name(index),
+                Some(TimeDomainConfig::Dc {
+                    value: Some(Unit::Base(0.0)),
+                }),
+                Some(SmallSignalConfig::new(
+                    Unit::Base(if index == excitation { 1.0 } else { 0.0 }),
+                    None,
+                )),
+                get_port_source_node(index),
+                port.reference.clone().unwrap_or("gnd".to_owned()),
+                port_position,
+            ));
+
+            schematic.insert(Element::R(
+                get_port_name(index),
+                port.impedance.clone(),
+                get_port_source_node(index),
+                port.node.to_owned(),
+                port_position,
+            ));
+        }
+
+        Ok(schematic)
+    }
+
     pub fn build_netlist(&self, sim_config: Simulation) -> Result<String, SimulatorError> {
+        if let Simulation::Stability {
+            element,
+            node,
+            injection,
+            ..
+        } = &sim_config
+        {
+            return self
+                .with_loop_probe(element, node, injection)?
+                .write_netlist(sim_config);
+        }
+
+        if let Simulation::Sp {
+            ports, excitation, ..
+        } = &sim_config
+        {
+            return self
+                .with_sparameter_ports(ports, *excitation)?
+                .write_netlist(sim_config);
+        }
+
+        self.write_netlist(sim_config)
+    }
+
+    fn write_netlist(&self, sim_config: Simulation) -> Result<String, SimulatorError> {
         let mut netlist: String = String::default();
 
         netlist.push_str(&Self::get_netlist_header());
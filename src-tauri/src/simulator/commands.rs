@@ -1,234 +1,97 @@
 use std::{
     collections::HashMap,
-    fs::File,
+    fs::{self, File},
     path::PathBuf,
     sync::{Arc, Mutex},
-    thread::{self, sleep},
-    time::{Duration, Instant, SystemTime},
+    thread,
+    time::Instant,
+};
+use tauri::{
+    ipc::{Channel, InvokeResponseBody},
+    Manager, State,
 };
-use tauri::{ipc::Channel, Manager, State};
 
 use tauri_plugin_dialog::DialogExt;
+use tauri_plugin_fs::FilePath;
 
 use crate::{
-    app_state::{instance::InstanceState, AppState},
+    api::{get_run_options, run_analyses},
+    app_state::{
+        instance::InstanceState,
+        settings::{get_app_lib_dir, get_app_resource_dir, get_app_result_cache_dir},
+        AppState,
+    },
     compat::{engine::Engine, spice::graphic_spice::engine::GraphicSpice},
 };
 
 use super::{
     circuit::canvas::{CanvasEdge, CanvasNode},
-    sharedlib::get_shared_lib_path,
+    estimate::{self, SimulationEstimate},
+    export::{self, ComplexFormat},
+    orchestrator::{get_simulation_id, SimulationThreadOrchestrator},
+    resource_report::ResourceReportPayload,
+    result_cache::ResultCache,
+    result_store::{ResultStore, StoredPlotInfo, VectorRange, VectorSlice},
+    run_history::{self, LoadedRun, RunComparison, RunRecord, RunSummary},
     simulation::SimulationConfig,
-    simulation_data::{SimulationData, SimulationDataPayload},
+    simulation_data::{SimulationDataPayload, SimulationSchemaPayload},
+    simulation_result::SimulationResultPayload,
     simulation_status::SimulationStatusPayload,
     simulator::Simulator,
     simulator_error::SimulatorError,
+    sink::{ResultSink, SinkClosed},
+    sparameters::{NetworkParameter, NetworkParameters},
+    unit_of_magnitude::UnitOfMagnitude as Unit,
 };
 
-#[derive(Debug, Clone)]
-enum MainThreadStatus {
-    Running,
-    Done,
-}
-
-#[derive(Debug, Clone)]
-pub enum SecondaryThreadStatus {
-    Idle,
-    Running,
-    Halted,
-    Done,
-    Panic,
-}
-
-const AVAIL_SPICE_THREADS: usize = 4;
-
-#[derive(Debug, Clone)]
-struct ThreadOperationInfo {
-    status: SecondaryThreadStatus,
-    ongoing_simulation: Option<String>,
-    queued_simulations: Vec<(String, SimulationConfig)>,
-    current_timer: Option<SystemTime>,
-    simulation_data_buffer: Vec<SimulationData>,
+// Forwards everything to the frontend
+struct ChannelSink {
+    schema_update_channel: Channel<SimulationSchemaPayload>,
+    data_update_channel: Channel<SimulationDataPayload>,
+    // Raw columns of the points, the json channel is the fallback without it
+    data_columns_channel: Option<Channel<InvokeResponseBody>>,
+    status_update_channel: Channel<SimulationStatusPayload>,
+    result_update_channel: Channel<SimulationResultPayload>,
+    report_update_channel: Channel<ResourceReportPayload>,
 }
 
-impl ThreadOperationInfo {
-    pub fn new() -> Self {
-        Self {
-            status: SecondaryThreadStatus::Idle,
-            queued_simulations: Vec::default(),
-            ongoing_simulation: None,
-            current_timer: None,
-            simulation_data_buffer: Vec::default(),
-        }
-    }
-
-    pub fn restart_timer(&mut self) {
-        self.current_timer = Some(SystemTime::now());
+impl ResultSink for ChannelSink {
+    fn send_schema(&self, payload: SimulationSchemaPayload) -> Result<(), SinkClosed> {
+        self.schema_update_channel
+            .send(payload)
+            .map_err(|_| SinkClosed)
     }
 
-    pub fn has_threshold_elapsed(&self, threshold: u128) -> bool {
-        if let Some(timer) = &self.current_timer {
-            if let Ok(elapsed) = timer.elapsed() {
-                if elapsed.as_millis() > threshold {
-                    log::info!("Elapsed {}", elapsed.as_millis())
-                }
-
-                return elapsed.as_millis() > threshold;
+    fn send_data(&self, payload: SimulationDataPayload) -> Result<(), SinkClosed> {
+        if let Some(data_columns_channel) = &self.data_columns_channel {
+            if let Some(columns) = payload.to_columns() {
+                return data_columns_channel
+                    .send(InvokeResponseBody::Raw(columns))
+                    .map_err(|_| SinkClosed);
             }
         }
 
-        return false;
-    }
-
-    pub fn flush_simulation_data_buffer(&mut self) -> Vec<SimulationData> {
-        let buffer = self.simulation_data_buffer.clone();
-
-        self.simulation_data_buffer = Vec::default();
-
-        return buffer;
-    }
-
-    pub fn push_simulation_data(&mut self, simulation_data: SimulationData) {
-        self.simulation_data_buffer.push(simulation_data);
-    }
-
-    pub fn queue_simulation(&mut self, simulation_info: (String, SimulationConfig)) {
-        self.queued_simulations.push(simulation_info)
-    }
-
-    pub fn dequeue_simulation(&mut self) -> Option<(String, SimulationConfig)> {
-        self.queued_simulations.pop()
+        self.data_update_channel
+            .send(payload)
+            .map_err(|_| SinkClosed)
     }
 
-    pub fn set_status(&mut self, new_status: SecondaryThreadStatus) {
-        self.status = new_status;
+    fn send_status(&self, payload: SimulationStatusPayload) -> Result<(), SinkClosed> {
+        self.status_update_channel
+            .send(payload)
+            .map_err(|_| SinkClosed)
     }
 
-    pub fn set_active_simulation(&mut self, sim_id: &str) {
-        self.ongoing_simulation = Some(sim_id.to_owned());
+    fn send_result(&self, payload: SimulationResultPayload) -> Result<(), SinkClosed> {
+        self.result_update_channel
+            .send(payload)
+            .map_err(|_| SinkClosed)
     }
 
-    pub fn get_ongoing_simulation_id(&self) -> Option<String> {
-        self.ongoing_simulation.clone()
-    }
-}
-
-#[derive(Debug, Clone)]
-pub struct SimulationThreadOrchestrator {
-    status: MainThreadStatus,
-    thread_info: HashMap<usize, ThreadOperationInfo>,
-}
-
-impl SimulationThreadOrchestrator {
-    pub fn new(simulations_to_run: HashMap<String, SimulationConfig>) -> Self {
-        let mut allocated: usize = 0;
-
-        let mut thread_info: HashMap<usize, ThreadOperationInfo> = HashMap::default();
-
-        for (id, simulation) in simulations_to_run {
-            let thread_n = allocated % AVAIL_SPICE_THREADS;
-
-            let operation_info = thread_info
-                .entry(thread_n)
-                .or_insert(ThreadOperationInfo::new());
-
-            operation_info.queue_simulation((id, simulation));
-
-            allocated = allocated + 1;
-        }
-
-        Self {
-            status: MainThreadStatus::Running,
-            thread_info,
-        }
-    }
-
-    pub fn has_running_threads(&self) -> bool {
-        for (_, thread_info) in &self.thread_info {
-            match thread_info.status {
-                SecondaryThreadStatus::Running => {
-                    return true;
-                }
-
-                SecondaryThreadStatus::Halted => {
-                    return true;
-                }
-
-                SecondaryThreadStatus::Idle => return true,
-
-                _ => {
-                    continue;
-                }
-            }
-        }
-
-        return false;
-    }
-
-    pub fn get_thread_ongoing_simulation_id(&self, id: usize) -> Option<String> {
-        if let Some(thread_info) = self.thread_info.get(&id) {
-            return thread_info.get_ongoing_simulation_id();
-        }
-
-        return None;
-    }
-
-    pub fn restart_timer(&mut self, id: usize) {
-        if let Some(thread_info) = self.thread_info.get_mut(&id) {
-            return thread_info.restart_timer();
-        }
-    }
-
-    pub fn has_threshold_elapsed(&self, id: usize, threshold: u128) -> bool {
-        if let Some(thread_info) = self.thread_info.get(&id) {
-            return thread_info.has_threshold_elapsed(threshold);
-        }
-
-        return false;
-    }
-
-    pub fn push_simulation_data(&mut self, id: usize, simulation_data: SimulationData) {
-        if let Some(thread_info) = self.thread_info.get_mut(&id) {
-            return thread_info.push_simulation_data(simulation_data);
-        }
-    }
-
-    pub fn flush_simulation_data_buffer(&mut self, id: usize) -> Vec<SimulationData> {
-        if let Some(thread_info) = self.thread_info.get_mut(&id) {
-            return thread_info.flush_simulation_data_buffer();
-        }
-
-        return Vec::default();
-    }
-
-    pub fn get_thread_status(&self, id: usize) -> SecondaryThreadStatus {
-        self.thread_info.get(&id).unwrap().status.clone()
-    }
-
-    pub fn dequeue_simulation(&mut self, id: usize) -> Option<(String, SimulationConfig)> {
-        if let Some(thread_info) = self.thread_info.get_mut(&id) {
-            return thread_info.dequeue_simulation();
-        } else {
-            return None::<(String, SimulationConfig)>;
-        }
-    }
-
-    pub fn set_thread_status(&mut self, id: usize, new_status: SecondaryThreadStatus) {
-        if let Some(thread_info) = self.thread_info.get_mut(&id) {
-            thread_info.set_status(new_status);
-        }
-    }
-
-    pub fn set_active_simulation(&mut self, id: usize, sim_id: &str) {
-        if let Some(thread_info) = self.thread_info.get_mut(&id) {
-            thread_info.set_active_simulation(sim_id);
-        }
-    }
-
-    pub fn threads_needed(&self) -> usize {
-        let threads_configured = self.thread_info.len();
-
-        return threads_configured;
+    fn send_report(&self, payload: ResourceReportPayload) -> Result<(), SinkClosed> {
+        self.report_update_channel
+            .send(payload)
+            .map_err(|_| SinkClosed)
     }
 }
 
@@ -237,11 +100,15 @@ pub async fn simulate(
     nodes: Vec<CanvasNode>,
     edges: Vec<CanvasEdge>,
     config: HashMap<String, SimulationConfig>,
+    schema_update_channel: Channel<SimulationSchemaPayload>,
     data_update_channel: Channel<SimulationDataPayload>,
+    data_columns_channel: Option<Channel<InvokeResponseBody>>,
     status_update_channel: Channel<SimulationStatusPayload>,
+    result_update_channel: Channel<SimulationResultPayload>,
+    report_update_channel: Channel<ResourceReportPayload>,
     app_handle: tauri::AppHandle,
 ) -> Result<(), SimulatorError> {
-    let app_state: State<AppState, '_> = app_handle.state();
+    let app_state: State<'_, AppState> = app_handle.state();
 
     let instance_state_guard = app_state.instance_state.lock().unwrap();
     let mut instance_state = (*instance_state_guard).clone();
@@ -316,88 +183,404 @@ pub async fn simulate(
     }
 
     log::info!("Starts simulate command");
-    let mut simulation_handles: Vec<thread::JoinHandle<()>> = Vec::default();
 
-    let orchestrator = Arc::new(Mutex::new(SimulationThreadOrchestrator::new(config)));
-    let orchestrator_guard = orchestrator.lock().unwrap();
-    let threads_needed = orchestrator_guard.threads_needed();
-    log::info!("Threads needed: {}", threads_needed);
-    drop(orchestrator_guard);
+    let settings_guard = app_state.spice_settings.lock().unwrap();
+    let settings = settings_guard.clone();
+    drop(settings_guard);
 
-    let schematic = Simulator::create_schematic_from_canvas(nodes, edges)?;
+    // Every thread loads its own copy of the source library
+    let mut options = get_run_options(
+        &settings,
+        get_app_resource_dir(&app_handle).as_deref(),
+        get_app_lib_dir(&app_handle)?,
+    )?;
 
-    log::info!("Begin thread creation...");
+    if settings.cache_results.unwrap_or(true) {
+        options.result_cache_dir = get_app_result_cache_dir(&app_handle);
+    }
+    log::info!(
+        "Using ngspice from: {:?}",
+        options.source_lib_path.as_os_str()
+    );
 
-    for thread_n in 0..(threads_needed) {
-        log::info!("Spawns {} thread...", thread_n);
+    let schematic = Simulator::create_schematic_from_canvas(nodes, edges)?;
+    let project_path = get_project_path(&app_handle);
+    let run_config = config.clone();
+
+    let sink = Arc::new(ChannelSink {
+        schema_update_channel,
+        data_update_channel,
+        data_columns_channel,
+        status_update_channel,
+        result_update_channel,
+        report_update_channel,
+    });
+
+    let simulation_run = run_analyses(schematic, config, options, sink);
+    let orchestrator = Arc::clone(&simulation_run.orchestrator);
+
+    let orch_guard = orchestrator.lock().unwrap();
+    let waveforms = orch_guard.get_waveforms();
+    let results = orch_guard.get_results();
+    drop(orch_guard);
+
+    let run_results = Arc::clone(&results);
+
+    let mut waveforms_guard = app_state.simulation_waveforms.lock().unwrap();
+    *waveforms_guard = Some(waveforms);
+    drop(waveforms_guard);
+
+    let mut results_guard = app_state.simulation_results.lock().unwrap();
+    *results_guard = Some(results);
+    drop(results_guard);
+
+    // Lets cancel, pause and resume reach the running simulations
+    let mut orchestrator_guard = app_state.simulation_orchestrator.lock().unwrap();
+    *orchestrator_guard = Some(Arc::clone(&orchestrator));
+    drop(orchestrator_guard);
 
-        let t_orchestrator = Arc::clone(&orchestrator);
-        let t_app_handle = app_handle.clone();
-        let t_schematic = schematic.clone();
-        let t_data_update_channel = data_update_channel.clone();
-        let t_status_update_channel = status_update_channel.clone();
+    let supervisor_app_handle = app_handle.clone();
 
-        let handle = thread::spawn(move || {
-            let thread_id = thread_n;
+    // Waits for the simulation threads in the background, so the command returns right away
+    thread::spawn(move || {
+        simulation_run.wait();
 
-            log::info!("Init {} thread...", thread_id);
+        // Saved with the project so the results outlive the app
+        let results_guard = run_results.lock().unwrap();
+        let store = results_guard.clone();
+        drop(results_guard);
 
-            let path = get_shared_lib_path(&t_app_handle, thread_id);
+        if let (Some(project_path), false) = (project_path, store.is_empty()) {
+            let run = RunRecord::new(run_config, store);
 
-            log::info!("Opening lib at: {:?}", path.as_os_str());
+            match run.save(&project_path) {
+                Ok(()) => log::info!("Saved run {}", run.summary.id),
+                Err(error) => log::error!("Failed to save run {:?}", error),
+            }
+        }
 
-            // Library needs to live until its done being used
-            let (mut simulator, library) = Simulator::init(
-                thread_id,
-                t_orchestrator,
-                path,
-                t_data_update_channel,
-                t_status_update_channel,
-            );
+        let app_state: State<'_, AppState> = supervisor_app_handle.state();
+        let mut orchestrator_guard = app_state.simulation_orchestrator.lock().unwrap();
 
-            simulator.load_schematic(t_schematic);
+        // Another simulate call may have replaced it in the meantime
+        if let Some(current_orchestrator) = &*orchestrator_guard {
+            if Arc::ptr_eq(current_orchestrator, &orchestrator) {
+                *orchestrator_guard = None;
+            }
+        }
+    });
 
-            simulator.run();
+    Ok(())
+}
+
+fn get_project_path(app_handle: &tauri::AppHandle) -> Option<PathBuf> {
+    let app_state: State<'_, AppState> = app_handle.state();
 
-            library.close();
-        });
+    let instance_state_guard = app_state.instance_state.lock().unwrap();
+    let instance_state = (*instance_state_guard).clone();
+    drop(instance_state_guard);
 
-        simulation_handles.push(handle);
+    match instance_state {
+        InstanceState::NotSaved => None,
+        InstanceState::FailedToSave { path, .. } | InstanceState::Saved { path, .. } => Some(path),
     }
+}
 
-    loop {
-        let orch_guard = orchestrator.lock().unwrap();
-        let status = orch_guard.status.clone();
+fn with_running_orchestrator(
+    app_handle: &tauri::AppHandle,
+    action: impl FnOnce(&mut SimulationThreadOrchestrator),
+) {
+    let app_state: State<'_, AppState> = app_handle.state();
+
+    let orchestrator_guard = app_state.simulation_orchestrator.lock().unwrap();
+    let maybe_orchestrator = orchestrator_guard.clone();
+    drop(orchestrator_guard);
+
+    if let Some(orchestrator) = maybe_orchestrator {
+        let mut orch_guard = orchestrator.lock().unwrap();
+        action(&mut orch_guard);
         drop(orch_guard);
+    }
+}
 
-        match status {
-            MainThreadStatus::Running => {
-                let mut orch_guard = orchestrator.lock().unwrap();
-                let has_running_threads = orch_guard.has_running_threads();
+// Points of a simulation of the last simulate call between two times or frequencies,
+// at most max_points of them. None when it has no points kept
+#[tauri::command]
+pub fn get_simulation_range(
+    id: String,
+    from: f64,
+    to: f64,
+    max_points: usize,
+    app_handle: tauri::AppHandle,
+) -> Option<SimulationDataPayload> {
+    let app_state: State<'_, AppState> = app_handle.state();
 
-                if has_running_threads {
-                    continue;
-                } else {
-                    orch_guard.status = MainThreadStatus::Done;
-                }
+    let waveforms_guard = app_state.simulation_waveforms.lock().unwrap();
+    let maybe_waveforms = waveforms_guard.clone();
+    drop(waveforms_guard);
 
-                drop(orch_guard);
-            }
+    let waveforms = maybe_waveforms?;
+    let waveforms_guard = waveforms.lock().unwrap();
+    let range = waveforms_guard.get_range(&id, from, to, max_points);
+    drop(waveforms_guard);
 
-            MainThreadStatus::Done => {
-                log::info!("Main simulation thread is done");
-                break;
-            }
-        }
+    range
+}
+
+fn with_results<T>(
+    app_handle: &tauri::AppHandle,
+    query: impl FnOnce(&ResultStore) -> Option<T>,
+) -> Option<T> {
+    let app_state: State<'_, AppState> = app_handle.state();
+
+    let results_guard = app_state.simulation_results.lock().unwrap();
+    let maybe_results = results_guard.clone();
+    drop(results_guard);
+
+    let results = maybe_results?;
+    let results_guard = results.lock().unwrap();
+    let result = query(&results_guard);
+    drop(results_guard);
+
+    result
+}
+
+// Plots and vectors a finished simulation of the last simulate call left
+#[tauri::command]
+pub fn get_result_plots(id: String, app_handle: tauri::AppHandle) -> Option<Vec<StoredPlotInfo>> {
+    with_results(&app_handle, |results| results.get_plot_infos(&id))
+}
 
-        sleep(Duration::from_millis(100));
+// Part of a vector of a finished simulation, at most max_points of it
+#[tauri::command]
+pub fn get_result_vector(
+    id: String,
+    plot: String,
+    vector: String,
+    range: VectorRange,
+    max_points: usize,
+    app_handle: tauri::AppHandle,
+) -> Option<VectorSlice> {
+    with_results(&app_handle, |results| {
+        results.get_slice(&id, &plot, &vector, &range, max_points)
+    })
+}
+
+// Vectors of a plot of a finished simulation as csv, its first plot unless one is named
+#[tauri::command]
+pub fn export_results_csv(
+    id: String,
+    plot: Option<String>,
+    complex_format: ComplexFormat,
+    file_path: FilePath,
+    app_handle: tauri::AppHandle,
+) -> Result<(), SimulatorError> {
+    let csv = with_results(&app_handle, |results| {
+        let simulation = results.get(&id)?;
+        let plot = match &plot {
+            Some(plot) => simulation
+                .plots
+                .iter()
+                .find(|stored_plot| &stored_plot.name == plot)?,
+            None => simulation.plots.first()?,
+        };
+
+        Some(export::plot_to_csv(plot, &complex_format))
+    })
+    .ok_or_else(|| SimulatorError::NoResultsToExport(id.clone()))?;
+
+    let mut path = PathBuf::from(
+        file_path
+            .as_path()
+            .ok_or(SimulatorError::FailedToExportResults)?,
+    );
+    path.set_extension("csv");
+
+    fs::write(path, csv).map_err(|_| SimulatorError::FailedToExportResults)
+}
+
+// Every plot of a finished simulation as json, with the netlist it ran and its results
+#[tauri::command]
+pub fn export_results_json(
+    id: String,
+    complex_format: ComplexFormat,
+    file_path: FilePath,
+    app_handle: tauri::AppHandle,
+) -> Result<(), SimulatorError> {
+    let json = with_results(&app_handle, |results| {
+        let simulation = results.get(&id)?;
+
+        // Stability and S-parameter results are sent under the analysis id
+        let simulation_results = results
+            .get_results()
+            .iter()
+            .filter(|(result_id, _)| {
+                *result_id == &id || get_simulation_id(&id) == result_id.as_str()
+            })
+            .map(|(_, result)| result)
+            .collect();
+
+        Some(export::simulation_to_json(
+            &id,
+            simulation,
+            simulation_results,
+            &complex_format,
+        ))
+    })
+    .ok_or_else(|| SimulatorError::NoResultsToExport(id.clone()))??;
+
+    let mut path = PathBuf::from(
+        file_path
+            .as_path()
+            .ok_or(SimulatorError::FailedToExportResults)?,
+    );
+    path.set_extension("json");
+
+    fs::write(path, json).map_err(|_| SimulatorError::FailedToExportResults)
+}
+
+// Forgets every cached result, the next simulations run ngspice again
+#[tauri::command]
+pub fn clear_result_cache(app_handle: tauri::AppHandle) {
+    log::info!("Clearing the result cache");
+
+    if let Some(cache_dir) = get_app_result_cache_dir(&app_handle) {
+        ResultCache::clear(&cache_dir);
     }
+}
 
-    for handle in simulation_handles {
-        handle.join().unwrap();
+// Past runs of the open project, latest first
+#[tauri::command]
+pub fn list_runs(app_handle: tauri::AppHandle) -> Vec<RunSummary> {
+    match get_project_path(&app_handle) {
+        Some(project_path) => run_history::list_runs(&project_path),
+        None => Vec::default(),
     }
+}
+
+// Makes a past run the one results are queried from, without simulating again
+#[tauri::command]
+pub fn load_run(id: String, app_handle: tauri::AppHandle) -> Result<LoadedRun, SimulatorError> {
+    let project_path =
+        get_project_path(&app_handle).ok_or_else(|| SimulatorError::RunNotFound(id.clone()))?;
+    let run = RunRecord::load(&project_path, &id)?;
+    let results = run.store.get_results().clone();
+
+    let app_state: State<'_, AppState> = app_handle.state();
+    let mut results_guard = app_state.simulation_results.lock().unwrap();
+    *results_guard = Some(Arc::new(Mutex::new(run.store)));
+    drop(results_guard);
+
+    Ok(LoadedRun {
+        summary: run.summary,
+        results,
+    })
+}
+
+// Vectors of one simulation of two past runs, to draw over each other
+#[tauri::command]
+pub fn compare_runs(
+    a: String,
+    b: String,
+    simulation_id: String,
+    max_points: usize,
+    app_handle: tauri::AppHandle,
+) -> Result<RunComparison, SimulatorError> {
+    let project_path =
+        get_project_path(&app_handle).ok_or_else(|| SimulatorError::RunNotFound(a.clone()))?;
+
+    let run_a = RunRecord::load(&project_path, &a)?;
+    let run_b = RunRecord::load(&project_path, &b)?;
+
+    run_history::compare_runs(&run_a, &run_b, &simulation_id, max_points)
+}
+
+// Lets the frontend warn about heavy analyses and suggest transient steps before simulating
+#[tauri::command]
+pub fn estimate_simulations(
+    nodes: Vec<CanvasNode>,
+    edges: Vec<CanvasEdge>,
+    config: HashMap<String, SimulationConfig>,
+    app_handle: tauri::AppHandle,
+) -> Result<HashMap<String, SimulationEstimate>, SimulatorError> {
+    let app_state: State<'_, AppState> = app_handle.state();
+    let settings_guard = app_state.spice_settings.lock().unwrap();
+    let thresholds = settings_guard.cost_thresholds.clone();
+    drop(settings_guard);
+
+    let schematic = Simulator::create_schematic_from_canvas(nodes, edges)?;
+
+    Ok(estimate::estimate_simulations(
+        &schematic,
+        &config,
+        &thresholds,
+    ))
+}
 
-    log::info!("All threads joined");
+#[tauri::command]
+pub fn cancel_simulation(id: String, app_handle: tauri::AppHandle) {
+    log::info!("Cancelling simulation {}", id);
+
+    with_running_orchestrator(&app_handle, |orchestrator| {
+        orchestrator.cancel_simulation(&id)
+    });
+}
+
+#[tauri::command]
+pub fn pause_simulation(id: String, app_handle: tauri::AppHandle) {
+    log::info!("Pausing simulation {}", id);
+
+    with_running_orchestrator(&app_handle, |orchestrator| {
+        orchestrator.pause_simulation(&id)
+    });
+}
+
+#[tauri::command]
+pub fn resume_simulation(id: String, app_handle: tauri::AppHandle) {
+    log::info!("Resuming simulation {}", id);
+
+    with_running_orchestrator(&app_handle, |orchestrator| {
+        orchestrator.resume_simulation(&id)
+    });
+}
+
+// Runs a transient stopped at a breakpoint, or paused, by step seconds, its tstep without one
+#[tauri::command]
+pub fn step_simulation(
+    id: String,
+    step: Option<String>,
+    app_handle: tauri::AppHandle,
+) -> Result<(), SimulatorError> {
+    log::info!("Stepping simulation {}", id);
+
+    let step = step
+        .map(|step| Unit::from(step).map_err(|error| SimulatorError::UnitError(error)))
+        .transpose()?
+        .map(|step| step.value());
+
+    with_running_orchestrator(&app_handle, |orchestrator| {
+        orchestrator.step_simulation(&id, step)
+    });
 
     Ok(())
 }
+
+#[tauri::command]
+pub fn export_touchstone(
+    network: NetworkParameters,
+    parameter: NetworkParameter,
+    file_path: FilePath,
+) -> Result<(), SimulatorError> {
+    let touchstone = network
+        .to_touchstone(&parameter)
+        .ok_or(SimulatorError::FailedToExportTouchstone)?;
+
+    let mut path = PathBuf::from(
+        file_path
+            .as_path()
+            .ok_or(SimulatorError::FailedToExportTouchstone)?,
+    );
+    path.set_extension(format!("s{}p", network.ports.len()));
+
+    fs::write(path, touchstone).map_err(|_| SimulatorError::FailedToExportTouchstone)
+}
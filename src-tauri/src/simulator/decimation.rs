@@ -0,0 +1,142 @@
+use super::simulation_data::SimulationData;
+
+/// Points per second streamed for live display when the settings do not say
+pub const DEFAULT_DISPLAY_POINTS_PER_SECOND: usize = 2000;
+
+// Signed value of real vectors, magnitude of complex ones
+pub fn level(c_real: f64, c_imag: f64) -> f64 {
+    if c_imag == 0.0 {
+        return c_real;
+    }
+
+    c_real.hypot(c_imag)
+}
+
+/// Indices of at most `max_points` of `len` points, the first and the last one and, in
+/// each bucket of consecutive points, the lowest and highest of every series so spikes
+/// are still drawn. `level` gives the value of a series at a point
+pub fn decimate_indices(
+    len: usize,
+    series_count: usize,
+    max_points: usize,
+    level: impl Fn(usize, usize) -> Option<f64>,
+) -> Vec<usize> {
+    if len <= max_points.max(2) {
+        return (0..len).collect();
+    }
+
+    // Every bucket keeps up to two points per series
+    let bucket_count = (max_points.saturating_sub(2) / (2 * series_count.max(1))).max(1);
+    let bucket_len = (len - 2).div_ceil(bucket_count);
+
+    let mut kept: Vec<usize> = vec![0];
+
+    for bucket_start in (1..len - 1).step_by(bucket_len) {
+        let bucket = bucket_start..(bucket_start + bucket_len).min(len - 1);
+
+        if series_count == 0 {
+            kept.push(bucket_start);
+            continue;
+        }
+
+        let mut bucket_kept: Vec<usize> = (0..series_count)
+            .flat_map(|series| {
+                let levels = bucket
+                    .clone()
+                    .filter_map(|index| level(series, index).map(|value| (index, value)));
+
+                let min = levels
+                    .clone()
+                    .min_by(|(_, a), (_, b)| a.total_cmp(b))
+                    .map(|(index, _)| index);
+                let max = levels
+                    .max_by(|(_, a), (_, b)| a.total_cmp(b))
+                    .map(|(index, _)| index);
+
+                min.into_iter().chain(max)
+            })
+            .collect();
+
+        bucket_kept.sort_unstable();
+        bucket_kept.dedup();
+        kept.extend(bucket_kept);
+    }
+
+    kept.push(len - 1);
+
+    kept
+}
+
+/// Thins the points down to at most `max_points`, see `decimate_indices`. The scale is
+/// left out of the comparison
+pub fn decimate(
+    data: &[SimulationData],
+    scale_index: Option<usize>,
+    max_points: usize,
+) -> Vec<SimulationData> {
+    let vectors: Vec<usize> = (0..data.first().map_or(0, |it| it.get_values().len()))
+        .filter(|index| Some(*index) != scale_index)
+        .collect();
+
+    decimate_indices(data.len(), vectors.len(), max_points, |series, index| {
+        data[index]
+            .get_values()
+            .get(vectors[series])
+            .map(|(c_real, c_imag)| level(*c_real, *c_imag))
+    })
+    .into_iter()
+    .map(|index| data[index].clone())
+    .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sine(series: usize, index: usize) -> Option<f64> {
+        Some((index as f64 / (10.0 + series as f64)).sin())
+    }
+
+    #[test]
+    fn short_series_are_kept_whole() {
+        assert_eq!(decimate_indices(5, 2, 10, sine), vec![0, 1, 2, 3, 4]);
+        assert_eq!(decimate_indices(2, 1, 0, sine), vec![0, 1]);
+    }
+
+    #[test]
+    fn decimated_series_fit_in_the_budget_and_keep_both_ends() {
+        for (len, series_count, max_points) in [(1000, 1, 100), (1000, 3, 50), (10_001, 2, 7)] {
+            let kept = decimate_indices(len, series_count, max_points, sine);
+
+            assert!(kept.len() <= max_points, "{} points kept", kept.len());
+            assert_eq!(kept.first(), Some(&0));
+            assert_eq!(kept.last(), Some(&(len - 1)));
+            assert!(kept.windows(2).all(|pair| pair[0] < pair[1]));
+        }
+    }
+
+    #[test]
+    fn points_without_series_are_spread_evenly() {
+        let kept = decimate_indices(1000, 0, 10, |_, _| None);
+
+        assert!(kept.len() <= 10);
+        assert_eq!(kept.first(), Some(&0));
+        assert_eq!(kept.last(), Some(&999));
+        assert!(kept.windows(2).all(|pair| pair[0] < pair[1]));
+    }
+
+    #[test]
+    fn spikes_are_kept() {
+        let kept = decimate_indices(1000, 1, 20, |_, index| {
+            Some(if index == 567 { 100.0 } else { 0.0 })
+        });
+
+        assert!(kept.contains(&567));
+    }
+
+    #[test]
+    fn complex_values_are_compared_by_magnitude() {
+        assert_eq!(level(-2.0, 0.0), -2.0);
+        assert_eq!(level(3.0, -4.0), 5.0);
+    }
+}
@@ -0,0 +1,559 @@
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+use super::{
+    circuit::{
+        element::{Element, TimeDomainConfig},
+        schematic::Schematic,
+    },
+    orchestrator::get_simulation_id,
+    simulation::{FrequencyVariation, Simulation, SimulationConfig},
+    sparameters::expand_sparameter_configs,
+    stability::expand_stability_configs,
+    unit_of_magnitude::UnitOfMagnitude as Unit,
+};
+
+// ngspice keeps every vector value as a double, complex ones as two
+const REAL_VALUE_SIZE: usize = 8;
+const COMPLEX_VALUE_SIZE: usize = 16;
+
+// Without tmax ngspice never steps over a fiftieth of the simulated time
+const DEFAULT_TMAX_DIVISOR: f64 = 50.0;
+
+// Suggested steps resolve the shortest source feature with this many points
+const SUGGESTED_POINTS_PER_FEATURE: f64 = 20.0;
+// Below this many points per feature the transient is warned about
+const MIN_POINTS_PER_FEATURE: f64 = 4.0;
+
+/// Sizes over which analyses are warned about or not run at all, memory in bytes
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CostThresholds {
+    pub warn_points: usize,
+    pub warn_memory: usize,
+    // None never refuses
+    pub max_points: Option<usize>,
+    pub max_memory: Option<usize>,
+}
+
+impl Default for CostThresholds {
+    fn default() -> Self {
+        Self {
+            warn_points: 1_000_000,
+            warn_memory: 256 * 1024 * 1024,
+            max_points: Some(50_000_000),
+            max_memory: Some(2 * 1024 * 1024 * 1024),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum CostWarning {
+    TooManyPoints(usize),
+    TooMuchMemory(usize),
+    // Largest transient step against the shortest source feature, both in seconds
+    CoarseTimestep { max_step: f64, feature: f64 },
+}
+
+/// Transient steps resolving the fastest source of the schematic
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TimestepSuggestion {
+    pub tstep: String,
+    pub tmax: String,
+}
+
+/// Expected size of one simulation, summed over its runs for stability and S-parameters
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SimulationEstimate {
+    // None when the analysis is missing what its size depends on
+    pub points: Option<usize>,
+    pub vectors: usize,
+    // Bytes
+    pub memory: Option<usize>,
+    pub warnings: Vec<CostWarning>,
+    // Over the max thresholds, it is failed instead of run
+    pub refused: bool,
+    pub suggestion: Option<TimestepSuggestion>,
+}
+
+fn sweep_points(
+    fstart: &Option<Unit>,
+    fstop: &Option<Unit>,
+    variation: &Option<FrequencyVariation>,
+    nx: &Option<i32>,
+) -> Option<usize> {
+    let (fstart, fstop) = (fstart.as_ref()?.value(), fstop.as_ref()?.value());
+    let nx = *nx.as_ref()? as f64;
+
+    if nx <= 0.0 || fstart <= 0.0 || fstop < fstart {
+        return None;
+    }
+
+    let points = match variation.as_ref()? {
+        FrequencyVariation::Dec => nx * (fstop / fstart).log10() + 1.0,
+        FrequencyVariation::Oct => nx * (fstop / fstart).log2() + 1.0,
+        FrequencyVariation::Lin => nx,
+    };
+
+    Some(points.floor() as usize)
+}
+
+fn dc_steps(start: &Option<Unit>, stop: &Option<Unit>, incr: &Option<Unit>) -> Option<usize> {
+    let (start, stop, incr) = (
+        start.as_ref()?.value(),
+        stop.as_ref()?.value(),
+        incr.as_ref()?.value(),
+    );
+
+    if incr == 0.0 {
+        return None;
+    }
+
+    Some(((stop - start) / incr).abs().floor() as usize + 1)
+}
+
+// Largest step ngspice takes, it outputs at least one point every max step
+fn transient_max_step(
+    tstep: &Option<Unit>,
+    tstop: &Option<Unit>,
+    tstart: &Option<Unit>,
+    tmax: &Option<Unit>,
+) -> Option<(f64, f64)> {
+    let tstep = tstep.as_ref()?.value();
+    let span = tstop.as_ref()?.value() - tstart.as_ref().map_or(0.0, |tstart| tstart.value());
+
+    let max_step = match tmax {
+        Some(tmax) => tmax.value(),
+        None => tstep.min(span / DEFAULT_TMAX_DIVISOR),
+    };
+
+    if max_step <= 0.0 || span <= 0.0 {
+        return None;
+    }
+
+    Some((max_step, span))
+}
+
+fn estimate_points(simulation: &Simulation) -> Option<usize> {
+    match simulation {
+        Simulation::Tran {
+            tstep,
+            tstop,
+            tstart,
+            tmax,
+            ..
+        } => {
+            let (max_step, span) = transient_max_step(tstep, tstop, tstart, tmax)?;
+
+            Some((span / max_step).ceil() as usize + 1)
+        }
+
+        Simulation::Op | Simulation::Pz { .. } | Simulation::Tf { .. } => Some(1),
+
+        Simulation::Ac {
+            fstart,
+            fstop,
+            variation,
+            nx,
+        }
+        | Simulation::Disto {
+            fstart,
+            fstop,
+            variation,
+            nx,
+            ..
+        }
+        | Simulation::Stability {
+            fstart,
+            fstop,
+            variation,
+            nx,
+            ..
+        }
+        | Simulation::Sp {
+            fstart,
+            fstop,
+            variation,
+            nx,
+            ..
+        } => sweep_points(fstart, fstop, variation, nx),
+
+        Simulation::Noise {
+            fstart,
+            fstop,
+            variation,
+            pts,
+            ..
+        } => sweep_points(fstart, fstop, variation, pts),
+
+        // DC sensitivity is a single point, AC sensitivity sweeps like an AC analysis
+        Simulation::Sens {
+            fstart,
+            fstop,
+            variation,
+            nx,
+            ..
+        } => match variation {
+            Some(_) => sweep_points(fstart, fstop, variation, nx),
+            None => Some(1),
+        },
+
+        // The second source is swept once per step of the first
+        Simulation::Dc {
+            vstart,
+            vstop,
+            vincr,
+            src2,
+            start2,
+            stop2,
+            incr2,
+            ..
+        } => {
+            let steps = dc_steps(vstart, vstop, vincr)?;
+
+            match src2 {
+                Some(_) => Some(steps * dc_steps(start2, stop2, incr2)?),
+                None => Some(steps),
+            }
+        }
+    }
+}
+
+fn is_complex(simulation: &Simulation) -> bool {
+    match simulation {
+        Simulation::Ac { .. }
+        | Simulation::Disto { .. }
+        | Simulation::Stability { .. }
+        | Simulation::Sp { .. }
+        | Simulation::Pz { .. } => true,
+        Simulation::Sens { variation, .. } => variation.is_some(),
+        _ => false,
+    }
+}
+
+// Node voltages, a current per device since the netlist saves currents, and the scale
+fn estimate_vectors(schematic: &Schematic) -> usize {
+    let mut nodes: Vec<&String> = schematic
+        .elements()
+        .iter()
+        .flat_map(|element| element.nodes())
+        .filter(|node| !schematic.is_ground(node))
+        .collect();
+    nodes.sort();
+    nodes.dedup();
+
+    let currents: usize = schematic
+        .elements()
+        .iter()
+        .map(|element| match element {
+            Element::Q(..) => 4,
+            _ => 1,
+        })
+        .sum();
+
+    nodes.len() + currents + 1
+}
+
+fn positive(unit: &Option<Unit>) -> Option<f64> {
+    unit.as_ref()
+        .map(|unit| unit.value())
+        .filter(|value| *value > 0.0)
+}
+
+fn period(frequency: &Option<Unit>) -> Option<f64> {
+    positive(frequency).map(|frequency| 1.0 / frequency)
+}
+
+// Shortest time the source needs to be resolved over: periods, edges and time constants
+fn shortest_source_feature(time_domain: &TimeDomainConfig) -> Option<f64> {
+    let features = match time_domain {
+        // Only known once the external sources are asked for them
+        TimeDomainConfig::Dc { .. } | TimeDomainConfig::External { .. } => vec![],
+        TimeDomainConfig::Pulse {
+            rise_time,
+            fall_time,
+            pulse_width,
+            period,
+            ..
+        } => vec![
+            positive(rise_time),
+            positive(fall_time),
+            positive(pulse_width),
+            positive(period),
+        ],
+        TimeDomainConfig::Sin { frequency, .. } => vec![period(frequency)],
+        TimeDomainConfig::Exp {
+            rise_time,
+            fall_time,
+            ..
+        } => vec![positive(rise_time), positive(fall_time)],
+        TimeDomainConfig::Sffm {
+            carrier_frequency,
+            signal_frequency,
+            ..
+        } => vec![period(carrier_frequency), period(signal_frequency)],
+        TimeDomainConfig::Am {
+            modulating_frequency,
+            carrier_frequency,
+            ..
+        } => vec![period(modulating_frequency), period(carrier_frequency)],
+    };
+
+    features.into_iter().flatten().reduce(f64::min)
+}
+
+fn shortest_schematic_feature(schematic: &Schematic) -> Option<f64> {
+    schematic
+        .elements()
+        .iter()
+        .filter_map(|element| match element {
+            Element::V(_, Some(time_domain), ..) | Element::I(_, Some(time_domain), ..) => {
+                shortest_source_feature(time_domain)
+            }
+            _ => None,
+        })
+        .reduce(f64::min)
+}
+
+fn suggest_timestep(feature: f64) -> TimestepSuggestion {
+    let tmax = Unit::Base(feature / SUGGESTED_POINTS_PER_FEATURE).format();
+
+    TimestepSuggestion {
+        tstep: tmax.clone(),
+        tmax,
+    }
+}
+
+/// Estimates the points and memory of every simulation before it is dispatched and
+/// checks them against the thresholds. Malformed configs are left out, they fail
+/// once they run
+pub fn estimate_simulations(
+    schematic: &Schematic,
+    config: &HashMap<String, SimulationConfig>,
+    thresholds: &CostThresholds,
+) -> HashMap<String, SimulationEstimate> {
+    let vectors = estimate_vectors(schematic);
+    let shortest_feature = shortest_schematic_feature(schematic);
+
+    let mut estimates: HashMap<String, SimulationEstimate> = HashMap::default();
+
+    for (run_id, run_config) in expand_sparameter_configs(expand_stability_configs(config.clone()))
+    {
+        let Ok(simulation) = Simulation::from_config(run_config) else {
+            continue;
+        };
+
+        let points = estimate_points(&simulation);
+        let value_size = match is_complex(&simulation) {
+            true => COMPLEX_VALUE_SIZE,
+            false => REAL_VALUE_SIZE,
+        };
+        let memory = points.map(|points| points * vectors * value_size);
+
+        let estimate = estimates
+            .entry(get_simulation_id(&run_id).to_owned())
+            .or_insert(SimulationEstimate {
+                points: Some(0),
+                vectors,
+                memory: Some(0),
+                warnings: Vec::default(),
+                refused: false,
+                suggestion: None,
+            });
+
+        estimate.points = estimate.points.zip(points).map(|(sum, run)| sum + run);
+        estimate.memory = estimate.memory.zip(memory).map(|(sum, run)| sum + run);
+
+        if let (
+            Simulation::Tran {
+                tstep,
+                tstop,
+                tstart,
+                tmax,
+                ..
+            },
+            Some(feature),
+        ) = (&simulation, shortest_feature)
+        {
+            estimate.suggestion = Some(suggest_timestep(feature));
+
+            if let Some((max_step, _)) = transient_max_step(tstep, tstop, tstart, tmax) {
+                if max_step > feature / MIN_POINTS_PER_FEATURE {
+                    estimate
+                        .warnings
+                        .push(CostWarning::CoarseTimestep { max_step, feature });
+                }
+            }
+        }
+    }
+
+    for (_, estimate) in estimates.iter_mut() {
+        if let Some(points) = estimate.points {
+            if points > thresholds.warn_points {
+                estimate.warnings.push(CostWarning::TooManyPoints(points));
+            }
+
+            if thresholds
+                .max_points
+                .is_some_and(|max_points| points > max_points)
+            {
+                estimate.refused = true;
+            }
+        }
+
+        if let Some(memory) = estimate.memory {
+            if memory > thresholds.warn_memory {
+                estimate.warnings.push(CostWarning::TooMuchMemory(memory));
+            }
+
+            if thresholds
+                .max_memory
+                .is_some_and(|max_memory| memory > max_memory)
+            {
+                estimate.refused = true;
+            }
+        }
+    }
+
+    estimates
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::common::numbers::position::Position;
+
+    use super::*;
+
+    fn tran(tstep: &str) -> SimulationConfig {
+        SimulationConfig::Tran {
+            tstep: Some(tstep.to_owned()),
+            tstop: Some("8".to_owned()),
+            tstart: Some("0".to_owned()),
+            tmax: Some("0.25".to_owned()),
+            uic: None,
+            limits: None,
+            breakpoints: None,
+        }
+    }
+
+    fn ac() -> SimulationConfig {
+        SimulationConfig::Ac {
+            fstart: Some("1".to_owned()),
+            fstop: Some("1000".to_owned()),
+            variation: Some(FrequencyVariation::Dec),
+            nx: Some(10),
+            limits: None,
+        }
+    }
+
+    fn estimate(
+        schematic: &Schematic,
+        config: SimulationConfig,
+        thresholds: &CostThresholds,
+    ) -> Option<SimulationEstimate> {
+        estimate_simulations(
+            schematic,
+            &HashMap::from([("sim".to_owned(), config)]),
+            thresholds,
+        )
+        .remove("sim")
+    }
+
+    // A 1 kHz sine between in and ground
+    fn sine_source() -> Schematic {
+        let mut schematic = Schematic::new();
+        schematic.insert_ground_alias(&"gnd".to_owned());
+        schematic.insert(Element::V(
+            "v1".to_owned(),
+            Some(TimeDomainConfig::Sin {
+                offset: None,
+                amplitude: Some(Unit::Base(1.0)),
+                frequency: Some(Unit::Base(1000.0)),
+                delay: None,
+                damping_factor: None,
+            }),
+            None,
+            "in".to_owned(),
+            "gnd".to_owned(),
+            Position { x: 0, y: 0 },
+        ));
+
+        schematic
+    }
+
+    #[test]
+    fn transients_output_a_point_every_max_step() {
+        let estimate =
+            estimate(&Schematic::new(), tran("0.25"), &CostThresholds::default()).unwrap();
+
+        assert_eq!(estimate.points, Some(33));
+        // Only the scale without any element
+        assert_eq!(estimate.vectors, 1);
+        assert_eq!(estimate.memory, Some(33 * REAL_VALUE_SIZE));
+        assert!(estimate.warnings.is_empty());
+        assert!(!estimate.refused);
+    }
+
+    #[test]
+    fn sweeps_keep_complex_values() {
+        let estimate = estimate(&Schematic::new(), ac(), &CostThresholds::default()).unwrap();
+
+        assert_eq!(estimate.points, Some(31));
+        assert_eq!(estimate.memory, Some(31 * COMPLEX_VALUE_SIZE));
+    }
+
+    #[test]
+    fn stability_analyses_add_up_both_runs() {
+        let config = SimulationConfig::Stability {
+            element: Some("r1".to_owned()),
+            node: Some("out".to_owned()),
+            fstart: Some("1".to_owned()),
+            fstop: Some("1000".to_owned()),
+            variation: Some(FrequencyVariation::Dec),
+            nx: Some(10),
+            injection: None,
+            limits: None,
+        };
+
+        let estimate = estimate(&Schematic::new(), config, &CostThresholds::default()).unwrap();
+
+        assert_eq!(estimate.points, Some(62));
+    }
+
+    #[test]
+    fn simulations_over_the_max_thresholds_are_refused() {
+        let thresholds = CostThresholds {
+            warn_points: 10,
+            warn_memory: usize::MAX,
+            max_points: Some(20),
+            max_memory: None,
+        };
+
+        let estimate = estimate(&Schematic::new(), tran("0.25"), &thresholds).unwrap();
+
+        assert!(estimate.refused);
+        assert!(matches!(
+            estimate.warnings.as_slice(),
+            [CostWarning::TooManyPoints(33)]
+        ));
+    }
+
+    #[test]
+    fn malformed_configs_are_left_to_fail_when_run() {
+        assert!(estimate(&Schematic::new(), tran("fast"), &CostThresholds::default()).is_none());
+    }
+
+    #[test]
+    fn transients_stepping_over_a_source_are_warned_about() {
+        let estimate = estimate(&sine_source(), tran("0.25"), &CostThresholds::default()).unwrap();
+
+        // The node, the source current and the scale
+        assert_eq!(estimate.vectors, 3);
+        assert!(estimate.warnings.iter().any(|warning| matches!(
+            warning,
+            CostWarning::CoarseTimestep { max_step, feature }
+                if *max_step == 0.25 && (*feature - 1e-3).abs() < 1e-12
+        )));
+        assert!(estimate.suggestion.is_some());
+    }
+}
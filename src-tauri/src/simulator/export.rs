@@ -0,0 +1,270 @@
+use super::{
+    result_store::{StoredPlot, StoredSimulation, StoredVector},
+    simulation_data::VectorKind,
+    simulation_result::SimulationResult,
+    simulator_error::SimulatorError,
+};
+
+/// How complex vectors are written, each part in its own column. Phase is in degrees
+#[derive(Debug, Clone, serde::Deserialize)]
+pub enum ComplexFormat {
+    RealImaginary,
+    MagnitudePhase,
+}
+
+impl ComplexFormat {
+    fn get_headers(&self, name: &str) -> [String; 2] {
+        match self {
+            ComplexFormat::RealImaginary => [format!("{} (re)", name), format!("{} (im)", name)],
+            ComplexFormat::MagnitudePhase => {
+                [format!("{} (mag)", name), format!("{} (phase)", name)]
+            }
+        }
+    }
+
+    fn split(&self, c_real: f64, c_imag: f64) -> (f64, f64) {
+        match self {
+            ComplexFormat::RealImaginary => (c_real, c_imag),
+            ComplexFormat::MagnitudePhase => {
+                (c_real.hypot(c_imag), c_imag.atan2(c_real).to_degrees())
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, serde::Serialize)]
+#[serde(untagged)]
+enum ExportedValues {
+    Real {
+        values: Vec<f64>,
+    },
+    RealImaginary {
+        real: Vec<f64>,
+        imag: Vec<f64>,
+    },
+    MagnitudePhase {
+        magnitude: Vec<f64>,
+        phase: Vec<f64>,
+    },
+}
+
+#[derive(Debug, Clone, serde::Serialize)]
+struct ExportedVector {
+    name: String,
+    kind: VectorKind,
+    #[serde(flatten)]
+    values: ExportedValues,
+}
+
+impl ExportedVector {
+    fn new(vector: &StoredVector, complex_format: &ComplexFormat) -> ExportedVector {
+        let values = match (&vector.imag, complex_format) {
+            (None, _) => ExportedValues::Real {
+                values: vector.real.clone(),
+            },
+            (Some(imag), ComplexFormat::RealImaginary) => ExportedValues::RealImaginary {
+                real: vector.real.clone(),
+                imag: imag.clone(),
+            },
+            (Some(_), ComplexFormat::MagnitudePhase) => {
+                let (magnitude, phase) = (0..vector.get_length())
+                    .filter_map(|index| vector.get(index))
+                    .map(|(c_real, c_imag)| complex_format.split(c_real, c_imag))
+                    .unzip();
+
+                ExportedValues::MagnitudePhase { magnitude, phase }
+            }
+        };
+
+        ExportedVector {
+            name: vector.name.clone(),
+            kind: vector.kind.clone(),
+            values,
+        }
+    }
+}
+
+#[derive(Debug, Clone, serde::Serialize)]
+struct ExportedPlot {
+    name: String,
+    scale: Option<String>,
+    vectors: Vec<ExportedVector>,
+}
+
+#[derive(Debug, Clone, serde::Serialize)]
+struct ExportedSimulation<'a> {
+    id: &'a str,
+    netlist: &'a str,
+    plots: Vec<ExportedPlot>,
+    results: Vec<&'a SimulationResult>,
+}
+
+/// The plot with its scale in the first column and a column per vector after it,
+/// two for complex ones. Shorter vectors leave their cells empty
+pub fn plot_to_csv(plot: &StoredPlot, complex_format: &ComplexFormat) -> String {
+    let mut vectors: Vec<&StoredVector> = plot.vectors.iter().collect();
+
+    if let Some(scale) = plot.scale {
+        let scale = vectors.remove(scale);
+        vectors.insert(0, scale);
+    }
+
+    let header: Vec<String> = vectors
+        .iter()
+        .enumerate()
+        .flat_map(|(index, vector)| match (vector.is_complex(), index) {
+            // Frequency comes as a complex vector, only its real part means anything
+            (true, 0) if plot.scale.is_some() => vec![vector.name.clone()],
+            (true, _) => complex_format.get_headers(&vector.name).to_vec(),
+            (false, _) => vec![vector.name.clone()],
+        })
+        .collect();
+
+    let mut csv = header.join(",");
+    csv.push('\n');
+
+    let rows = vectors
+        .iter()
+        .map(|vector| vector.get_length())
+        .max()
+        .unwrap_or_default();
+
+    for row in 0..rows {
+        let cells: Vec<String> = vectors
+            .iter()
+            .enumerate()
+            .flat_map(|(index, vector)| {
+                let value = vector.get(row);
+
+                match (vector.is_complex(), index, value) {
+                    (true, 0, Some((c_real, _))) if plot.scale.is_some() => {
+                        vec![c_real.to_string()]
+                    }
+                    (true, 0, None) if plot.scale.is_some() => vec![String::default()],
+                    (true, _, Some((c_real, c_imag))) => {
+                        let (first, second) = complex_format.split(c_real, c_imag);
+                        vec![first.to_string(), second.to_string()]
+                    }
+                    (true, _, None) => vec![String::default(), String::default()],
+                    (false, _, Some((c_real, _))) => vec![c_real.to_string()],
+                    (false, _, None) => vec![String::default()],
+                }
+            })
+            .collect();
+
+        csv.push_str(&cells.join(","));
+        csv.push('\n');
+    }
+
+    csv
+}
+
+/// Every plot of the simulation with the netlist that produced it and its results
+pub fn simulation_to_json(
+    id: &str,
+    simulation: &StoredSimulation,
+    results: Vec<&SimulationResult>,
+    complex_format: &ComplexFormat,
+) -> Result<String, SimulatorError> {
+    let exported = ExportedSimulation {
+        id,
+        netlist: &simulation.netlist,
+        plots: simulation
+            .plots
+            .iter()
+            .map(|plot| ExportedPlot {
+                name: plot.name.clone(),
+                scale: plot.get_scale().map(|scale| scale.name.clone()),
+                vectors: plot
+                    .vectors
+                    .iter()
+                    .map(|vector| ExportedVector::new(vector, complex_format))
+                    .collect(),
+            })
+            .collect(),
+        results,
+    };
+
+    serde_json::to_string_pretty(&exported).map_err(|_| SimulatorError::FailedToExportResults)
+}
+
+#[cfg(test)]
+mod tests {
+    use num_complex::Complex64;
+
+    use crate::paprika::ngspice::types::PkVectorinfo;
+
+    use super::*;
+
+    fn ac_plot() -> StoredPlot {
+        StoredPlot::new(
+            "ac1",
+            &[
+                PkVectorinfo::from_complex(
+                    "v(out)",
+                    vec![Complex64::new(3.0, 4.0), Complex64::new(0.0, 1.0)],
+                ),
+                PkVectorinfo::from_complex(
+                    "frequency",
+                    vec![Complex64::new(10.0, 0.0), Complex64::new(100.0, 0.0)],
+                ),
+            ],
+        )
+    }
+
+    #[test]
+    fn csv_starts_with_the_scale_and_leaves_missing_cells_empty() {
+        let plot = StoredPlot::new(
+            "tran1",
+            &[
+                PkVectorinfo::from_real("v(out)", vec![1.0]),
+                PkVectorinfo::from_real("time", vec![0.0, 0.5]),
+            ],
+        );
+
+        assert_eq!(
+            plot_to_csv(&plot, &ComplexFormat::RealImaginary),
+            "time,v(out)\n0,1\n0.5,\n"
+        );
+    }
+
+    #[test]
+    fn csv_splits_complex_vectors_but_not_the_frequency() {
+        let plot = ac_plot();
+
+        assert_eq!(
+            plot_to_csv(&plot, &ComplexFormat::RealImaginary),
+            "frequency,v(out) (re),v(out) (im)\n10,3,4\n100,0,1\n"
+        );
+        assert_eq!(
+            plot_to_csv(&plot, &ComplexFormat::MagnitudePhase),
+            "frequency,v(out) (mag),v(out) (phase)\n10,5,53.13010235415598\n100,1,90\n"
+        );
+    }
+
+    #[test]
+    fn json_keeps_the_netlist_and_the_parts_of_every_vector() {
+        let simulation = StoredSimulation {
+            netlist: "* netlist".to_owned(),
+            plots: vec![ac_plot()],
+        };
+
+        let json =
+            simulation_to_json("ac", &simulation, vec![], &ComplexFormat::MagnitudePhase).unwrap();
+        let exported: serde_json::Value = serde_json::from_str(&json).unwrap();
+
+        assert_eq!(exported["id"], "ac");
+        assert_eq!(exported["netlist"], "* netlist");
+
+        let plot = &exported["plots"][0];
+        assert_eq!(plot["name"], "ac1");
+        assert_eq!(plot["scale"], "frequency");
+        assert_eq!(plot["vectors"][0]["name"], "v(out)");
+        assert_eq!(plot["vectors"][0]["kind"], "Voltage");
+        assert_eq!(
+            plot["vectors"][0]["magnitude"],
+            serde_json::json!([5.0, 1.0])
+        );
+        assert_eq!(plot["vectors"][0]["phase"][1], 90.0);
+    }
+}
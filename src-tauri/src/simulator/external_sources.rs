@@ -0,0 +1,44 @@
+use std::{fmt, sync::Arc};
+
+/// Values of the sources set as external in the schematic, computed while ngspice runs
+/// instead of being fixed in the netlist. Lets a digital controller or a script drive
+/// the analog circuit it is co-simulated with.
+///
+/// ngspice may ask for the same time point several times and goes back in time when it
+/// rejects a step, so values are best held between the times `next_sync_time` returns
+pub trait ExternalSources: Send {
+    /// Voltage of the source `name` at `time`, in seconds, 0 for the operating point
+    fn voltage(&mut self, name: &str, time: f64) -> f64;
+
+    /// Current of the source `name` at `time`, in seconds, 0 for the operating point
+    fn current(&mut self, name: &str, time: f64) -> f64;
+
+    /// Next time the sources have to be seen at, like the next controller sample.
+    /// ngspice shortens its steps to land on it, None lets it pick them
+    fn next_sync_time(&mut self, _time: f64) -> Option<f64> {
+        None
+    }
+}
+
+/// Builds the external sources of each simulation from its id, so every run starts
+/// from a fresh controller
+#[derive(Clone)]
+pub struct ExternalSourcesFactory(Arc<dyn Fn(&str) -> Box<dyn ExternalSources> + Send + Sync>);
+
+impl ExternalSourcesFactory {
+    pub fn new(
+        build: impl Fn(&str) -> Box<dyn ExternalSources> + Send + Sync + 'static,
+    ) -> ExternalSourcesFactory {
+        ExternalSourcesFactory(Arc::new(build))
+    }
+
+    pub fn build(&self, simulation_id: &str) -> Box<dyn ExternalSources> {
+        (self.0)(simulation_id)
+    }
+}
+
+impl fmt::Debug for ExternalSourcesFactory {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("ExternalSourcesFactory")
+    }
+}
@@ -1,19 +1,22 @@
 use super::paprika::{ngspice::types::*, spice::manager::SpiceManager};
 use colored::Colorize;
 use std::{
-    collections::VecDeque,
+    collections::{HashMap, VecDeque},
     sync::{Arc, Mutex, RwLock},
 };
-use tauri::ipc::Channel;
 
 use crate::simulator::{
-    commands::SecondaryThreadStatus,
+    orchestrator::SecondaryThreadStatus,
     simulation_status::{SimulationStatus, SimulationStatusPayload},
 };
 
 use super::{
-    commands::SimulationThreadOrchestrator,
-    simulation_data::{SimulationData, SimulationDataPayload},
+    external_sources::{ExternalSources, ExternalSourcesFactory},
+    orchestrator::SimulationThreadOrchestrator,
+    simulation_data::{
+        SimulationData, SimulationDataPayload, SimulationSchema, SimulationSchemaPayload,
+    },
+    sink::ResultSink,
 };
 
 #[derive(Clone)]
@@ -22,112 +25,159 @@ pub struct NGGSpiceManager {
     sharedres: Arc<RwLock<VecDeque<String>>>,
     quit_flag: bool,
 
+    // -------- Co-simulation
+    external_sources_factory: Option<ExternalSourcesFactory>,
+    // Built for the ongoing simulation, shared with the clone ngspice calls back
+    external_sources: Arc<Mutex<Option<Box<dyn ExternalSources>>>>,
+
     // -------- Internal
     id: usize,
+    // Canvas nodes behind the schematic names, to map the vectors back to them
+    canvas_ids: Arc<RwLock<HashMap<String, Vec<String>>>>,
     thread_orchestrator: Arc<Mutex<SimulationThreadOrchestrator>>,
-    data_update_channel: Channel<SimulationDataPayload>,
-    status_update_channel: Channel<SimulationStatusPayload>,
+    sink: Arc<dyn ResultSink>,
 }
 
 impl NGGSpiceManager {
     pub fn new(
         id: usize,
         thread_orchestrator: Arc<Mutex<SimulationThreadOrchestrator>>,
-        data_update_channel: Channel<SimulationDataPayload>,
-        status_update_channel: Channel<SimulationStatusPayload>,
+        sink: Arc<dyn ResultSink>,
+        external_sources_factory: Option<ExternalSourcesFactory>,
     ) -> Self {
         NGGSpiceManager {
             sharedres: Arc::new(RwLock::new(VecDeque::<String>::with_capacity(10))),
             quit_flag: false,
 
-            data_update_channel,
-            status_update_channel,
+            external_sources_factory,
+            external_sources: Arc::new(Mutex::new(None)),
+
+            sink,
 
             thread_orchestrator,
             id,
+            canvas_ids: Arc::new(RwLock::new(HashMap::default())),
         }
     }
 }
 
-impl SpiceManager for NGGSpiceManager {
-    fn cb_send_char(&mut self, msg: String, id: i32) {
-        let mut arvs = self.sharedres.write().unwrap();
-        (*arvs).push_back(msg.clone());
-
-        let opt = msg.split_once(' ');
-        let (token, msgs) = match opt {
-            Some(tup) => (tup.0, tup.1),
-            None => (msg.as_str(), msg.as_str()),
-        };
-        let msgc = match token {
-            "stdout" => msgs.green(),
-            "stderr" => msgs.red(),
-            _ => msg.magenta().strikethrough(),
-        };
-        // log::info!("{}", msgc);
+impl NGGSpiceManager {
+    // The frontend stopped listening, unlike cb_ctrldexit ngspice itself is still fine
+    fn quit(&mut self) {
+        self.quit_flag = true;
     }
-    fn cb_send_stat(&mut self, msg: String, id: i32) {
-        // TODO: error handling
 
-        log::info!("{}", msg.green());
+    // The handlers below are shared by the ngspice callbacks and the worker process events
 
+    pub fn handle_status(&mut self, status: SimulationStatus) {
         let orch_guard = self.thread_orchestrator.lock().unwrap();
-        let maybe_id = orch_guard.get_thread_ongoing_simulation_id(id as usize);
+        let maybe_id = orch_guard.get_thread_ongoing_simulation_id(self.id);
         drop(orch_guard);
 
         match maybe_id {
             Some(id) => {
-                match SimulationStatus::new(&msg) {
-                    Ok(status) => {
-                        if let Err(_) = self.status_update_channel.send(SimulationStatusPayload {
-                            status,
-                            id: id.to_owned(),
-                        }) {
-                            self.cb_ctrldexit(1, true, true, 1);
-                        };
-                    }
-
-                    Err(_) => {
-                        self.cb_ctrldexit(1, true, true, 1);
-                    }
+                if let Err(_) = self
+                    .sink
+                    .send_status(SimulationStatusPayload { status, id })
+                {
+                    self.quit();
                 };
-
-                log::info!("{}", msg.blue());
             }
             None => {
                 // TODO: Handle
             }
         }
     }
-    fn cb_ctrldexit(&mut self, status: i32, is_immediate: bool, is_quit: bool, id: i32) {
-        // TODO: Safe exit and restart implementation
-        /*
-        If ngspice has been linked at runtime by dlopen/LoadLibrary (see 19.2.2),
-        the callback may close all threads, and then detach ngspice.dll by invoking
-        dlclose/FreeLibrary. The caller may then restart ngspice by another
-        loading and initialization (19.3.2.1).
-        */
+
+    pub fn set_canvas_ids(&self, canvas_ids: HashMap<String, Vec<String>>) {
+        *self.canvas_ids.write().unwrap() = canvas_ids;
+    }
+
+    pub fn has_schema(&self) -> bool {
+        let orch_guard = self.thread_orchestrator.lock().unwrap();
+        let has_schema = orch_guard.has_schema(self.id);
+        drop(orch_guard);
+
+        has_schema
+    }
+
+    // Fresh external sources for the simulation about to run
+    pub fn reset_external_sources(&self, simulation_id: &str) {
+        let mut external_sources = self.external_sources.lock().unwrap();
+        *external_sources = self
+            .external_sources_factory
+            .as_ref()
+            .map(|factory| factory.build(simulation_id));
+    }
+
+    // Everything ngspice printed since the last call
+    pub fn take_output(&self) -> Vec<String> {
+        let mut arvs = self.sharedres.write().unwrap();
+
+        arvs.drain(..).collect()
+    }
+
+    pub fn handle_exit(&mut self) {
+        self.quit_flag = true;
+
+        // The simulator thread unloads and restarts this ngspice instance
+        let mut orch_guard = self.thread_orchestrator.lock().unwrap();
+        orch_guard.set_thread_status(self.id, SecondaryThreadStatus::Panic);
+        drop(orch_guard);
+    }
+
+    pub fn handle_schema(&mut self, schema: SimulationSchema) {
+        let id = self.id;
+        let schema = schema.with_canvas_ids(&self.canvas_ids.read().unwrap());
+
+        let mut orch_guard = self.thread_orchestrator.lock().unwrap();
+        let maybe_id = orch_guard.get_thread_ongoing_simulation_id(id);
+        // Points of a previous plot go out first, the new schema does not describe them
+        let buffer = orch_guard.flush_simulation_data_buffer(id);
+        orch_guard.set_schema(id, schema.clone());
+        drop(orch_guard);
+
         log::info!(
-            "ctrldexit {}; {}; {}; {};",
-            status,
-            is_immediate,
-            is_quit,
-            id
+            "BG thread: {} schema of {} with {} vectors",
+            id,
+            schema.plot,
+            schema.vectors.len()
         );
-        self.quit_flag = true;
+
+        if let Some(simulation_id) = maybe_id {
+            if !buffer.is_empty() {
+                if let Err(_) = self.sink.send_data(SimulationDataPayload {
+                    id: simulation_id.to_owned(),
+                    data: buffer,
+                }) {
+                    self.quit();
+                }
+            }
+
+            if let Err(_) = self.sink.send_schema(SimulationSchemaPayload {
+                schema,
+                id: simulation_id,
+            }) {
+                self.quit();
+            }
+        }
     }
-    fn cb_send_init(&mut self, pkvecinfoall: PkVecinfoall, id: i32) {}
-    fn cb_send_data(&mut self, pkvecvaluesall: PkVecvaluesall, count: i32, id: i32) {
+
+    pub fn handle_data(&mut self, simulation_data: SimulationData) {
+        let id = self.id;
         let mut orch_guard = self.thread_orchestrator.lock().unwrap();
 
-        let maybe_id: Option<String> = orch_guard.get_thread_ongoing_simulation_id(id as usize);
-        let simulation_data = SimulationData::new(pkvecvaluesall);
+        let maybe_id: Option<String> = orch_guard.get_thread_ongoing_simulation_id(id);
 
         if let Some(simulation_id) = maybe_id {
-            if orch_guard.has_threshold_elapsed(id as usize, 150) {
-                orch_guard.restart_timer(id as usize);
+            orch_guard.count_simulation_point(id);
+            orch_guard.set_current_values(id, simulation_data.clone());
+            orch_guard.push_simulation_data(id, simulation_data);
 
-                let buffer = orch_guard.flush_simulation_data_buffer(id as usize);
+            if orch_guard.has_threshold_elapsed(id, 150) {
+                // Decimated to the time since the last flush, the timer restarts after
+                let buffer = orch_guard.flush_simulation_data_buffer(id);
+                orch_guard.restart_timer(id);
 
                 log::info!(
                     "BG thread: {} flushed buffer of length {}",
@@ -135,22 +185,24 @@ impl SpiceManager for NGGSpiceManager {
                     buffer.len()
                 );
 
-                if let Err(_) = self.data_update_channel.send(SimulationDataPayload {
+                if let Err(_) = self.sink.send_data(SimulationDataPayload {
                     id: simulation_id,
                     data: buffer,
                 }) {
                     drop(orch_guard);
-                    self.cb_ctrldexit(1, true, true, 1);
+                    self.quit();
                 }
             } else {
-                orch_guard.push_simulation_data(id as usize, simulation_data);
                 drop(orch_guard);
             }
         } else {
             drop(orch_guard);
         }
     }
-    fn cb_bgt_state(&mut self, is_fin: bool, id: i32) {
+
+    pub fn handle_bgt_state(&mut self, is_fin: bool) {
+        let id = self.id;
+
         log::info!(
             "BG thread for {} is {};",
             id,
@@ -159,9 +211,58 @@ impl SpiceManager for NGGSpiceManager {
 
         if is_fin {
             let mut orch_guard = self.thread_orchestrator.lock().unwrap();
-            orch_guard.set_thread_status(id as usize, SecondaryThreadStatus::Idle);
-            let maybe_id = orch_guard.get_thread_ongoing_simulation_id(id as usize);
-            let buffer = orch_guard.flush_simulation_data_buffer(id as usize);
+
+            // Paused or cancelled through bg_halt, or ngspice exited,
+            // the simulator thread handles the status
+            match orch_guard.get_thread_status(id) {
+                SecondaryThreadStatus::Halted
+                | SecondaryThreadStatus::Paused
+                | SecondaryThreadStatus::Panic => {
+                    drop(orch_guard);
+                    return;
+                }
+
+                _ => {}
+            }
+
+            // Stopped at a breakpoint rather than done, it waits to be resumed or stepped
+            if let Some(time) = orch_guard.take_reached_breakpoint(id) {
+                orch_guard.set_thread_status(id, SecondaryThreadStatus::Paused);
+                let maybe_id = orch_guard.get_thread_ongoing_simulation_id(id);
+                let values = orch_guard
+                    .get_current_values(id)
+                    .zip(orch_guard.get_schema(id))
+                    .map(|(current_values, schema)| schema.name_values(&current_values))
+                    .unwrap_or_default();
+                let buffer = orch_guard.flush_simulation_data_buffer(id);
+                drop(orch_guard);
+
+                log::info!("BG thread: {} stopped at breakpoint {}", id, time);
+
+                if let Some(running_id) = maybe_id {
+                    if let Err(_) = self.sink.send_data(SimulationDataPayload {
+                        id: running_id.to_owned(),
+                        data: buffer,
+                    }) {
+                        self.quit();
+                    }
+
+                    if let Err(_) = self.sink.send_status(SimulationStatusPayload {
+                        status: SimulationStatus::Breakpoint { time, values },
+                        id: running_id.to_owned(),
+                    }) {
+                        self.quit();
+                    }
+                }
+
+                return;
+            }
+
+            orch_guard.set_thread_status(id, SecondaryThreadStatus::Idle);
+            let maybe_id = orch_guard
+                .get_thread_ongoing_simulation_id(id)
+                .filter(|running_id| !orch_guard.is_cancelled(running_id));
+            let buffer = orch_guard.flush_simulation_data_buffer(id);
             drop(orch_guard);
 
             if let Some(running_id) = maybe_id {
@@ -170,20 +271,121 @@ impl SpiceManager for NGGSpiceManager {
 
                 log::info!("BG thread: {} flushing due to termination", id,);
 
-                if let Err(_) = self.data_update_channel.send(SimulationDataPayload {
+                if let Err(_) = self.sink.send_data(SimulationDataPayload {
                     id: running_id.to_owned(),
                     data: buffer,
                 }) {
-                    self.cb_ctrldexit(1, true, true, 1);
+                    self.quit();
                 }
 
-                if let Err(_) = self.status_update_channel.send(SimulationStatusPayload {
+                if let Err(_) = self.sink.send_status(SimulationStatusPayload {
                     status: SimulationStatus::Ready,
                     id: running_id.to_owned(),
                 }) {
-                    self.cb_ctrldexit(1, true, true, 1);
+                    self.quit();
                 }
             };
         }
     }
 }
+
+impl SpiceManager for NGGSpiceManager {
+    fn cb_send_char(&mut self, msg: String, id: i32) {
+        let mut arvs = self.sharedres.write().unwrap();
+        (*arvs).push_back(msg.clone());
+
+        let opt = msg.split_once(' ');
+        let (token, msgs) = match opt {
+            Some(tup) => (tup.0, tup.1),
+            None => (msg.as_str(), msg.as_str()),
+        };
+        let msgc = match token {
+            "stdout" => msgs.green(),
+            "stderr" => msgs.red(),
+            _ => msg.magenta().strikethrough(),
+        };
+        // log::info!("{}", msgc);
+    }
+    fn cb_send_stat(&mut self, msg: String, _id: i32) {
+        // TODO: error handling
+
+        log::info!("{}", msg.green());
+
+        match SimulationStatus::new(&msg) {
+            Ok(status) => self.handle_status(status),
+            Err(_) => self.quit(),
+        };
+    }
+    fn cb_ctrldexit(&mut self, status: i32, is_immediate: bool, is_quit: bool, id: i32) {
+        // TODO: Safe exit and restart implementation
+        /*
+        If ngspice has been linked at runtime by dlopen/LoadLibrary (see 19.2.2),
+        the callback may close all threads, and then detach ngspice.dll by invoking
+        dlclose/FreeLibrary. The caller may then restart ngspice by another
+        loading and initialization (19.3.2.1).
+        */
+        log::info!(
+            "ctrldexit {}; {}; {}; {};",
+            status,
+            is_immediate,
+            is_quit,
+            id
+        );
+
+        self.handle_exit();
+    }
+    fn cb_send_init(&mut self, pkvecinfoall: PkVecinfoall, _id: i32) {
+        self.handle_schema(SimulationSchema::new(&pkvecinfoall));
+    }
+    fn cb_send_data(&mut self, pkvecvaluesall: PkVecvaluesall, _count: i32, _id: i32) {
+        if !self.has_schema() {
+            self.handle_schema(SimulationSchema::from_values(&pkvecvaluesall));
+        }
+
+        self.handle_data(SimulationData::new(pkvecvaluesall));
+    }
+    fn cb_bgt_state(&mut self, is_fin: bool, _id: i32) {
+        self.handle_bgt_state(is_fin);
+    }
+    fn cb_get_vsrc_data(&mut self, time: f64, name: String, _id: i32) -> f64 {
+        let mut external_sources = self.external_sources.lock().unwrap();
+
+        external_sources
+            .as_mut()
+            .map_or(0.0, |sources| sources.voltage(&name, time))
+    }
+    fn cb_get_isrc_data(&mut self, time: f64, name: String, _id: i32) -> f64 {
+        let mut external_sources = self.external_sources.lock().unwrap();
+
+        external_sources
+            .as_mut()
+            .map_or(0.0, |sources| sources.current(&name, time))
+    }
+    fn cb_get_sync_data(
+        &mut self,
+        time: f64,
+        delta: &mut f64,
+        _old_delta: f64,
+        _redo_step: bool,
+        _location: i32,
+        _id: i32,
+    ) -> i32 {
+        let mut external_sources = self.external_sources.lock().unwrap();
+
+        let maybe_sync_time = external_sources
+            .as_mut()
+            .and_then(|sources| sources.next_sync_time(time));
+
+        // Shortens the step so the next time point lands on the sync time
+        if let Some(sync_time) = maybe_sync_time {
+            if sync_time > time && time + *delta > sync_time {
+                *delta = sync_time - time;
+            }
+        }
+
+        0
+    }
+    fn is_synchronized(&self) -> bool {
+        self.external_sources_factory.is_some()
+    }
+}
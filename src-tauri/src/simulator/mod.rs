@@ -1,11 +1,28 @@
 pub mod circuit;
 pub mod commands;
+pub mod decimation;
+pub mod estimate;
+pub mod export;
+pub mod external_sources;
 pub mod manager;
+pub mod orchestrator;
+pub mod resource_report;
+pub mod result_cache;
+pub mod result_store;
+pub mod run_history;
+pub mod runner;
+pub mod settings;
 pub mod sharedlib;
 pub mod simulation;
 pub mod simulation_data;
+pub mod simulation_result;
 pub mod simulation_status;
 pub mod simulator;
 pub mod simulator_error;
+pub mod sink;
+pub mod sparameters;
+pub mod stability;
 pub mod unit_of_magnitude;
+pub mod waveforms;
+pub mod worker;
 use super::paprika;
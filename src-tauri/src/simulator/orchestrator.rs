@@ -0,0 +1,862 @@
+use std::{
+    collections::{HashMap, HashSet, VecDeque},
+    sync::{Arc, Condvar, Mutex, MutexGuard},
+    time::{Duration, Instant, SystemTime},
+};
+
+use super::{
+    decimation::decimate,
+    result_cache::CachedAnalysis,
+    result_store::{ResultStore, StoredSimulation},
+    simulation::{LoopInjection, SimulationConfig, SimulationLimits},
+    simulation_data::{
+        SimulationData, SimulationDataPayload, SimulationSchema, SimulationSchemaPayload,
+    },
+    simulation_result::{SimulationResult, SimulationResultPayload},
+    simulation_status::{SimulationStatus, SimulationStatusPayload},
+    simulator_error::SimulatorError,
+    sink::ResultSink,
+    sparameters::SParameterRun,
+    stability::LoopGainRun,
+    waveforms::WaveformStore,
+};
+
+#[derive(Debug, Clone)]
+enum MainThreadStatus {
+    Running,
+    Done,
+}
+
+#[derive(Debug, Clone)]
+pub enum SecondaryThreadStatus {
+    Idle,
+    Running,
+    // Stopped through bg_halt, to pause or cancel
+    Halted,
+    // Stopped by ngspice at a transient breakpoint
+    Paused,
+    Done,
+    Panic,
+}
+
+// Requests for a secondary thread, handled by its Simulator::run loop
+#[derive(Debug, Clone)]
+pub enum ThreadCommand {
+    Pause,
+    Resume,
+    Cancel,
+    // Runs a stopped transient until the given time past the current one,
+    // its tstep when None
+    Step(Option<f64>),
+    // A limit of the running simulation was reached, it has to fail with the error
+    Abort(SimulatorError),
+}
+
+// Stability and S-parameter analyses run as several simulations, with ids like {id}#voltage
+pub fn get_simulation_id(run_id: &str) -> &str {
+    run_id.split('#').next().unwrap_or(run_id)
+}
+
+fn is_part_of(run_id: &str, simulation_id: &str) -> bool {
+    run_id == simulation_id
+        || run_id
+            .strip_prefix(simulation_id)
+            .is_some_and(|suffix| suffix.starts_with('#'))
+}
+
+#[derive(Debug, Clone)]
+struct ThreadOperationInfo {
+    status: SecondaryThreadStatus,
+    ongoing_simulation: Option<String>,
+    current_timer: Option<SystemTime>,
+    simulation_data_buffer: Vec<SimulationData>,
+    pending_command: Option<ThreadCommand>,
+    limits: Option<SimulationLimits>,
+    // Run time of the ongoing simulation, pauses excluded
+    running_since: Option<Instant>,
+    run_time: Duration,
+    point_count: usize,
+    // Transient times ngspice was asked to stop at and has not reached yet
+    breakpoints: Vec<f64>,
+    // Last point ngspice sent, all the vectors at the current time
+    current_values: Option<SimulationData>,
+    // Vectors of the analysis ngspice is running, in the order of the point values
+    schema: Option<SimulationSchema>,
+}
+
+impl ThreadOperationInfo {
+    pub fn new() -> Self {
+        Self {
+            status: SecondaryThreadStatus::Idle,
+            ongoing_simulation: None,
+            current_timer: None,
+            simulation_data_buffer: Vec::default(),
+            pending_command: None,
+            limits: None,
+            running_since: None,
+            run_time: Duration::ZERO,
+            point_count: 0,
+            breakpoints: Vec::default(),
+            current_values: None,
+            schema: None,
+        }
+    }
+
+    pub fn restart_timer(&mut self) {
+        self.current_timer = Some(SystemTime::now());
+    }
+
+    pub fn has_threshold_elapsed(&self, threshold: u128) -> bool {
+        if let Some(timer) = &self.current_timer {
+            if let Ok(elapsed) = timer.elapsed() {
+                if elapsed.as_millis() > threshold {
+                    log::info!("Elapsed {}", elapsed.as_millis())
+                }
+
+                return elapsed.as_millis() > threshold;
+            }
+        }
+
+        return false;
+    }
+
+    // Points that fit in the time since the last flush
+    pub fn get_display_budget(&self, points_per_second: usize) -> usize {
+        let elapsed = self
+            .current_timer
+            .and_then(|timer| timer.elapsed().ok())
+            .unwrap_or(Duration::from_secs(1));
+
+        (points_per_second as f64 * elapsed.as_secs_f64()).ceil() as usize
+    }
+
+    pub fn flush_simulation_data_buffer(&mut self) -> Vec<SimulationData> {
+        let buffer = self.simulation_data_buffer.clone();
+
+        self.simulation_data_buffer = Vec::default();
+
+        return buffer;
+    }
+
+    pub fn push_simulation_data(&mut self, simulation_data: SimulationData) {
+        self.simulation_data_buffer.push(simulation_data);
+    }
+
+    pub fn set_status(&mut self, new_status: SecondaryThreadStatus) {
+        if let Some(running_since) = self.running_since.take() {
+            self.run_time += running_since.elapsed();
+        }
+
+        if let SecondaryThreadStatus::Running = new_status {
+            self.running_since = Some(Instant::now());
+        }
+
+        self.status = new_status;
+    }
+
+    pub fn set_active_simulation(&mut self, sim_id: &str, limits: Option<SimulationLimits>) {
+        self.ongoing_simulation = Some(sim_id.to_owned());
+        self.limits = limits;
+        self.running_since = None;
+        self.run_time = Duration::ZERO;
+        self.point_count = 0;
+        self.breakpoints = Vec::default();
+        self.current_values = None;
+        self.schema = None;
+    }
+
+    pub fn set_schema(&mut self, schema: SimulationSchema) {
+        self.schema = Some(schema);
+    }
+
+    pub fn get_schema(&self) -> Option<&SimulationSchema> {
+        self.schema.as_ref()
+    }
+
+    pub fn add_breakpoint(&mut self, time: f64) {
+        self.breakpoints.push(time);
+    }
+
+    pub fn set_current_values(&mut self, simulation_data: SimulationData) {
+        self.current_values = Some(simulation_data);
+    }
+
+    pub fn get_current_values(&self) -> Option<SimulationData> {
+        self.current_values.clone()
+    }
+
+    // Value of the scale vector of the last point, the time on a transient
+    pub fn get_current_time(&self) -> Option<f64> {
+        let scale_index = self.schema.as_ref()?.get_scale_index()?;
+
+        self.current_values
+            .as_ref()?
+            .get_values()
+            .get(scale_index)
+            .map(|(c_real, _)| *c_real)
+    }
+
+    // ngspice stops right on a breakpoint, it is reached once the current time is there
+    pub fn take_reached_breakpoint(&mut self) -> Option<f64> {
+        let current_time = self.get_current_time()?;
+
+        let reached: Vec<f64> = self
+            .breakpoints
+            .iter()
+            .copied()
+            .filter(|breakpoint| *breakpoint <= current_time + breakpoint.abs() * 1e-9)
+            .collect();
+
+        self.breakpoints
+            .retain(|breakpoint| !reached.contains(breakpoint));
+
+        reached.into_iter().reduce(f64::max)
+    }
+
+    pub fn get_run_time(&self) -> Duration {
+        match self.running_since {
+            Some(running_since) => self.run_time + running_since.elapsed(),
+            None => self.run_time,
+        }
+    }
+
+    pub fn get_point_count(&self) -> usize {
+        self.point_count
+    }
+
+    // Time the running simulation has left before its timeout
+    pub fn get_time_left(&self) -> Option<Duration> {
+        let timeout = self.limits.as_ref()?.timeout?;
+
+        self.running_since?;
+
+        Some(Duration::from_millis(timeout).saturating_sub(self.get_run_time()))
+    }
+
+    // Asks the thread to abort once a limit is reached, other commands go first
+    fn abort_over_limit(&mut self, error: SimulatorError) {
+        let is_running = match self.status {
+            SecondaryThreadStatus::Running => true,
+            _ => false,
+        };
+
+        if is_running && self.pending_command.is_none() {
+            log::info!("Simulation over its limits {:?}", error);
+            self.pending_command = Some(ThreadCommand::Abort(error));
+        }
+    }
+
+    pub fn check_time_limit(&mut self) -> bool {
+        let Some(timeout) = self.limits.as_ref().and_then(|limits| limits.timeout) else {
+            return false;
+        };
+
+        if self.get_time_left() != Some(Duration::ZERO) {
+            return false;
+        }
+
+        self.abort_over_limit(SimulatorError::SimulationTimedOut(timeout));
+
+        return true;
+    }
+
+    pub fn count_point(&mut self) -> bool {
+        self.point_count += 1;
+
+        let Some(max_points) = self.limits.as_ref().and_then(|limits| limits.max_points) else {
+            return false;
+        };
+
+        if self.point_count <= max_points {
+            return false;
+        }
+
+        self.abort_over_limit(SimulatorError::PointLimitExceeded(max_points));
+
+        return true;
+    }
+
+    pub fn get_ongoing_simulation_id(&self) -> Option<String> {
+        self.ongoing_simulation.clone()
+    }
+
+    pub fn set_pending_command(&mut self, command: ThreadCommand) {
+        self.pending_command = Some(command);
+    }
+
+    pub fn take_pending_command(&mut self) -> Option<ThreadCommand> {
+        self.pending_command.take()
+    }
+
+    // Running, halted or paused without any command to handle, nothing to do until something changes
+    pub fn is_waiting(&self) -> bool {
+        let is_active = match self.status {
+            SecondaryThreadStatus::Running
+            | SecondaryThreadStatus::Halted
+            | SecondaryThreadStatus::Paused => true,
+            _ => false,
+        };
+
+        is_active && self.pending_command.is_none()
+    }
+
+    pub fn is_running_simulation(&self, simulation_id: &str) -> bool {
+        let is_active = match self.status {
+            SecondaryThreadStatus::Running
+            | SecondaryThreadStatus::Halted
+            | SecondaryThreadStatus::Paused => true,
+            _ => false,
+        };
+
+        is_active
+            && self
+                .ongoing_simulation
+                .as_ref()
+                .is_some_and(|run_id| is_part_of(run_id, simulation_id))
+    }
+}
+
+#[derive(Clone)]
+pub struct SimulationThreadOrchestrator {
+    status: MainThreadStatus,
+    thread_info: HashMap<usize, ThreadOperationInfo>,
+    // Shared by all threads, whichever is idle first takes the next simulation
+    queued_simulations: VecDeque<(String, SimulationConfig)>,
+    // Notified on every change threads may be waiting on
+    changed: Arc<Condvar>,
+    sink: Arc<dyn ResultSink>,
+    cancelled_simulations: HashSet<String>,
+    // First finished run of each stability analysis, waiting for the other injection
+    loop_gain_runs: HashMap<String, (LoopInjection, LoopGainRun)>,
+    // Finished port runs of each S-parameter analysis, by excited port
+    sparameter_runs: HashMap<String, HashMap<usize, SParameterRun>>,
+    // Every point of the simulations, the sink gets them decimated to this rate
+    waveforms: Arc<Mutex<WaveformStore>>,
+    display_points_per_second: Option<usize>,
+    // Vectors of the finished simulations, as ngspice kept them
+    results: Arc<Mutex<ResultStore>>,
+}
+
+impl SimulationThreadOrchestrator {
+    pub fn new(
+        simulations_to_run: HashMap<String, SimulationConfig>,
+        sink: Arc<dyn ResultSink>,
+        pool_size: usize,
+        display_points_per_second: Option<usize>,
+    ) -> Self {
+        let threads_needed = simulations_to_run.len().min(pool_size);
+
+        let thread_info: HashMap<usize, ThreadOperationInfo> = (0..threads_needed)
+            .map(|thread_n| (thread_n, ThreadOperationInfo::new()))
+            .collect();
+
+        Self {
+            status: MainThreadStatus::Running,
+            thread_info,
+            queued_simulations: simulations_to_run.into_iter().collect(),
+            changed: Arc::new(Condvar::new()),
+            sink,
+            cancelled_simulations: HashSet::default(),
+            loop_gain_runs: HashMap::default(),
+            sparameter_runs: HashMap::default(),
+            waveforms: Arc::new(Mutex::new(WaveformStore::default())),
+            display_points_per_second,
+            results: Arc::new(Mutex::new(ResultStore::default())),
+        }
+    }
+
+    pub fn get_waveforms(&self) -> Arc<Mutex<WaveformStore>> {
+        Arc::clone(&self.waveforms)
+    }
+
+    pub fn get_results(&self) -> Arc<Mutex<ResultStore>> {
+        Arc::clone(&self.results)
+    }
+
+    pub fn store_simulation(&mut self, run_id: &str, simulation: StoredSimulation) {
+        let mut results_guard = self.results.lock().unwrap();
+        results_guard.insert(run_id, simulation);
+        drop(results_guard);
+    }
+
+    pub fn store_result(&mut self, id: &str, result: SimulationResult) {
+        let mut results_guard = self.results.lock().unwrap();
+        results_guard.insert_result(id, result);
+        drop(results_guard);
+    }
+
+    // Blocks until the orchestrator changes and the condition no longer holds
+    pub fn wait_while<'a>(
+        orch_guard: MutexGuard<'a, SimulationThreadOrchestrator>,
+        condition: impl FnMut(&mut SimulationThreadOrchestrator) -> bool,
+    ) -> MutexGuard<'a, SimulationThreadOrchestrator> {
+        let changed = Arc::clone(&orch_guard.changed);
+
+        changed.wait_while(orch_guard, condition).unwrap()
+    }
+
+    fn notify_change(&self) {
+        self.changed.notify_all();
+    }
+
+    // Like wait_while on is_thread_waiting, but also wakes up when the simulation of the
+    // thread runs out of time, so its timeout is enforced even if ngspice sends nothing
+    pub fn wait_for_thread<'a>(
+        orch_guard: MutexGuard<'a, SimulationThreadOrchestrator>,
+        id: usize,
+    ) -> MutexGuard<'a, SimulationThreadOrchestrator> {
+        let changed = Arc::clone(&orch_guard.changed);
+        let mut orch_guard = orch_guard;
+
+        loop {
+            if let Some(thread_info) = orch_guard.thread_info.get_mut(&id) {
+                thread_info.check_time_limit();
+            }
+
+            if !orch_guard.is_thread_waiting(id) {
+                return orch_guard;
+            }
+
+            let maybe_time_left = orch_guard
+                .thread_info
+                .get(&id)
+                .and_then(|thread_info| thread_info.get_time_left());
+
+            orch_guard = match maybe_time_left {
+                Some(time_left) => changed.wait_timeout(orch_guard, time_left).unwrap().0,
+                None => changed.wait(orch_guard).unwrap(),
+            };
+        }
+    }
+
+    pub fn is_thread_waiting(&self, id: usize) -> bool {
+        if let Some(thread_info) = self.thread_info.get(&id) {
+            return thread_info.is_waiting();
+        }
+
+        return false;
+    }
+
+    pub fn finish(&mut self) {
+        self.status = MainThreadStatus::Done;
+    }
+
+    pub fn has_running_threads(&self) -> bool {
+        for (_, thread_info) in &self.thread_info {
+            match thread_info.status {
+                SecondaryThreadStatus::Running => {
+                    return true;
+                }
+
+                SecondaryThreadStatus::Halted => {
+                    return true;
+                }
+
+                SecondaryThreadStatus::Paused => {
+                    return true;
+                }
+
+                SecondaryThreadStatus::Idle => return true,
+
+                _ => {
+                    continue;
+                }
+            }
+        }
+
+        return false;
+    }
+
+    pub fn get_thread_ongoing_simulation_id(&self, id: usize) -> Option<String> {
+        if let Some(thread_info) = self.thread_info.get(&id) {
+            return thread_info.get_ongoing_simulation_id();
+        }
+
+        return None;
+    }
+
+    pub fn restart_timer(&mut self, id: usize) {
+        if let Some(thread_info) = self.thread_info.get_mut(&id) {
+            return thread_info.restart_timer();
+        }
+    }
+
+    pub fn has_threshold_elapsed(&self, id: usize, threshold: u128) -> bool {
+        if let Some(thread_info) = self.thread_info.get(&id) {
+            return thread_info.has_threshold_elapsed(threshold);
+        }
+
+        return false;
+    }
+
+    pub fn push_simulation_data(&mut self, id: usize, simulation_data: SimulationData) {
+        if let Some(thread_info) = self.thread_info.get_mut(&id) {
+            return thread_info.push_simulation_data(simulation_data);
+        }
+    }
+
+    // Keeps every point and gives back the ones to display
+    pub fn flush_simulation_data_buffer(&mut self, id: usize) -> Vec<SimulationData> {
+        if let Some(thread_info) = self.thread_info.get_mut(&id) {
+            let buffer = thread_info.flush_simulation_data_buffer();
+
+            if let Some(simulation_id) = thread_info.get_ongoing_simulation_id() {
+                let mut waveforms_guard = self.waveforms.lock().unwrap();
+                waveforms_guard.extend(&simulation_id, &buffer);
+                drop(waveforms_guard);
+            }
+
+            if let Some(points_per_second) = self.display_points_per_second {
+                let scale_index = thread_info
+                    .get_schema()
+                    .and_then(|schema| schema.get_scale_index());
+
+                return decimate(
+                    &buffer,
+                    scale_index,
+                    thread_info.get_display_budget(points_per_second),
+                );
+            }
+
+            return buffer;
+        }
+
+        return Vec::default();
+    }
+
+    pub fn get_thread_status(&self, id: usize) -> SecondaryThreadStatus {
+        self.thread_info.get(&id).unwrap().status.clone()
+    }
+
+    pub fn dequeue_simulation(&mut self) -> Option<(String, SimulationConfig)> {
+        self.queued_simulations.pop_front()
+    }
+
+    pub fn set_thread_status(&mut self, id: usize, new_status: SecondaryThreadStatus) {
+        if let Some(thread_info) = self.thread_info.get_mut(&id) {
+            thread_info.set_status(new_status);
+        }
+
+        self.notify_change();
+    }
+
+    pub fn set_active_simulation(
+        &mut self,
+        id: usize,
+        sim_id: &str,
+        limits: Option<SimulationLimits>,
+    ) {
+        if let Some(thread_info) = self.thread_info.get_mut(&id) {
+            thread_info.set_active_simulation(sim_id, limits);
+        }
+    }
+
+    pub fn add_breakpoint(&mut self, id: usize, time: f64) {
+        if let Some(thread_info) = self.thread_info.get_mut(&id) {
+            thread_info.add_breakpoint(time);
+        }
+    }
+
+    pub fn set_current_values(&mut self, id: usize, simulation_data: SimulationData) {
+        if let Some(thread_info) = self.thread_info.get_mut(&id) {
+            thread_info.set_current_values(simulation_data);
+        }
+    }
+
+    pub fn get_current_values(&self, id: usize) -> Option<SimulationData> {
+        self.thread_info
+            .get(&id)
+            .and_then(|thread_info| thread_info.get_current_values())
+    }
+
+    pub fn set_schema(&mut self, id: usize, schema: SimulationSchema) {
+        if let Some(thread_info) = self.thread_info.get_mut(&id) {
+            if let Some(simulation_id) = thread_info.get_ongoing_simulation_id() {
+                let mut waveforms_guard = self.waveforms.lock().unwrap();
+                waveforms_guard.set_schema(&simulation_id, schema.clone());
+                drop(waveforms_guard);
+            }
+
+            thread_info.set_schema(schema);
+        }
+    }
+
+    pub fn has_schema(&self, id: usize) -> bool {
+        self.thread_info
+            .get(&id)
+            .is_some_and(|thread_info| thread_info.get_schema().is_some())
+    }
+
+    pub fn get_schema(&self, id: usize) -> Option<SimulationSchema> {
+        self.thread_info
+            .get(&id)
+            .and_then(|thread_info| thread_info.get_schema())
+            .cloned()
+    }
+
+    pub fn get_current_time(&self, id: usize) -> Option<f64> {
+        self.thread_info
+            .get(&id)
+            .and_then(|thread_info| thread_info.get_current_time())
+    }
+
+    pub fn take_reached_breakpoint(&mut self, id: usize) -> Option<f64> {
+        self.thread_info
+            .get_mut(&id)
+            .and_then(|thread_info| thread_info.take_reached_breakpoint())
+    }
+
+    // Counts a point sent by ngspice, wakes the thread up once it is over the limit
+    pub fn count_simulation_point(&mut self, id: usize) {
+        if let Some(thread_info) = self.thread_info.get_mut(&id) {
+            if thread_info.count_point() {
+                self.notify_change();
+            }
+        }
+    }
+
+    // Run time, pauses excluded, and points of the thread's ongoing simulation
+    pub fn get_simulation_usage(&self, id: usize) -> (Duration, usize) {
+        match self.thread_info.get(&id) {
+            Some(thread_info) => (thread_info.get_run_time(), thread_info.get_point_count()),
+            None => (Duration::ZERO, 0),
+        }
+    }
+
+    pub fn take_pending_command(&mut self, id: usize) -> Option<ThreadCommand> {
+        if let Some(thread_info) = self.thread_info.get_mut(&id) {
+            return thread_info.take_pending_command();
+        }
+
+        return None;
+    }
+
+    pub fn is_cancelled(&self, run_id: &str) -> bool {
+        self.cancelled_simulations
+            .iter()
+            .any(|simulation_id| is_part_of(run_id, simulation_id))
+    }
+
+    /// Sends a cached analysis as if its runs just finished, and keeps it like them
+    pub fn serve_cached_analysis(&mut self, cached: CachedAnalysis) {
+        for (run_id, run) in cached.runs {
+            let mut waveforms_guard = self.waveforms.lock().unwrap();
+            if let Some(schema) = &run.schema {
+                waveforms_guard.set_schema(&run_id, schema.clone());
+            }
+            waveforms_guard.extend(&run_id, &run.data);
+            drop(waveforms_guard);
+
+            let scale_index = run
+                .schema
+                .as_ref()
+                .and_then(|schema| schema.get_scale_index());
+            let data = match self.display_points_per_second {
+                Some(points_per_second) => decimate(&run.data, scale_index, points_per_second),
+                None => run.data,
+            };
+
+            if let Some(schema) = run.schema {
+                if let Err(_) = self.sink.send_schema(SimulationSchemaPayload {
+                    schema,
+                    id: run_id.clone(),
+                }) {
+                    log::error!("Failed to send schema for simulation {}", run_id);
+                }
+            }
+
+            if let Err(_) = self.sink.send_data(SimulationDataPayload {
+                data,
+                id: run_id.clone(),
+            }) {
+                log::error!("Failed to send data for simulation {}", run_id);
+            }
+
+            self.store_simulation(&run_id, run.simulation);
+            self.emit_status(&run_id, SimulationStatus::Ready);
+        }
+
+        for (id, result) in cached.results {
+            self.store_result(&id, result.clone());
+
+            if let Err(_) = self.sink.send_result(SimulationResultPayload {
+                result,
+                id: id.clone(),
+            }) {
+                log::error!("Failed to send results for simulation {}", id);
+            }
+        }
+    }
+
+    fn emit_status(&self, simulation_id: &str, status: SimulationStatus) {
+        if let Err(_) = self.sink.send_status(SimulationStatusPayload {
+            status,
+            id: simulation_id.to_owned(),
+        }) {
+            log::error!("Failed to send status for simulation {}", simulation_id);
+        }
+    }
+
+    // Queued work is dropped right away, threads running it halt ngspice on their next loop
+    fn stop_simulation(&mut self, simulation_id: &str, status: SimulationStatus) {
+        self.cancelled_simulations.insert(simulation_id.to_owned());
+
+        self.loop_gain_runs.remove(simulation_id);
+        self.sparameter_runs.remove(simulation_id);
+
+        self.queued_simulations
+            .retain(|(run_id, _)| !is_part_of(run_id, simulation_id));
+
+        for (_, thread_info) in self.thread_info.iter_mut() {
+            if thread_info.is_running_simulation(simulation_id) {
+                thread_info.set_pending_command(ThreadCommand::Cancel);
+            }
+        }
+
+        self.notify_change();
+        self.emit_status(simulation_id, status);
+    }
+
+    pub fn cancel_simulation(&mut self, simulation_id: &str) {
+        self.stop_simulation(simulation_id, SimulationStatus::Cancelled);
+    }
+
+    // The other runs of a failed stability or S-parameter analysis are useless, they are stopped too
+    pub fn fail_simulation(&mut self, run_id: &str, error: SimulatorError) {
+        let simulation_id = get_simulation_id(run_id);
+
+        self.stop_simulation(simulation_id, SimulationStatus::Failed(error));
+    }
+
+    // Marks a thread that can no longer simulate as done, the queue is left to the
+    // other threads and only failed once none of them is left
+    pub fn fail_thread_simulations(&mut self, id: usize, error: SimulatorError) {
+        self.set_thread_status(id, SecondaryThreadStatus::Done);
+
+        if self.has_running_threads() {
+            return;
+        }
+
+        let queued_simulations: Vec<String> = self
+            .queued_simulations
+            .drain(..)
+            .map(|(run_id, _)| run_id)
+            .collect();
+
+        for run_id in queued_simulations {
+            if !self.is_cancelled(&run_id) {
+                self.fail_simulation(&run_id, error.clone());
+            }
+        }
+    }
+
+    pub fn pause_simulation(&mut self, simulation_id: &str) {
+        let mut is_paused = false;
+
+        for (_, thread_info) in self.thread_info.iter_mut() {
+            if thread_info.is_running_simulation(simulation_id) {
+                thread_info.set_pending_command(ThreadCommand::Pause);
+                is_paused = true;
+            }
+        }
+
+        if is_paused {
+            self.notify_change();
+            self.emit_status(simulation_id, SimulationStatus::Paused);
+        }
+    }
+
+    // Only a halted or paused transient steps, a running one carries on
+    pub fn step_simulation(&mut self, simulation_id: &str, step: Option<f64>) {
+        for (_, thread_info) in self.thread_info.iter_mut() {
+            let is_stopped = match thread_info.status {
+                SecondaryThreadStatus::Halted | SecondaryThreadStatus::Paused => true,
+                _ => false,
+            };
+
+            if is_stopped && thread_info.is_running_simulation(simulation_id) {
+                thread_info.set_pending_command(ThreadCommand::Step(step));
+            }
+        }
+
+        self.notify_change();
+    }
+
+    // The thread carries on with its simulation after a pause or a breakpoint
+    pub fn resume_thread(&mut self, id: usize) {
+        self.set_thread_status(id, SecondaryThreadStatus::Running);
+
+        if let Some(run_id) = self.get_thread_ongoing_simulation_id(id) {
+            self.emit_status(get_simulation_id(&run_id), SimulationStatus::Running);
+        }
+    }
+
+    pub fn resume_simulation(&mut self, simulation_id: &str) {
+        for (_, thread_info) in self.thread_info.iter_mut() {
+            if thread_info.is_running_simulation(simulation_id) {
+                thread_info.set_pending_command(ThreadCommand::Resume);
+            }
+        }
+
+        self.notify_change();
+    }
+
+    // Returns the voltage and current runs once both injections of a stability analysis are done.
+    // A run finishing after the other one failed or was cancelled is dropped, it has no pair
+    pub fn complete_loop_gain_run(
+        &mut self,
+        stability_id: &str,
+        injection: LoopInjection,
+        run: LoopGainRun,
+    ) -> Option<(LoopGainRun, LoopGainRun)> {
+        if self.is_cancelled(stability_id) {
+            return None;
+        }
+
+        match self.loop_gain_runs.remove(stability_id) {
+            Some((LoopInjection::Voltage, voltage_run)) => Some((voltage_run, run)),
+            Some((LoopInjection::Current, current_run)) => Some((run, current_run)),
+            None => {
+                self.loop_gain_runs
+                    .insert(stability_id.to_owned(), (injection, run));
+
+                None
+            }
+        }
+    }
+
+    // Returns every port run, in port order, once all of them are done. Same as the loop
+    // gain, runs of a failed or cancelled analysis are dropped
+    pub fn complete_sparameter_run(
+        &mut self,
+        sparameter_id: &str,
+        excitation: usize,
+        port_count: usize,
+        run: SParameterRun,
+    ) -> Option<Vec<SParameterRun>> {
+        if self.is_cancelled(sparameter_id) {
+            return None;
+        }
+
+        let runs = self
+            .sparameter_runs
+            .entry(sparameter_id.to_owned())
+            .or_default();
+
+        runs.insert(excitation, run);
+
+        if runs.len() < port_count {
+            return None;
+        }
+
+        let mut runs = self.sparameter_runs.remove(sparameter_id)?;
+
+        (0..port_count).map(|port| runs.remove(&port)).collect()
+    }
+
+    pub fn threads_needed(&self) -> usize {
+        let threads_configured = self.thread_info.len();
+
+        return threads_configured;
+    }
+}
@@ -0,0 +1,65 @@
+use std::time::Duration;
+
+// the payload type must implement `Serialize` and `Clone`.
+#[derive(Clone, serde::Serialize)]
+pub struct ResourceReportPayload {
+    pub report: ResourceReport,
+    pub id: String,
+}
+
+/// Runtime and memory one simulation took, sent once it is done or stopped by its limits
+#[derive(Debug, Clone, Default, serde::Serialize, serde::Deserialize)]
+pub struct ResourceReport {
+    // Measured by the orchestrator in milliseconds, pauses excluded
+    pub run_time: u64,
+    pub points: usize,
+
+    // Read from the rusage output, None when ngspice did not print them
+    // Seconds
+    pub analysis_time: Option<f64>,
+    pub elapsed_time: Option<f64>,
+    // MB
+    pub max_program_size: Option<f64>,
+    pub current_program_size: Option<f64>,
+    pub accepted_timepoints: Option<u64>,
+    pub rejected_timepoints: Option<u64>,
+    pub iterations: Option<u64>,
+}
+
+// rusage prints lines like "Maximum ngspice program size =   55.375 MB."
+fn parse_rusage_line(line: &str) -> Option<(&str, &str)> {
+    let line = line.strip_prefix("stdout ").unwrap_or(line);
+    let (key, value) = line.split_once('=')?;
+
+    let value = value.split_whitespace().next()?.trim_end_matches('.');
+
+    Some((key.trim(), value))
+}
+
+impl ResourceReport {
+    pub fn new(run_time: Duration, points: usize, rusage_output: &[String]) -> ResourceReport {
+        let mut report = ResourceReport {
+            run_time: run_time.as_millis() as u64,
+            points,
+            ..ResourceReport::default()
+        };
+
+        for (key, value) in rusage_output
+            .iter()
+            .filter_map(|line| parse_rusage_line(line))
+        {
+            match key {
+                "Total analysis time (seconds)" => report.analysis_time = value.parse().ok(),
+                "Total elapsed time (seconds)" => report.elapsed_time = value.parse().ok(),
+                "Maximum ngspice program size" => report.max_program_size = value.parse().ok(),
+                "Current ngspice program size" => report.current_program_size = value.parse().ok(),
+                "Accepted timepoints" => report.accepted_timepoints = value.parse().ok(),
+                "Rejected timepoints" => report.rejected_timepoints = value.parse().ok(),
+                "Total iterations" => report.iterations = value.parse().ok(),
+                _ => {}
+            }
+        }
+
+        report
+    }
+}
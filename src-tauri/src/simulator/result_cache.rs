@@ -0,0 +1,369 @@
+use std::{
+    collections::{hash_map::DefaultHasher, HashMap},
+    fs,
+    hash::{Hash, Hasher},
+    path::{Path, PathBuf},
+};
+
+use super::{
+    circuit::schematic::Schematic,
+    orchestrator::get_simulation_id,
+    result_store::{ResultStore, StoredSimulation},
+    simulation::{Simulation, SimulationConfig},
+    simulation_data::{SimulationData, SimulationSchema},
+    simulation_result::SimulationResult,
+    sparameters::expand_sparameter_configs,
+    stability::expand_stability_configs,
+    waveforms::WaveformStore,
+};
+
+/// A run of a cached analysis, as it was streamed and as ngspice kept it
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct CachedRun {
+    pub schema: Option<SimulationSchema>,
+    pub data: Vec<SimulationData>,
+    pub simulation: StoredSimulation,
+}
+
+/// Everything an analysis produced, served again when its netlists did not change
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct CachedAnalysis {
+    // Whole key of the entry, so a hash collision is not taken for a hit
+    fingerprint: String,
+    // By run id, stability and S-parameter analyses have several
+    pub runs: HashMap<String, CachedRun>,
+    pub results: HashMap<String, SimulationResult>,
+}
+
+/// What an analysis is cached by, its runs are the ids its results are stored under
+#[derive(Debug, Clone)]
+pub struct CacheKey {
+    fingerprint: String,
+    run_ids: Vec<String>,
+}
+
+/// Results of finished analyses on disk, keyed by the netlists ngspice ran, the analysis
+/// config and the library. Re-running an unchanged circuit is served from here
+#[derive(Debug, Clone)]
+pub struct ResultCache {
+    dir: PathBuf,
+    library: String,
+}
+
+// The ngspice version, the path and size of the file stand in for it when it could
+// not be read
+fn get_library_fingerprint(version: Option<&str>, source_lib_path: &Path) -> String {
+    match version {
+        Some(version) => format!("ngspice-{}", version),
+        None => format!(
+            "{} {:?}",
+            source_lib_path.to_string_lossy(),
+            fs::metadata(source_lib_path)
+                .ok()
+                .map(|metadata| metadata.len())
+        ),
+    }
+}
+
+impl ResultCache {
+    /// `version` is what the library reports, see `sharedlib::detect_spice_version`
+    pub fn new(dir: PathBuf, version: Option<&str>, source_lib_path: &Path) -> ResultCache {
+        ResultCache {
+            dir,
+            library: get_library_fingerprint(version, source_lib_path),
+        }
+    }
+
+    /// None for analyses that are not cached: the ones that pause at breakpoints, as a
+    /// cached run would not stop there, and the ones whose netlist can not be built
+    pub fn get_key(
+        &self,
+        schematic: &Schematic,
+        analysis_id: &str,
+        config: &SimulationConfig,
+    ) -> Option<CacheKey> {
+        if config
+            .get_breakpoints()
+            .is_some_and(|breakpoints| !breakpoints.is_empty())
+        {
+            return None;
+        }
+
+        let mut runs: Vec<(String, SimulationConfig)> = expand_sparameter_configs(
+            expand_stability_configs(HashMap::from([(analysis_id.to_owned(), config.clone())])),
+        )
+        .into_iter()
+        .collect();
+        runs.sort_by(|(a, _), (b, _)| a.cmp(b));
+
+        let mut fingerprint = format!(
+            "{}\n{}\n",
+            self.library,
+            serde_json::to_string(config).ok()?
+        );
+
+        for (run_id, run_config) in runs.iter() {
+            let simulation = Simulation::from_config(run_config.clone()).ok()?;
+            let netlist = schematic.build_netlist(simulation).ok()?;
+
+            // Cached runs are sent back under the ids they ran with
+            fingerprint.push_str(run_id);
+            fingerprint.push('\n');
+            fingerprint.push_str(&netlist);
+        }
+
+        Some(CacheKey {
+            fingerprint,
+            run_ids: runs.into_iter().map(|(run_id, _)| run_id).collect(),
+        })
+    }
+
+    fn get_entry_path(&self, key: &CacheKey) -> PathBuf {
+        let mut hasher = DefaultHasher::new();
+        key.fingerprint.hash(&mut hasher);
+
+        self.dir.join(format!("{:016x}.json", hasher.finish()))
+    }
+
+    pub fn get(&self, key: &CacheKey) -> Option<CachedAnalysis> {
+        let content = fs::read_to_string(self.get_entry_path(key)).ok()?;
+        let cached: CachedAnalysis = serde_json::from_str(&content)
+            .map_err(|error| log::error!("Malformed cached results {:?}", error))
+            .ok()?;
+
+        if cached.fingerprint != key.fingerprint {
+            return None;
+        }
+
+        Some(cached)
+    }
+
+    /// Caches the analysis if all of its runs finished
+    pub fn insert(&self, key: &CacheKey, waveforms: &WaveformStore, results: &ResultStore) {
+        let mut runs: HashMap<String, CachedRun> = HashMap::default();
+
+        for run_id in key.run_ids.iter() {
+            let Some(simulation) = results.get(run_id) else {
+                return;
+            };
+
+            runs.insert(
+                run_id.to_owned(),
+                CachedRun {
+                    schema: waveforms.get_schema(run_id).cloned(),
+                    data: waveforms.get_data(run_id).unwrap_or_default().to_vec(),
+                    simulation: simulation.clone(),
+                },
+            );
+        }
+
+        // Stability and S-parameter results are sent under the analysis id
+        let results: HashMap<String, SimulationResult> = results
+            .get_results()
+            .iter()
+            .filter(|(id, _)| {
+                key.run_ids
+                    .iter()
+                    .any(|run_id| run_id == *id || get_simulation_id(run_id) == id.as_str())
+            })
+            .map(|(id, result)| (id.to_owned(), result.clone()))
+            .collect();
+
+        let cached = CachedAnalysis {
+            fingerprint: key.fingerprint.clone(),
+            runs,
+            results,
+        };
+
+        let written = fs::create_dir_all(&self.dir)
+            .and_then(|_| fs::write(self.get_entry_path(key), serde_json::to_string(&cached)?));
+
+        if let Err(error) = written {
+            log::error!("Failed to cache results {:?}", error);
+        }
+    }
+
+    pub fn clear(dir: &Path) {
+        if !dir.exists() {
+            return;
+        }
+
+        if let Err(error) = fs::remove_dir_all(dir) {
+            log::error!("Failed to clear the result cache {:?}", error);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use uuid::Uuid;
+
+    use super::super::{circuit::element::Element, unit_of_magnitude::UnitOfMagnitude};
+    use super::*;
+    use crate::common::numbers::position::Position;
+
+    fn tran(tstop: &str, breakpoints: Option<Vec<String>>) -> SimulationConfig {
+        SimulationConfig::Tran {
+            tstep: Some("1m".to_owned()),
+            tstop: Some(tstop.to_owned()),
+            tstart: None,
+            tmax: None,
+            uic: None,
+            limits: None,
+            breakpoints,
+        }
+    }
+
+    fn simulation(netlist: &str) -> StoredSimulation {
+        StoredSimulation {
+            netlist: netlist.to_owned(),
+            plots: Vec::default(),
+        }
+    }
+
+    fn temp_dir() -> PathBuf {
+        std::env::temp_dir().join(format!("gspice-cache-{}", Uuid::new_v4()))
+    }
+
+    #[test]
+    fn keys_change_with_the_config_and_the_library() {
+        let schematic = Schematic::new();
+        let cache = ResultCache::new(temp_dir(), None, Path::new("libngspice.so"));
+        let other_library = ResultCache::new(temp_dir(), None, Path::new("libngspice.so.0"));
+
+        let key = cache
+            .get_key(&schematic, "tran", &tran("5m", None))
+            .unwrap();
+        let same = cache
+            .get_key(&schematic, "tran", &tran("5m", None))
+            .unwrap();
+        let longer = cache
+            .get_key(&schematic, "tran", &tran("10m", None))
+            .unwrap();
+        let other = other_library
+            .get_key(&schematic, "tran", &tran("5m", None))
+            .unwrap();
+
+        assert_eq!(key.fingerprint, same.fingerprint);
+        assert_ne!(key.fingerprint, longer.fingerprint);
+        assert_ne!(key.fingerprint, other.fingerprint);
+        assert_eq!(key.run_ids, vec!["tran".to_owned()]);
+    }
+
+    #[test]
+    fn libraries_reporting_the_same_version_share_keys() {
+        let schematic = Schematic::new();
+        let config = tran("5m", None);
+
+        let key = ResultCache::new(temp_dir(), Some("44"), Path::new("libngspice.so"))
+            .get_key(&schematic, "tran", &config)
+            .unwrap();
+        let moved = ResultCache::new(temp_dir(), Some("44"), Path::new("libngspice.so.0"))
+            .get_key(&schematic, "tran", &config)
+            .unwrap();
+        let newer = ResultCache::new(temp_dir(), Some("45"), Path::new("libngspice.so"))
+            .get_key(&schematic, "tran", &config)
+            .unwrap();
+
+        assert_eq!(key.fingerprint, moved.fingerprint);
+        assert_ne!(key.fingerprint, newer.fingerprint);
+    }
+
+    #[test]
+    fn transients_with_breakpoints_are_not_cached() {
+        let cache = ResultCache::new(temp_dir(), None, Path::new("libngspice.so"));
+        let config = tran("5m", Some(vec!["1m".to_owned()]));
+
+        assert!(cache.get_key(&Schematic::new(), "tran", &config).is_none());
+    }
+
+    #[test]
+    fn stability_analyses_are_keyed_by_both_runs() {
+        let cache = ResultCache::new(temp_dir(), None, Path::new("libngspice.so"));
+        let config = SimulationConfig::Stability {
+            element: Some("r1".to_owned()),
+            node: Some("out".to_owned()),
+            fstart: Some("1".to_owned()),
+            fstop: Some("1k".to_owned()),
+            variation: None,
+            nx: None,
+            injection: None,
+            limits: None,
+        };
+
+        // The loop can only be broken at an element of the schematic
+        assert!(cache.get_key(&Schematic::new(), "loop", &config).is_none());
+
+        let mut schematic = Schematic::new();
+        schematic.insert_ground_alias(&"gnd".to_owned());
+        schematic.insert(Element::R(
+            "r1".to_owned(),
+            UnitOfMagnitude::Base(1000.0),
+            "out".to_owned(),
+            "gnd".to_owned(),
+            Position { x: 0, y: 0 },
+        ));
+
+        let key = cache.get_key(&schematic, "loop", &config).unwrap();
+        assert_eq!(
+            key.run_ids,
+            vec!["loop#current".to_owned(), "loop#voltage".to_owned()]
+        );
+    }
+
+    #[test]
+    fn cached_analyses_are_served_back_by_their_key() {
+        let dir = temp_dir();
+        let cache = ResultCache::new(dir.clone(), None, Path::new("libngspice.so"));
+        let key = cache
+            .get_key(&Schematic::new(), "tran", &tran("5m", None))
+            .unwrap();
+
+        assert!(cache.get(&key).is_none());
+
+        let mut results = ResultStore::default();
+        results.insert("tran", simulation("* tran"));
+        cache.insert(&key, &WaveformStore::default(), &results);
+
+        let cached = cache.get(&key).unwrap();
+        assert_eq!(cached.runs["tran"].simulation.netlist, "* tran");
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn entries_of_another_fingerprint_are_a_miss() {
+        let dir = temp_dir();
+        let cache = ResultCache::new(dir.clone(), None, Path::new("libngspice.so"));
+        let key = cache
+            .get_key(&Schematic::new(), "tran", &tran("5m", None))
+            .unwrap();
+
+        let mut results = ResultStore::default();
+        results.insert("tran", simulation("* tran"));
+        cache.insert(&key, &WaveformStore::default(), &results);
+
+        // Another key landing on the same file, as with a hash collision
+        let path = cache.get_entry_path(&key);
+        let content = fs::read_to_string(&path).unwrap();
+        fs::write(&path, content.replace("5m", "6m")).unwrap();
+
+        assert!(cache.get(&key).is_none());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn analyses_missing_a_run_are_not_cached() {
+        let dir = temp_dir();
+        let cache = ResultCache::new(dir.clone(), None, Path::new("libngspice.so"));
+        let key = cache
+            .get_key(&Schematic::new(), "tran", &tran("5m", None))
+            .unwrap();
+
+        cache.insert(&key, &WaveformStore::default(), &ResultStore::default());
+
+        assert!(cache.get(&key).is_none());
+        assert!(!dir.exists());
+    }
+}
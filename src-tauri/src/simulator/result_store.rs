@@ -0,0 +1,413 @@
+use std::collections::HashMap;
+
+use super::{
+    decimation::{decimate_indices, level},
+    paprika::ngspice::types::PkVectorinfo,
+    simulation_data::VectorKind,
+    simulation_result::SimulationResult,
+};
+
+/// A vector ngspice kept once the simulation was done, by column
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct StoredVector {
+    pub name: String,
+    pub kind: VectorKind,
+    pub real: Vec<f64>,
+    // Only for complex vectors
+    pub imag: Option<Vec<f64>>,
+}
+
+impl StoredVector {
+    pub fn new(vector: &PkVectorinfo) -> StoredVector {
+        let (real, imag) = match (&vector.realdata, &vector.compdata) {
+            (Some(realdata), _) => (realdata.clone(), None),
+            (None, Some(compdata)) => (
+                compdata.iter().map(|value| value.re).collect(),
+                Some(compdata.iter().map(|value| value.im).collect()),
+            ),
+            (None, None) => (Vec::default(), None),
+        };
+
+        StoredVector {
+            name: vector.name.clone(),
+            kind: VectorKind::from_name(&vector.name),
+            real,
+            imag,
+        }
+    }
+
+    pub fn get_length(&self) -> usize {
+        self.real.len()
+    }
+
+    pub fn is_complex(&self) -> bool {
+        self.imag.is_some()
+    }
+
+    pub fn get(&self, index: usize) -> Option<(f64, f64)> {
+        let c_real = *self.real.get(index)?;
+        let c_imag = self
+            .imag
+            .as_ref()
+            .and_then(|imag| imag.get(index).copied())
+            .unwrap_or_default();
+
+        Some((c_real, c_imag))
+    }
+
+    // Time, frequency or the swept source of a dc analysis
+    fn is_scale(&self) -> bool {
+        match self.kind {
+            VectorKind::Time | VectorKind::Frequency => true,
+            _ => self.name.to_lowercase().ends_with("-sweep"),
+        }
+    }
+}
+
+/// A plot of a finished simulation, like tran1, with all its vectors
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct StoredPlot {
+    pub name: String,
+    // Vector the others are plotted against, if the plot has one
+    pub scale: Option<usize>,
+    pub vectors: Vec<StoredVector>,
+}
+
+impl StoredPlot {
+    pub fn new(name: &str, vectors: &[PkVectorinfo]) -> StoredPlot {
+        let vectors: Vec<StoredVector> = vectors.iter().map(StoredVector::new).collect();
+
+        StoredPlot {
+            name: name.to_owned(),
+            scale: vectors.iter().position(|vector| vector.is_scale()),
+            vectors,
+        }
+    }
+
+    pub fn get_scale(&self) -> Option<&StoredVector> {
+        self.scale.and_then(|scale| self.vectors.get(scale))
+    }
+
+    pub fn get_vector(&self, name: &str) -> Option<&StoredVector> {
+        self.vectors
+            .iter()
+            .find(|vector| vector.name.eq_ignore_ascii_case(name))
+    }
+
+    // Indices from the first point at or past `from` to the last one at or before `to`,
+    // plus one on each side so lines reach the edges
+    fn get_scale_range(&self, from: f64, to: f64) -> (usize, usize) {
+        let Some(scale) = self.get_scale() else {
+            return (0, 0);
+        };
+
+        let start = scale
+            .real
+            .partition_point(|value| *value < from)
+            .saturating_sub(1);
+        let end = (scale.real.partition_point(|value| *value <= to) + 1).min(scale.get_length());
+
+        (start, end.max(start))
+    }
+
+    /// Part of a vector, decimated down to `max_points`
+    pub fn get_slice(
+        &self,
+        vector: &str,
+        range: &VectorRange,
+        max_points: usize,
+    ) -> Option<VectorSlice> {
+        let vector = self.get_vector(vector)?;
+
+        let (start, end) = match range {
+            VectorRange::Index { start, end } => {
+                let end = (*end).min(vector.get_length());
+                ((*start).min(end), end)
+            }
+            VectorRange::Scale { from, to } => self.get_scale_range(*from, *to),
+        };
+
+        let indices: Vec<usize> = decimate_indices(end - start, 1, max_points, |_, index| {
+            vector
+                .get(start + index)
+                .map(|(c_real, c_imag)| level(c_real, c_imag))
+        })
+        .into_iter()
+        .map(|index| start + index)
+        .collect();
+
+        let pick = |values: &Vec<f64>| -> Vec<f64> {
+            indices
+                .iter()
+                .filter_map(|index| values.get(*index).copied())
+                .collect()
+        };
+
+        Some(VectorSlice {
+            name: vector.name.clone(),
+            scale: self.get_scale().map(|scale| pick(&scale.real)),
+            real: pick(&vector.real),
+            imag: vector.imag.as_ref().map(pick),
+            indices,
+        })
+    }
+}
+
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct StoredVectorInfo {
+    pub name: String,
+    pub kind: VectorKind,
+    pub is_complex: bool,
+    pub length: usize,
+}
+
+/// What a plot holds, without its values
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct StoredPlotInfo {
+    pub name: String,
+    pub scale: Option<String>,
+    pub vectors: Vec<StoredVectorInfo>,
+}
+
+impl StoredPlotInfo {
+    fn new(plot: &StoredPlot) -> StoredPlotInfo {
+        StoredPlotInfo {
+            name: plot.name.clone(),
+            scale: plot.get_scale().map(|scale| scale.name.clone()),
+            vectors: plot
+                .vectors
+                .iter()
+                .map(|vector| StoredVectorInfo {
+                    name: vector.name.clone(),
+                    kind: vector.kind.clone(),
+                    is_complex: vector.is_complex(),
+                    length: vector.get_length(),
+                })
+                .collect(),
+        }
+    }
+}
+
+/// Points of a vector to query, by index, end excluded, or by time or frequency
+#[derive(Debug, Clone, serde::Deserialize)]
+pub enum VectorRange {
+    Index { start: usize, end: usize },
+    Scale { from: f64, to: f64 },
+}
+
+/// Part of a vector, decimated to the resolution it was asked at
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct VectorSlice {
+    pub name: String,
+    // Index of each point in the whole vector
+    pub indices: Vec<usize>,
+    // Time or frequency of each point, if the plot has a scale
+    pub scale: Option<Vec<f64>>,
+    pub real: Vec<f64>,
+    pub imag: Option<Vec<f64>>,
+}
+
+/// What a finished simulation left, with the netlist ngspice ran for it
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct StoredSimulation {
+    pub netlist: String,
+    pub plots: Vec<StoredPlot>,
+}
+
+/// Vectors of the finished simulations of a run, by simulation id, kept in memory
+/// so they can be queried at any resolution after the streamed points are gone
+#[derive(Debug, Clone, Default, serde::Serialize, serde::Deserialize)]
+pub struct ResultStore {
+    simulations: HashMap<String, StoredSimulation>,
+    // Results read from the vectors, by the id they were sent with
+    results: HashMap<String, SimulationResult>,
+}
+
+impl ResultStore {
+    pub fn insert(&mut self, simulation_id: &str, simulation: StoredSimulation) {
+        self.simulations
+            .insert(simulation_id.to_owned(), simulation);
+    }
+
+    pub fn insert_result(&mut self, id: &str, result: SimulationResult) {
+        self.results.insert(id.to_owned(), result);
+    }
+
+    pub fn get(&self, simulation_id: &str) -> Option<&StoredSimulation> {
+        self.simulations.get(simulation_id)
+    }
+
+    pub fn get_results(&self) -> &HashMap<String, SimulationResult> {
+        &self.results
+    }
+
+    pub fn get_simulation_ids(&self) -> Vec<String> {
+        self.simulations.keys().cloned().collect()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.simulations.is_empty() && self.results.is_empty()
+    }
+
+    pub fn get_plot_infos(&self, simulation_id: &str) -> Option<Vec<StoredPlotInfo>> {
+        self.simulations
+            .get(simulation_id)
+            .map(|simulation| simulation.plots.iter().map(StoredPlotInfo::new).collect())
+    }
+
+    pub fn get_slice(
+        &self,
+        simulation_id: &str,
+        plot: &str,
+        vector: &str,
+        range: &VectorRange,
+        max_points: usize,
+    ) -> Option<VectorSlice> {
+        let plot = self
+            .simulations
+            .get(simulation_id)?
+            .plots
+            .iter()
+            .find(|stored_plot| stored_plot.name == plot)?;
+
+        plot.get_slice(vector, range, max_points)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Ten points, one per second, v(out) is twice the time
+    fn tran_plot() -> StoredPlot {
+        let time: Vec<f64> = (0..10).map(|time| time as f64).collect();
+        let out = time.iter().map(|time| time * 2.0).collect();
+
+        StoredPlot::new(
+            "tran1",
+            &[
+                PkVectorinfo::from_real("v(out)", out),
+                PkVectorinfo::from_real("time", time),
+            ],
+        )
+    }
+
+    #[test]
+    fn time_and_frequency_are_the_scale() {
+        let plot = tran_plot();
+
+        assert_eq!(plot.scale, Some(1));
+        assert_eq!(plot.get_scale().unwrap().name, "time");
+        assert!(plot.get_vector("V(OUT)").is_some());
+    }
+
+    #[test]
+    fn scale_ranges_reach_one_point_past_each_edge() {
+        let slice = tran_plot()
+            .get_slice("v(out)", &VectorRange::Scale { from: 2.5, to: 5.5 }, 100)
+            .unwrap();
+
+        assert_eq!(slice.indices, vec![2, 3, 4, 5, 6]);
+        assert_eq!(slice.scale, Some(vec![2.0, 3.0, 4.0, 5.0, 6.0]));
+        assert_eq!(slice.real, vec![4.0, 6.0, 8.0, 10.0, 12.0]);
+        assert!(slice.imag.is_none());
+    }
+
+    #[test]
+    fn scale_ranges_past_the_plot_are_clamped_to_it() {
+        let plot = tran_plot();
+
+        let after = plot
+            .get_slice(
+                "v(out)",
+                &VectorRange::Scale {
+                    from: 20.0,
+                    to: 30.0,
+                },
+                100,
+            )
+            .unwrap();
+        assert_eq!(after.indices, vec![9]);
+
+        let whole = plot
+            .get_slice(
+                "v(out)",
+                &VectorRange::Scale {
+                    from: -5.0,
+                    to: 50.0,
+                },
+                100,
+            )
+            .unwrap();
+        assert_eq!(whole.indices, (0..10).collect::<Vec<usize>>());
+    }
+
+    #[test]
+    fn index_ranges_are_clamped_to_the_vector() {
+        let slice = tran_plot()
+            .get_slice("v(out)", &VectorRange::Index { start: 8, end: 20 }, 100)
+            .unwrap();
+
+        assert_eq!(slice.indices, vec![8, 9]);
+        assert_eq!(slice.real, vec![16.0, 18.0]);
+    }
+
+    #[test]
+    fn long_ranges_are_decimated_to_the_resolution_asked() {
+        let time: Vec<f64> = (0..1000).map(|time| time as f64).collect();
+        let plot = StoredPlot::new(
+            "tran1",
+            &[
+                PkVectorinfo::from_real("time", time.clone()),
+                PkVectorinfo::from_real("v(out)", time),
+            ],
+        );
+
+        let slice = plot
+            .get_slice(
+                "v(out)",
+                &VectorRange::Index {
+                    start: 0,
+                    end: 1000,
+                },
+                20,
+            )
+            .unwrap();
+
+        assert!(slice.indices.len() <= 20);
+        assert_eq!(slice.indices.first(), Some(&0));
+        assert_eq!(slice.indices.last(), Some(&999));
+        assert_eq!(slice.scale.unwrap().len(), slice.indices.len());
+    }
+
+    #[test]
+    fn slices_are_looked_up_by_simulation_and_plot() {
+        let mut store = ResultStore::default();
+        store.insert(
+            "tran",
+            StoredSimulation {
+                netlist: String::default(),
+                plots: vec![tran_plot()],
+            },
+        );
+
+        let range = VectorRange::Index { start: 0, end: 2 };
+
+        assert!(store
+            .get_slice("tran", "tran1", "v(out)", &range, 10)
+            .is_some());
+        assert!(store
+            .get_slice("tran", "tran2", "v(out)", &range, 10)
+            .is_none());
+        assert!(store
+            .get_slice("ac", "tran1", "v(out)", &range, 10)
+            .is_none());
+        assert!(store
+            .get_slice("tran", "tran1", "v(in)", &range, 10)
+            .is_none());
+
+        let infos = store.get_plot_infos("tran").unwrap();
+        assert_eq!(infos[0].scale.as_deref(), Some("time"));
+        assert_eq!(infos[0].vectors[0].length, 10);
+    }
+}
@@ -0,0 +1,221 @@
+use std::{
+    collections::HashMap,
+    fs,
+    mem::discriminant,
+    path::{Path, PathBuf},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use uuid::Uuid;
+
+use super::{
+    orchestrator::get_simulation_id,
+    result_store::{ResultStore, VectorRange, VectorSlice},
+    simulation::SimulationConfig,
+    simulation_result::SimulationResult,
+    simulator_error::SimulatorError,
+};
+
+/// When a simulate call of a project finished and what it ran, saved next to the project
+/// with its results apart so runs can be listed without reading them
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct RunSummary {
+    pub id: String,
+    // Seconds since the unix epoch
+    pub created_at: u64,
+    pub config: HashMap<String, SimulationConfig>,
+    // Simulations that finished, with ids like {id}#voltage for stability analyses
+    pub simulations: Vec<String>,
+}
+
+/// A finished run with its results, loaded back without simulating
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct RunRecord {
+    pub summary: RunSummary,
+    pub store: ResultStore,
+}
+
+/// A run loaded back, its vectors are queried from the result store like a finished one
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct LoadedRun {
+    pub summary: RunSummary,
+    pub results: HashMap<String, SimulationResult>,
+}
+
+// project.gsp keeps its runs in project.runs
+fn get_runs_dir(project_path: &Path) -> PathBuf {
+    project_path.with_extension("runs")
+}
+
+fn get_summary_path(project_path: &Path, run_id: &str) -> PathBuf {
+    get_runs_dir(project_path).join(format!("{}.run.json", run_id))
+}
+
+fn get_results_path(project_path: &Path, run_id: &str) -> PathBuf {
+    get_runs_dir(project_path).join(format!("{}.results.json", run_id))
+}
+
+impl RunRecord {
+    pub fn new(config: HashMap<String, SimulationConfig>, store: ResultStore) -> RunRecord {
+        let created_at = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|since| since.as_secs())
+            .unwrap_or_default();
+
+        let mut simulations = store.get_simulation_ids();
+        simulations.sort();
+
+        RunRecord {
+            summary: RunSummary {
+                id: Uuid::new_v4().to_string(),
+                created_at,
+                config,
+                simulations,
+            },
+            store,
+        }
+    }
+
+    pub fn save(&self, project_path: &Path) -> Result<(), SimulatorError> {
+        fs::create_dir_all(get_runs_dir(project_path))
+            .map_err(|_| SimulatorError::FailedToSaveRun)?;
+
+        let summary =
+            serde_json::to_string(&self.summary).map_err(|_| SimulatorError::FailedToSaveRun)?;
+        let store =
+            serde_json::to_string(&self.store).map_err(|_| SimulatorError::FailedToSaveRun)?;
+
+        // Results first, a summary is only listed once they are there
+        fs::write(get_results_path(project_path, &self.summary.id), store)
+            .map_err(|_| SimulatorError::FailedToSaveRun)?;
+        fs::write(get_summary_path(project_path, &self.summary.id), summary)
+            .map_err(|_| SimulatorError::FailedToSaveRun)?;
+
+        Ok(())
+    }
+
+    pub fn load(project_path: &Path, run_id: &str) -> Result<RunRecord, SimulatorError> {
+        let not_found = || SimulatorError::RunNotFound(run_id.to_owned());
+
+        let summary =
+            fs::read_to_string(get_summary_path(project_path, run_id)).map_err(|_| not_found())?;
+        let store =
+            fs::read_to_string(get_results_path(project_path, run_id)).map_err(|_| not_found())?;
+
+        Ok(RunRecord {
+            summary: serde_json::from_str(&summary).map_err(|_| not_found())?,
+            store: serde_json::from_str(&store).map_err(|_| not_found())?,
+        })
+    }
+}
+
+/// Saved runs of the project, latest first
+pub fn list_runs(project_path: &Path) -> Vec<RunSummary> {
+    let Ok(entries) = fs::read_dir(get_runs_dir(project_path)) else {
+        return Vec::default();
+    };
+
+    let mut runs: Vec<RunSummary> = entries
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|path| path.to_string_lossy().ends_with(".run.json"))
+        .filter_map(|path| {
+            let content = fs::read_to_string(&path).ok()?;
+
+            serde_json::from_str(&content)
+                .map_err(|error| log::error!("Malformed run {:?} {:?}", path, error))
+                .ok()
+        })
+        .collect();
+
+    runs.sort_by(|a, b| b.created_at.cmp(&a.created_at));
+
+    runs
+}
+
+/// A vector of both runs, each against its own scale as their steps may differ
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct VectorComparison {
+    pub name: String,
+    pub plot: usize,
+    pub a: VectorSlice,
+    pub b: VectorSlice,
+}
+
+/// Two runs of the same analysis, to be drawn over each other
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct RunComparison {
+    pub simulation_id: String,
+    pub a: String,
+    pub b: String,
+    pub config_changed: bool,
+    pub netlist_changed: bool,
+    pub vectors: Vec<VectorComparison>,
+}
+
+/// Pairs the vectors both runs of the simulation have, plot by plot, down to `max_points`
+pub fn compare_runs(
+    a: &RunRecord,
+    b: &RunRecord,
+    simulation_id: &str,
+    max_points: usize,
+) -> Result<RunComparison, SimulatorError> {
+    let incomparable = || SimulatorError::IncomparableRuns(simulation_id.to_owned());
+
+    let (Some(simulation_a), Some(simulation_b)) =
+        (a.store.get(simulation_id), b.store.get(simulation_id))
+    else {
+        return Err(incomparable());
+    };
+
+    // Stability and S-parameter runs are configured under the analysis id
+    let analysis_id = get_simulation_id(simulation_id);
+    let (Some(config_a), Some(config_b)) = (
+        a.summary.config.get(analysis_id),
+        b.summary.config.get(analysis_id),
+    ) else {
+        return Err(incomparable());
+    };
+
+    if discriminant(config_a) != discriminant(config_b) {
+        return Err(incomparable());
+    }
+
+    let whole = VectorRange::Index {
+        start: 0,
+        end: usize::MAX,
+    };
+
+    // ngspice numbers plots as they come, like tran1 and tran2, so they pair by order
+    let vectors = simulation_a
+        .plots
+        .iter()
+        .zip(simulation_b.plots.iter())
+        .enumerate()
+        .flat_map(|(plot, (plot_a, plot_b))| {
+            plot_a
+                .vectors
+                .iter()
+                .enumerate()
+                .filter(|(index, _)| Some(*index) != plot_a.scale)
+                .filter_map(|(_, vector)| {
+                    Some(VectorComparison {
+                        name: vector.name.clone(),
+                        plot,
+                        a: plot_a.get_slice(&vector.name, &whole, max_points)?,
+                        b: plot_b.get_slice(&vector.name, &whole, max_points)?,
+                    })
+                })
+                .collect::<Vec<VectorComparison>>()
+        })
+        .collect();
+
+    Ok(RunComparison {
+        simulation_id: simulation_id.to_owned(),
+        a: a.summary.id.clone(),
+        b: b.summary.id.clone(),
+        config_changed: serde_json::to_value(config_a).ok() != serde_json::to_value(config_b).ok(),
+        netlist_changed: simulation_a.netlist != simulation_b.netlist,
+        vectors,
+    })
+}
@@ -0,0 +1,287 @@
+use std::{
+    collections::HashMap,
+    path::PathBuf,
+    sync::{Arc, Mutex},
+    thread,
+};
+
+use super::{
+    circuit::schematic::Schematic,
+    estimate::{estimate_simulations, CostThresholds},
+    external_sources::ExternalSourcesFactory,
+    orchestrator::SimulationThreadOrchestrator,
+    result_cache::{CacheKey, ResultCache},
+    sharedlib::{detect_spice_version, get_shared_lib_path},
+    simulation::SimulationConfig,
+    simulation_status::{SimulationStatus, SimulationStatusPayload},
+    simulator::Simulator,
+    simulator_error::SimulatorError,
+    sink::ResultSink,
+    sparameters::expand_sparameter_configs,
+    stability::expand_stability_configs,
+    worker::{instance::SpiceSource, WorkerMode},
+};
+
+/// Where ngspice comes from and how many threads run it
+#[derive(Debug, Clone)]
+pub struct SimulationRunOptions {
+    pub source_lib_path: PathBuf,
+    // Per-thread copies of the source library are made here
+    pub lib_dir: PathBuf,
+    pub pool_size: usize,
+    pub worker_mode: WorkerMode,
+    // Simulations estimated over the max thresholds are failed instead of run
+    pub cost_thresholds: CostThresholds,
+    // Values of the external sources, ngspice calls back for them at every time point
+    // so only in-process threads can be co-simulated
+    pub external_sources: Option<ExternalSourcesFactory>,
+    // Points per second sent while the simulations run, all of them are kept for range
+    // queries. Every point is sent when None
+    pub display_points_per_second: Option<usize>,
+    // Unchanged analyses are served from the results cached here instead of running,
+    // nothing is cached when None
+    pub result_cache_dir: Option<PathBuf>,
+}
+
+/// A started set of simulations, shared so they can be cancelled, paused or resumed
+pub struct SimulationRun {
+    pub orchestrator: Arc<Mutex<SimulationThreadOrchestrator>>,
+    supervisor: thread::JoinHandle<()>,
+}
+
+impl SimulationRun {
+    // Blocks until every simulation thread is done
+    pub fn wait(self) {
+        if let Err(_) = self.supervisor.join() {
+            log::error!("Simulation supervisor thread panicked");
+        }
+    }
+
+    pub fn is_done(&self) -> bool {
+        self.supervisor.is_finished()
+    }
+
+    pub fn cancel(&self, simulation_id: &str) {
+        let mut orch_guard = self.orchestrator.lock().unwrap();
+        orch_guard.cancel_simulation(simulation_id);
+        drop(orch_guard);
+    }
+
+    pub fn pause(&self, simulation_id: &str) {
+        let mut orch_guard = self.orchestrator.lock().unwrap();
+        orch_guard.pause_simulation(simulation_id);
+        drop(orch_guard);
+    }
+
+    pub fn resume(&self, simulation_id: &str) {
+        let mut orch_guard = self.orchestrator.lock().unwrap();
+        orch_guard.resume_simulation(simulation_id);
+        drop(orch_guard);
+    }
+
+    // Step in seconds, the transient's tstep when None
+    pub fn step(&self, simulation_id: &str, step: Option<f64>) {
+        let mut orch_guard = self.orchestrator.lock().unwrap();
+        orch_guard.step_simulation(simulation_id, step);
+        drop(orch_guard);
+    }
+}
+
+/// Runs every analysis of the config on a pool of simulation threads, everything they
+/// produce goes to the sink. Returns right away, see `SimulationRun::wait`
+pub fn start_simulations(
+    schematic: Schematic,
+    mut config: HashMap<String, SimulationConfig>,
+    options: SimulationRunOptions,
+    sink: Arc<dyn ResultSink>,
+) -> SimulationRun {
+    let estimates = estimate_simulations(&schematic, &config, &options.cost_thresholds);
+
+    for (simulation_id, estimate) in estimates {
+        for warning in estimate.warnings.iter() {
+            log::info!("Simulation {}: {:?}", simulation_id, warning);
+        }
+
+        if !estimate.refused {
+            continue;
+        }
+
+        log::info!(
+            "Simulation {} refused, {:?} points and {:?} bytes estimated",
+            simulation_id,
+            estimate.points,
+            estimate.memory
+        );
+
+        config.remove(&simulation_id);
+
+        let error = SimulatorError::SimulationTooLarge {
+            points: estimate.points.unwrap_or_default(),
+            memory: estimate.memory.unwrap_or_default(),
+        };
+
+        if let Err(_) = sink.send_status(SimulationStatusPayload {
+            status: SimulationStatus::Failed(error),
+            id: simulation_id.clone(),
+        }) {
+            log::error!("Failed to send status for simulation {}", simulation_id);
+        }
+    }
+
+    // External sources may give other values on every run
+    let cache = options
+        .result_cache_dir
+        .clone()
+        .filter(|_| options.external_sources.is_none())
+        .map(|dir| {
+            let library_info = detect_spice_version(&options.lib_dir, &options.source_lib_path)
+                .map_err(|error| log::error!("Caching results by the library file {:?}", error))
+                .ok();
+
+            ResultCache::new(
+                dir,
+                library_info.as_ref().map(|info| info.version.as_str()),
+                &options.source_lib_path,
+            )
+        });
+
+    let mut cache_keys: Vec<CacheKey> = Vec::default();
+    let mut cached_analyses = Vec::default();
+
+    if let Some(cache) = &cache {
+        for (simulation_id, simulation_config) in config.iter() {
+            let Some(key) = cache.get_key(&schematic, simulation_id, simulation_config) else {
+                continue;
+            };
+
+            match cache.get(&key) {
+                Some(cached) => cached_analyses.push((simulation_id.to_owned(), cached)),
+                None => cache_keys.push(key),
+            }
+        }
+    }
+
+    for (simulation_id, _) in cached_analyses.iter() {
+        log::info!("Simulation {} served from the result cache", simulation_id);
+        config.remove(simulation_id);
+    }
+
+    let mut simulation_handles: Vec<thread::JoinHandle<()>> = Vec::default();
+
+    let orchestrator = Arc::new(Mutex::new(SimulationThreadOrchestrator::new(
+        expand_sparameter_configs(expand_stability_configs(config)),
+        Arc::clone(&sink),
+        options.pool_size,
+        options.display_points_per_second,
+    )));
+    let mut orchestrator_guard = orchestrator.lock().unwrap();
+    for (_, cached) in cached_analyses {
+        orchestrator_guard.serve_cached_analysis(cached);
+    }
+    let threads_needed = orchestrator_guard.threads_needed();
+    log::info!("Threads needed: {}", threads_needed);
+    drop(orchestrator_guard);
+
+    log::info!("Begin thread creation...");
+
+    for thread_n in 0..(threads_needed) {
+        log::info!("Spawns {} thread...", thread_n);
+
+        let t_orchestrator = Arc::clone(&orchestrator);
+        let t_options = options.clone();
+        let t_schematic = schematic.clone();
+        let t_sink = Arc::clone(&sink);
+
+        let handle = thread::spawn(move || {
+            let thread_id = thread_n;
+
+            log::info!("Init {} thread...", thread_id);
+
+            // A worker process has ngspice to itself, only in process threads need a copy
+            let maybe_lib_path = match t_options.worker_mode {
+                WorkerMode::InProcess => {
+                    get_shared_lib_path(&t_options.lib_dir, &t_options.source_lib_path, thread_id)
+                }
+                WorkerMode::Process => Ok(t_options.source_lib_path),
+            };
+
+            let maybe_lib_path = match (t_options.worker_mode, &t_options.external_sources) {
+                (WorkerMode::Process, Some(_)) => Err(SimulatorError::ExternalSourcesInWorker),
+                _ => maybe_lib_path,
+            };
+
+            let maybe_simulator = maybe_lib_path.and_then(|path| {
+                log::info!("Opening lib at: {:?}", path.as_os_str());
+
+                Simulator::init(
+                    thread_id,
+                    Arc::clone(&t_orchestrator),
+                    SpiceSource::Library {
+                        mode: t_options.worker_mode,
+                        path,
+                    },
+                    t_sink,
+                    t_options.external_sources,
+                )
+            });
+
+            match maybe_simulator {
+                Ok(mut simulator) => {
+                    simulator.load_schematic(t_schematic);
+
+                    simulator.run();
+
+                    simulator.close();
+                }
+
+                Err(error) => {
+                    let mut orch_guard = t_orchestrator.lock().unwrap();
+                    orch_guard.fail_thread_simulations(thread_id, error);
+                    drop(orch_guard);
+                }
+            }
+        });
+
+        simulation_handles.push(handle);
+    }
+
+    let supervisor_orchestrator = Arc::clone(&orchestrator);
+
+    // Waits for the simulation threads in the background
+    let supervisor = thread::spawn(move || {
+        let orch_guard = supervisor_orchestrator.lock().unwrap();
+        let mut orch_guard = SimulationThreadOrchestrator::wait_while(orch_guard, |orchestrator| {
+            orchestrator.has_running_threads()
+        });
+        orch_guard.finish();
+        drop(orch_guard);
+
+        log::info!("Main simulation thread is done");
+
+        for handle in simulation_handles {
+            handle.join().unwrap();
+        }
+
+        log::info!("All threads joined");
+
+        if let Some(cache) = cache {
+            let orch_guard = supervisor_orchestrator.lock().unwrap();
+            let waveforms = orch_guard.get_waveforms();
+            let results = orch_guard.get_results();
+            drop(orch_guard);
+
+            let waveforms_guard = waveforms.lock().unwrap();
+            let results_guard = results.lock().unwrap();
+            for key in cache_keys.iter() {
+                cache.insert(key, &waveforms_guard, &results_guard);
+            }
+            drop(results_guard);
+            drop(waveforms_guard);
+        }
+    });
+
+    SimulationRun {
+        orchestrator,
+        supervisor,
+    }
+}
@@ -0,0 +1,51 @@
+use std::{fs, path::PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use super::{estimate::CostThresholds, worker::WorkerMode};
+
+const SETTINGS_FILE_NAME: &str = "settings.json";
+
+/// Simulation settings of the app, saved as settings.json in its data directory
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SpiceSettings {
+    // libngspice selected by the user, bundled or system one otherwise
+    pub library_path: Option<String>,
+    // Simulation threads, one per core otherwise
+    pub pool_size: Option<usize>,
+    #[serde(default)]
+    pub worker_mode: WorkerMode,
+    #[serde(default)]
+    pub cost_thresholds: CostThresholds,
+    // Points per second streamed for live display, 2000 otherwise
+    #[serde(default)]
+    pub display_points_per_second: Option<usize>,
+    // Unchanged analyses are served from the result cache, unless false
+    #[serde(default)]
+    pub cache_results: Option<bool>,
+}
+
+impl SpiceSettings {
+    fn get_settings_path(app_data_dir: &PathBuf) -> PathBuf {
+        app_data_dir.join(SETTINGS_FILE_NAME)
+    }
+
+    pub fn load(app_data_dir: &PathBuf) -> SpiceSettings {
+        let Ok(content) = fs::read_to_string(SpiceSettings::get_settings_path(app_data_dir)) else {
+            return SpiceSettings::default();
+        };
+
+        serde_json::from_str(&content).unwrap_or_else(|error| {
+            log::error!("Malformed settings file, using defaults {:?}", error);
+            SpiceSettings::default()
+        })
+    }
+
+    pub fn save(&self, app_data_dir: &PathBuf) {
+        let content = serde_json::to_string_pretty(self).unwrap();
+
+        if let Err(error) = fs::write(SpiceSettings::get_settings_path(app_data_dir), content) {
+            log::error!("Failed to save settings {:?}", error);
+        }
+    }
+}
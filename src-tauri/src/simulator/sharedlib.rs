@@ -1,19 +1,282 @@
-use std::path::PathBuf;
+use std::{
+    ffi::OsStr,
+    fs::{self, File},
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+    thread,
+};
 
-use tauri::{AppHandle, Manager};
+use uuid::Uuid;
 
-pub fn get_shared_lib_path(app_handle: &AppHandle, thread_id: usize) -> PathBuf {
-    let path = match tauri_plugin_os::platform() {
-        "macos" => format!("lib/libngspice.{}.dylib", thread_id),
-        // TODO: add windows format
-        "windows" => format!("lib/libngspice.{}.dll", thread_id),
-        "linux" => format!("lib/libngspice.{}.so", thread_id),
+use super::{
+    paprika::{
+        ngspice::types::{PkVecinfoall, PkVecvaluesall},
+        spice::{manager::SpiceManager, spice::Spice},
+    },
+    settings::SpiceSettings,
+    simulator_error::SimulatorError,
+};
+
+// Copies are loaded under this name, ngspice keeps its state global to the loaded library
+// so every thread needs its own file
+pub const LIB_NAME: &str = "libngspice";
+
+// Id used for the copy loaded to ask ngspice for its version
+const VERSION_LIB_ID: &str = "version";
+
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct SpiceLibraryInfo {
+    pub path: String,
+    pub version: String,
+}
+
+fn get_lib_extension() -> &'static str {
+    match std::env::consts::OS {
+        "macos" => "dylib",
+        "windows" => "dll",
+        "linux" => "so",
 
         _ => panic!("Unsupported platform"),
+    }
+}
+
+// Usual install locations of a system wide ngspice shared library
+fn get_system_lib_paths() -> Vec<PathBuf> {
+    let paths: Vec<&str> = match std::env::consts::OS {
+        "macos" => vec![
+            "/opt/homebrew/lib/libngspice.dylib",
+            "/usr/local/lib/libngspice.dylib",
+            "/opt/local/lib/libngspice.dylib",
+        ],
+        "windows" => vec![
+            "C:\\Spice64\\bin-dll\\ngspice.dll",
+            "C:\\Program Files\\Spice64\\bin-dll\\ngspice.dll",
+        ],
+        "linux" => vec![
+            "/usr/lib/libngspice.so",
+            "/usr/lib/libngspice.so.0",
+            "/usr/lib/x86_64-linux-gnu/libngspice.so.0",
+            "/usr/lib/aarch64-linux-gnu/libngspice.so.0",
+            "/usr/lib64/libngspice.so.0",
+            "/usr/local/lib/libngspice.so",
+        ],
+
+        _ => vec![],
     };
 
-    return app_handle
-        .path()
-        .resolve(path, tauri::path::BaseDirectory::Resource)
-        .unwrap();
+    paths.into_iter().map(PathBuf::from).collect()
+}
+
+/// Library every thread copy is made from: the one selected by the user, the bundled one,
+/// or the first system install found
+pub fn get_source_lib_path(
+    resource_dir: Option<&Path>,
+    settings: &SpiceSettings,
+) -> Result<PathBuf, SimulatorError> {
+    if let Some(library_path) = &settings.library_path {
+        let path = PathBuf::from(library_path);
+
+        if !path.is_file() {
+            return Err(SimulatorError::SpiceLibraryNotFound);
+        }
+
+        return Ok(path);
+    }
+
+    let bundled_path = resource_dir
+        .map(|resource_dir| {
+            resource_dir
+                .join("lib")
+                .join(format!("{}.{}", LIB_NAME, get_lib_extension()))
+        })
+        .filter(|path| path.is_file());
+
+    if let Some(path) = bundled_path {
+        return Ok(path);
+    }
+
+    return get_system_lib_paths()
+        .into_iter()
+        .find(|path| path.is_file())
+        .ok_or(SimulatorError::SpiceLibraryNotFound);
+}
+
+// Copies keep the modification time of the source, any other time means it changed
+fn is_copy_outdated(source_path: &Path, copy_path: &Path) -> bool {
+    let (Ok(source), Ok(copy)) = (fs::metadata(source_path), fs::metadata(copy_path)) else {
+        return true;
+    };
+
+    if source.len() != copy.len() {
+        return true;
+    }
+
+    match (source.modified(), copy.modified()) {
+        (Ok(source_modified), Ok(copy_modified)) => source_modified != copy_modified,
+        _ => true,
+    }
+}
+
+fn copy_lib(lib_dir: &Path, source_path: &Path, copy_id: &str) -> Result<PathBuf, SimulatorError> {
+    let failed_to_copy =
+        || SimulatorError::FailedToCopySpiceLibrary(source_path.to_string_lossy().to_string());
+
+    fs::create_dir_all(lib_dir).map_err(|_| failed_to_copy())?;
+
+    let copy_path = lib_dir.join(format!("{}.{}.{}", LIB_NAME, copy_id, get_lib_extension()));
+
+    if is_copy_outdated(source_path, &copy_path) {
+        log::info!("Copying {:?} to {:?}", source_path, copy_path);
+
+        // Written apart and renamed over the copy, a thread of a previous run may still
+        // have it loaded and keeps reading the old file
+        let temp_path = lib_dir.join(format!("{}.{}.{}.tmp", LIB_NAME, copy_id, Uuid::new_v4()));
+
+        let copied = fs::copy(source_path, &temp_path)
+            .and_then(|_| {
+                let modified = fs::metadata(source_path)?.modified()?;
+                File::options()
+                    .write(true)
+                    .open(&temp_path)?
+                    .set_modified(modified)
+            })
+            .and_then(|_| fs::rename(&temp_path, &copy_path));
+
+        if let Err(error) = copied {
+            let _ = fs::remove_file(&temp_path);
+
+            // Windows does not rename over a library that is still loaded, the outdated
+            // copy still works and is refreshed once no thread holds it anymore
+            if !copy_path.exists() {
+                log::error!("{:?}", error);
+
+                return Err(failed_to_copy());
+            }
+
+            log::error!("Keeping the outdated copy {:?}: {:?}", copy_path, error);
+        }
+    }
+
+    return Ok(copy_path);
+}
+
+/// Per-thread copy of the source library, created or refreshed when needed
+pub fn get_shared_lib_path(
+    lib_dir: &Path,
+    source_path: &Path,
+    thread_id: usize,
+) -> Result<PathBuf, SimulatorError> {
+    copy_lib(lib_dir, source_path, &thread_id.to_string())
+}
+
+/// Pool size from the settings, or one thread per available core
+pub fn get_pool_size(settings: &SpiceSettings) -> usize {
+    if let Some(pool_size) = settings.pool_size.filter(|pool_size| *pool_size > 0) {
+        return pool_size;
+    }
+
+    thread::available_parallelism()
+        .map(|cores| cores.get())
+        .unwrap_or(1)
+}
+
+// Only keeps what ngspice writes to stdout, used to read the answer to `version`
+#[derive(Clone, Default)]
+struct VersionManager {
+    output: Arc<Mutex<Vec<String>>>,
+}
+
+impl SpiceManager for VersionManager {
+    fn cb_send_char(&mut self, msg: String, _id: i32) {
+        if let Some(line) = msg.strip_prefix("stdout ") {
+            self.output.lock().unwrap().push(line.to_owned());
+        }
+    }
+    fn cb_send_stat(&mut self, _msg: String, _id: i32) {}
+    fn cb_ctrldexit(&mut self, _status: i32, _is_immediate: bool, _is_quit: bool, _id: i32) {}
+    fn cb_send_data(&mut self, _pkvecvaluesall: PkVecvaluesall, _count: i32, _id: i32) {}
+    fn cb_send_init(&mut self, _pkvecinfoall: PkVecinfoall, _id: i32) {}
+    fn cb_bgt_state(&mut self, _is_fin: bool, _id: i32) {}
+}
+
+// `version` prints a banner like "** ngspice-42 : Circuit level simulation program"
+fn parse_version(output: &[String]) -> Option<String> {
+    output.iter().find_map(|line| {
+        line.split_whitespace()
+            .find_map(|word| word.strip_prefix("ngspice-"))
+            .map(|version| version.to_owned())
+    })
+}
+
+/// Loads a separate copy of the library, so threads simulating with it are not disturbed,
+/// and asks ngspice for its version
+pub fn detect_spice_version(
+    lib_dir: &Path,
+    source_path: &Path,
+) -> Result<SpiceLibraryInfo, SimulatorError> {
+    let lib_path = copy_lib(lib_dir, source_path, VERSION_LIB_ID)?;
+    let manager = VersionManager::default();
+
+    let (spice, library) = Spice::init(OsStr::new(lib_path.as_os_str()), manager.clone(), 0)
+        .map_err(|error| {
+            log::error!("Failed to load ngspice for version detection {:?}", error);
+            SimulatorError::SpiceFailedToLoad(source_path.to_string_lossy().to_string())
+        })?;
+
+    spice.command("version");
+
+    let output = manager.output.lock().unwrap().clone();
+
+    drop(spice);
+    if let Err(error) = library.close() {
+        log::error!("Failed to unload ngspice {:?}", error);
+    }
+
+    let version = parse_version(&output).ok_or(SimulatorError::UnknownSpiceVersion(
+        source_path.to_string_lossy().to_string(),
+    ))?;
+
+    return Ok(SpiceLibraryInfo {
+        path: source_path.to_string_lossy().to_string(),
+        version,
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, SystemTime};
+
+    use super::*;
+
+    #[test]
+    fn copies_are_only_refreshed_when_the_source_changes() {
+        let dir = std::env::temp_dir().join(format!("gspice-sharedlib-{}", Uuid::new_v4()));
+        let source_path = dir.join("source");
+        let lib_dir = dir.join("lib");
+
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(&source_path, b"first").unwrap();
+
+        let copy_path = copy_lib(&lib_dir, &source_path, "0").unwrap();
+        assert_eq!(fs::read(&copy_path).unwrap(), b"first");
+        assert!(!is_copy_outdated(&source_path, &copy_path));
+
+        // Same size, only the modification time tells them apart
+        fs::write(&source_path, b"again").unwrap();
+        File::options()
+            .write(true)
+            .open(&source_path)
+            .unwrap()
+            .set_modified(SystemTime::now() - Duration::from_secs(3600))
+            .unwrap();
+        assert!(is_copy_outdated(&source_path, &copy_path));
+
+        copy_lib(&lib_dir, &source_path, "0").unwrap();
+        assert_eq!(fs::read(&copy_path).unwrap(), b"again");
+
+        // No temporary file is left next to the copy
+        assert_eq!(fs::read_dir(&lib_dir).unwrap().count(), 1);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
 }
@@ -109,6 +109,103 @@ impl Display for SensitivityAnalysisType {
     }
 }
 
+#[derive(serde::Deserialize, serde::Serialize, Debug, Clone)]
+pub enum LoopInjection {
+    Voltage,
+    Current,
+}
+
+impl Display for LoopInjection {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                LoopInjection::Voltage => "voltage",
+                LoopInjection::Current => "current",
+            }
+        )
+    }
+}
+
+#[derive(serde::Deserialize, serde::Serialize, Debug, Clone)]
+pub struct SParameterPortConfig {
+    pub node: String,
+    // Defaults to GND
+    pub reference: Option<String>,
+    // Defaults to 50 ohm
+    pub impedance: Option<String>,
+}
+
+impl SParameterPortConfig {
+    // Persisted as node:reference:impedance, missing values are left empty
+    pub fn values_to_string(&self) -> String {
+        format!(
+            "{}:{}:{}",
+            self.node,
+            self.reference.clone().unwrap_or_default(),
+            self.impedance.clone().unwrap_or_default()
+        )
+    }
+
+    pub fn from_string(formatted: &str) -> Result<SParameterPortConfig, SimulatorError> {
+        let mut values = formatted
+            .split(":")
+            .map(|value| (!value.is_empty()).then(|| value.to_owned()));
+
+        if let Some(Some(node)) = values.next() {
+            return Ok(SParameterPortConfig {
+                node,
+                reference: values.next().flatten(),
+                impedance: values.next().flatten(),
+            });
+        }
+
+        Err(SimulatorError::MalformedSimulationConfig("Sp".to_owned()))
+    }
+}
+
+/// Stops a simulation that runs away, e.g. a transient with a far too small tstep
+#[derive(serde::Deserialize, serde::Serialize, Debug, Clone, Default, PartialEq)]
+pub struct SimulationLimits {
+    // Wall-clock time in milliseconds, time spent paused is not counted
+    pub timeout: Option<u64>,
+    // Points ngspice may send before the simulation is stopped
+    pub max_points: Option<usize>,
+}
+
+impl SimulationLimits {
+    // Persisted as timeout max_points, missing values are written as -
+    pub fn values_to_string(&self) -> String {
+        let format_value = |value: Option<String>| value.unwrap_or("-".to_owned());
+
+        format!(
+            "{} {}",
+            format_value(self.timeout.map(|timeout| timeout.to_string())),
+            format_value(self.max_points.map(|max_points| max_points.to_string()))
+        )
+    }
+
+    pub fn from_string(formatted: &str) -> Result<SimulationLimits, SimulatorError> {
+        let malformed = || SimulatorError::MalformedSimulationConfig("Limits".to_owned());
+
+        let [timeout, max_points] = formatted.split_whitespace().collect::<Vec<&str>>()[..] else {
+            return Err(malformed());
+        };
+
+        Ok(SimulationLimits {
+            timeout: match timeout {
+                "-" => None,
+                timeout => Some(timeout.parse().map_err(|_| malformed())?),
+            },
+            max_points: match max_points {
+                "-" => None,
+                max_points => Some(max_points.parse().map_err(|_| malformed())?),
+            },
+        })
+    }
+}
+
 #[derive(serde::Deserialize, serde::Serialize, Debug, Clone)]
 pub enum SimulationConfig {
     Tran {
@@ -117,13 +214,19 @@ pub enum SimulationConfig {
         tstart: Option<String>,
         tmax: Option<String>,
         uic: Option<bool>,
+        limits: Option<SimulationLimits>,
+        // Times the run pauses at, with the values of every vector sent
+        breakpoints: Option<Vec<String>>,
+    },
+    Op {
+        limits: Option<SimulationLimits>,
     },
-    Op {},
     Ac {
         fstart: Option<String>,
         fstop: Option<String>,
         variation: Option<FrequencyVariation>,
         nx: Option<i32>,
+        limits: Option<SimulationLimits>,
     },
     Dc {
         srcnam: Option<String>,
@@ -135,6 +238,7 @@ pub enum SimulationConfig {
         start2: Option<String>,
         stop2: Option<String>,
         incr2: Option<String>,
+        limits: Option<SimulationLimits>,
     },
     Disto {
         fstart: Option<String>,
@@ -142,6 +246,7 @@ pub enum SimulationConfig {
         variation: Option<FrequencyVariation>,
         nx: Option<i32>,
         f2overf1: Option<f32>,
+        limits: Option<SimulationLimits>,
     },
     Noise {
         output: Option<String>,
@@ -153,6 +258,7 @@ pub enum SimulationConfig {
         fstart: Option<String>,
         fstop: Option<String>,
         pts_per_summary: Option<i32>,
+        limits: Option<SimulationLimits>,
     },
     Pz {
         node1: Option<String>,
@@ -161,6 +267,7 @@ pub enum SimulationConfig {
         node4: Option<String>,
         transfer_function: Option<TransferFunction>,
         analysis_type: Option<PoleZerAnalysis>,
+        limits: Option<SimulationLimits>,
     },
     Sens {
         output_type: Option<CurrentOrVoltage>,
@@ -170,6 +277,38 @@ pub enum SimulationConfig {
         fstop: Option<String>,
         variation: Option<FrequencyVariation>,
         nx: Option<i32>,
+        limits: Option<SimulationLimits>,
+    },
+    Tf {
+        output_type: Option<CurrentOrVoltage>,
+        output: Option<String>,
+        // Defaults to GND, only used for voltage outputs
+        oref: Option<String>,
+        src: Option<String>,
+        limits: Option<SimulationLimits>,
+    },
+    Stability {
+        // Element port on the loop input side of the break
+        element: Option<String>,
+        // Net where the loop is broken
+        node: Option<String>,
+        fstart: Option<String>,
+        fstop: Option<String>,
+        variation: Option<FrequencyVariation>,
+        nx: Option<i32>,
+        // Set when the analysis is expanded into its AC runs, not persisted
+        injection: Option<LoopInjection>,
+        limits: Option<SimulationLimits>,
+    },
+    Sp {
+        fstart: Option<String>,
+        fstop: Option<String>,
+        variation: Option<FrequencyVariation>,
+        nx: Option<i32>,
+        ports: Option<Vec<SParameterPortConfig>>,
+        // Index of the driven port, set when the analysis is expanded into its AC runs
+        excitation: Option<usize>,
+        limits: Option<SimulationLimits>,
     },
 }
 
@@ -182,6 +321,7 @@ impl SimulationConfig {
                 tstart,
                 tmax,
                 uic,
+                ..
             } => {
                 let mut formatted = format!("TRAN");
 
@@ -208,13 +348,14 @@ impl SimulationConfig {
                 formatted
             }
 
-            SimulationConfig::Op {} => "OP".to_owned(),
+            SimulationConfig::Op { .. } => "OP".to_owned(),
 
             SimulationConfig::Ac {
                 fstart,
                 fstop,
                 variation,
                 nx,
+                ..
             } => {
                 let mut formatted = format!("AC");
 
@@ -246,6 +387,7 @@ impl SimulationConfig {
                 start2,
                 stop2,
                 incr2,
+                ..
             } => {
                 let mut formatted = format!("DC");
 
@@ -290,6 +432,7 @@ impl SimulationConfig {
                 variation,
                 nx,
                 f2overf1,
+                ..
             } => {
                 let mut formatted = format!("DISTO");
 
@@ -325,6 +468,7 @@ impl SimulationConfig {
                 fstart,
                 fstop,
                 pts_per_summary,
+                ..
             } => {
                 let mut formatted = format!("NOISE");
 
@@ -366,6 +510,7 @@ impl SimulationConfig {
                 node4,
                 transfer_function,
                 analysis_type,
+                ..
             } => {
                 let mut formatted = format!("PZ");
 
@@ -404,6 +549,7 @@ impl SimulationConfig {
                 fstop,
                 variation,
                 nx,
+                ..
             } => {
                 let mut formatted = format!("SENS");
 
@@ -437,6 +583,108 @@ impl SimulationConfig {
 
                 formatted
             }
+
+            SimulationConfig::Tf {
+                output_type,
+                output,
+                oref,
+                src,
+                ..
+            } => {
+                let mut formatted = format!("TF");
+
+                if let Some(output_type) = output_type {
+                    formatted.push_str(&format!(" {}", output_type));
+
+                    if let Some(output) = output {
+                        formatted.push_str(&format!(" {}", output));
+
+                        if let Some(src) = src {
+                            formatted.push_str(&format!(" {}", src));
+
+                            if let Some(oref) = oref {
+                                formatted.push_str(&format!(" {}", oref));
+                            }
+                        }
+                    }
+                }
+
+                formatted
+            }
+
+            SimulationConfig::Stability {
+                element,
+                node,
+                fstart,
+                fstop,
+                variation,
+                nx,
+                ..
+            } => {
+                let mut formatted = format!("STABILITY");
+
+                if let Some(element) = element {
+                    formatted.push_str(&format!(" {}", element));
+
+                    if let Some(node) = node {
+                        formatted.push_str(&format!(" {}", node));
+
+                        if let Some(fstart) = fstart {
+                            formatted.push_str(&format!(" {}", fstart));
+
+                            if let Some(fstop) = fstop {
+                                formatted.push_str(&format!(" {}", fstop));
+
+                                if let Some(variation) = variation {
+                                    formatted.push_str(&format!(" {}", variation));
+
+                                    if let Some(nx) = nx {
+                                        formatted.push_str(&format!(" {}", nx));
+                                    }
+                                }
+                            }
+                        }
+                    }
+                }
+
+                formatted
+            }
+
+            SimulationConfig::Sp {
+                fstart,
+                fstop,
+                variation,
+                nx,
+                ports,
+                ..
+            } => {
+                let mut formatted = format!("SP");
+
+                if let Some(fstart) = fstart {
+                    formatted.push_str(&format!(" {}", fstart));
+
+                    if let Some(fstop) = fstop {
+                        formatted.push_str(&format!(" {}", fstop));
+
+                        if let Some(variation) = variation {
+                            formatted.push_str(&format!(" {}", variation));
+
+                            if let Some(nx) = nx {
+                                formatted.push_str(&format!(" {}", nx));
+
+                                if let Some(ports) = ports {
+                                    for port in ports {
+                                        formatted
+                                            .push_str(&format!(" {}", port.values_to_string()));
+                                    }
+                                }
+                            }
+                        }
+                    }
+                }
+
+                formatted
+            }
         }
     }
 
@@ -450,6 +698,55 @@ impl SimulationConfig {
             SimulationConfig::Noise { .. } => "NOISE".to_owned(),
             SimulationConfig::Pz { .. } => "PZ".to_owned(),
             SimulationConfig::Sens { .. } => "SENS".to_owned(),
+            SimulationConfig::Tf { .. } => "TF".to_owned(),
+            SimulationConfig::Stability { .. } => "STABILITY".to_owned(),
+            SimulationConfig::Sp { .. } => "SP".to_owned(),
+        }
+    }
+
+    pub fn get_limits(&self) -> Option<SimulationLimits> {
+        match self {
+            SimulationConfig::Tran { limits, .. }
+            | SimulationConfig::Op { limits, .. }
+            | SimulationConfig::Ac { limits, .. }
+            | SimulationConfig::Dc { limits, .. }
+            | SimulationConfig::Disto { limits, .. }
+            | SimulationConfig::Noise { limits, .. }
+            | SimulationConfig::Pz { limits, .. }
+            | SimulationConfig::Sens { limits, .. }
+            | SimulationConfig::Tf { limits, .. }
+            | SimulationConfig::Stability { limits, .. }
+            | SimulationConfig::Sp { limits, .. } => limits.clone(),
+        }
+    }
+
+    pub fn set_limits(&mut self, new_limits: Option<SimulationLimits>) {
+        match self {
+            SimulationConfig::Tran { limits, .. }
+            | SimulationConfig::Op { limits, .. }
+            | SimulationConfig::Ac { limits, .. }
+            | SimulationConfig::Dc { limits, .. }
+            | SimulationConfig::Disto { limits, .. }
+            | SimulationConfig::Noise { limits, .. }
+            | SimulationConfig::Pz { limits, .. }
+            | SimulationConfig::Sens { limits, .. }
+            | SimulationConfig::Tf { limits, .. }
+            | SimulationConfig::Stability { limits, .. }
+            | SimulationConfig::Sp { limits, .. } => *limits = new_limits,
+        }
+    }
+
+    // Only transients have breakpoints
+    pub fn get_breakpoints(&self) -> Option<Vec<String>> {
+        match self {
+            SimulationConfig::Tran { breakpoints, .. } => breakpoints.clone(),
+            _ => None,
+        }
+    }
+
+    pub fn set_breakpoints(&mut self, new_breakpoints: Option<Vec<String>>) {
+        if let SimulationConfig::Tran { breakpoints, .. } = self {
+            *breakpoints = new_breakpoints;
         }
     }
 
@@ -466,10 +763,12 @@ impl SimulationConfig {
                     tstart: params.next().map(|tstart| tstart.to_owned()),
                     tmax: params.next().map(|tmax| tmax.to_owned()),
                     uic: params.next().map(|uic| uic == "uic"),
+                    limits: None,
+                    breakpoints: None,
                 });
             }
             "OP" => {
-                return Ok(SimulationConfig::Op {});
+                return Ok(SimulationConfig::Op { limits: None });
             }
             "AC" => {
                 let mut params = config.split(" ");
@@ -491,6 +790,7 @@ impl SimulationConfig {
                         })
                         .transpose()?,
                     nx: params.next().map(|nx| nx.parse().unwrap()),
+                    limits: None,
                 });
             }
             "DC" => {
@@ -506,6 +806,7 @@ impl SimulationConfig {
                     start2: params.next().map(|start2| start2.to_owned()),
                     stop2: params.next().map(|stop2| stop2.to_owned()),
                     incr2: params.next().map(|incr2| incr2.to_owned()),
+                    limits: None,
                 });
             }
             "DISTO" => {
@@ -533,6 +834,7 @@ impl SimulationConfig {
                         .transpose()
                         .map_err(|_| SimulatorError::MalformedSimulationConfig(kind.to_owned()))?,
                     f2overf1: params.next().map(|f2overf1| f2overf1.parse().unwrap()),
+                    limits: None,
                 });
             }
             "NOISE" => {
@@ -567,6 +869,7 @@ impl SimulationConfig {
                         .map(|pts_per_summary| pts_per_summary.parse::<i32>())
                         .transpose()
                         .map_err(|_| SimulatorError::MalformedSimulationConfig(kind.to_owned()))?,
+                    limits: None,
                 });
             }
             "PZ" => {
@@ -602,6 +905,7 @@ impl SimulationConfig {
                             }
                         })
                         .transpose()?,
+                    limits: None,
                 });
             }
             "SENS" => {
@@ -653,6 +957,95 @@ impl SimulationConfig {
                         .map(|nx| nx.parse::<i32>())
                         .transpose()
                         .map_err(|_| SimulatorError::MalformedSimulationConfig(kind.to_owned()))?,
+                    limits: None,
+                });
+            }
+            "TF" => {
+                let mut params = config.split(" ");
+
+                return Ok(SimulationConfig::Tf {
+                    output_type: params
+                        .next()
+                        .map(|output_type| match output_type {
+                            "V" => Ok(CurrentOrVoltage::V),
+                            "I" => Ok(CurrentOrVoltage::I),
+                            _ => {
+                                return Err(SimulatorError::MalformedSimulationConfig(
+                                    kind.to_owned(),
+                                ))
+                            }
+                        })
+                        .transpose()?,
+                    output: params.next().map(|output| output.to_owned()),
+                    src: params.next().map(|src| src.to_owned()),
+                    oref: params.next().map(|oref| oref.to_owned()),
+                    limits: None,
+                });
+            }
+            "STABILITY" => {
+                let mut params = config.split(" ");
+
+                return Ok(SimulationConfig::Stability {
+                    element: params.next().map(|element| element.to_owned()),
+                    node: params.next().map(|node| node.to_owned()),
+                    fstart: params.next().map(|fstart| fstart.to_owned()),
+                    fstop: params.next().map(|fstop| fstop.to_owned()),
+                    variation: params
+                        .next()
+                        .map(|variation| match variation {
+                            "dec" => Ok(FrequencyVariation::Dec),
+                            "oct" => Ok(FrequencyVariation::Oct),
+                            "lin" => Ok(FrequencyVariation::Lin),
+                            _ => {
+                                return Err(SimulatorError::MalformedSimulationConfig(
+                                    "Stability".to_owned(),
+                                ))
+                            }
+                        })
+                        .transpose()?,
+                    nx: params
+                        .next()
+                        .map(|nx| nx.parse::<i32>())
+                        .transpose()
+                        .map_err(|_| SimulatorError::MalformedSimulationConfig(kind.to_owned()))?,
+                    injection: None,
+                    limits: None,
+                });
+            }
+            "SP" => {
+                let mut params = config.split(" ");
+
+                let fstart = params.next().map(|fstart| fstart.to_owned());
+                let fstop = params.next().map(|fstop| fstop.to_owned());
+                let variation = params
+                    .next()
+                    .map(|variation| match variation {
+                        "dec" => Ok(FrequencyVariation::Dec),
+                        "oct" => Ok(FrequencyVariation::Oct),
+                        "lin" => Ok(FrequencyVariation::Lin),
+                        _ => {
+                            return Err(SimulatorError::MalformedSimulationConfig("Sp".to_owned()))
+                        }
+                    })
+                    .transpose()?;
+                let nx = params
+                    .next()
+                    .map(|nx| nx.parse::<i32>())
+                    .transpose()
+                    .map_err(|_| SimulatorError::MalformedSimulationConfig(kind.to_owned()))?;
+
+                let ports = params
+                    .map(|port| SParameterPortConfig::from_string(port))
+                    .collect::<Result<Vec<SParameterPortConfig>, SimulatorError>>()?;
+
+                return Ok(SimulationConfig::Sp {
+                    fstart,
+                    fstop,
+                    variation,
+                    nx,
+                    ports: (!ports.is_empty()).then(|| ports),
+                    excitation: None,
+                    limits: None,
                 });
             }
             _ => return Err(SimulatorError::MalformedSimulationConfig(kind.to_owned())),
@@ -660,6 +1053,7 @@ impl SimulationConfig {
     }
 }
 
+#[derive(Clone)]
 pub enum Simulation {
     Tran {
         tstep: Option<Unit>,
@@ -667,6 +1061,7 @@ pub enum Simulation {
         tstart: Option<Unit>,
         tmax: Option<Unit>,
         uic: Option<bool>,
+        breakpoints: Vec<Unit>,
     },
     Op,
     Ac {
@@ -720,6 +1115,53 @@ pub enum Simulation {
         variation: Option<FrequencyVariation>,
         nx: Option<i32>,
     },
+    Tf {
+        output_type: Option<CurrentOrVoltage>,
+        output: Option<String>,
+        oref: Option<String>,
+        src: Option<String>,
+    },
+    Stability {
+        element: String,
+        node: String,
+        injection: LoopInjection,
+        fstart: Option<Unit>,
+        fstop: Option<Unit>,
+        variation: Option<FrequencyVariation>,
+        nx: Option<i32>,
+    },
+    Sp {
+        fstart: Option<Unit>,
+        fstop: Option<Unit>,
+        variation: Option<FrequencyVariation>,
+        nx: Option<i32>,
+        ports: Vec<SParameterPort>,
+        excitation: usize,
+    },
+}
+
+#[derive(Clone)]
+pub struct SParameterPort {
+    pub node: String,
+    pub reference: Option<String>,
+    pub impedance: Unit,
+}
+
+impl SParameterPort {
+    fn from_config(config: SParameterPortConfig) -> Result<SParameterPort, SimulatorError> {
+        Ok(SParameterPort {
+            node: config.node,
+            reference: config.reference,
+            impedance: config
+                .impedance
+                .map(|impedance| {
+                    Unit::from(impedance)
+                        .map_err(|_| SimulatorError::MalformedSimulationConfig("Sp".to_owned()))
+                })
+                .transpose()?
+                .unwrap_or(Unit::Base(50.0)),
+        })
+    }
 }
 
 impl Simulation {
@@ -744,6 +1186,7 @@ impl Simulation {
                 tstart,
                 tmax,
                 uic,
+                ..
             } => {
                 let mut formatted = format!(".tran");
 
@@ -886,9 +1329,12 @@ impl Simulation {
                                     if let Some(fstop) = fstop {
                                         formatted.push_str(&format!(" {}", fstop.format()));
 
-                                        if let Some(pts_per_summary) = pts_per_summary {
-                                            formatted.push_str(&format!(" {}", pts_per_summary));
-                                        }
+                                        // ngspice only saves the per-device contributions the
+                                        // noise summary ranks when pts_per_summary is given
+                                        formatted.push_str(&format!(
+                                            " {}",
+                                            pts_per_summary.unwrap_or(1)
+                                        ));
                                     }
                                 }
                             }
@@ -1016,18 +1462,96 @@ impl Simulation {
 
                 formatted
             }
+
+            Simulation::Tf {
+                output_type,
+                output,
+                oref,
+                src,
+            } => {
+                let mut formatted = format!(".tf");
+
+                if let (Some(output_type), Some(output), Some(src)) = (output_type, output, src) {
+                    let formatted_output = match (output_type, oref) {
+                        (CurrentOrVoltage::V, Some(oref)) => format!("V({},{})", output, oref),
+                        _ => output_type.format(output),
+                    };
+
+                    formatted.push_str(&format!(" {} {}", formatted_output, src));
+                }
+
+                formatted.push('\n');
+
+                formatted
+            }
+
+            // Each injection is a plain AC run, the probe is inserted by the schematic
+            Simulation::Stability {
+                fstart,
+                fstop,
+                variation,
+                nx,
+                ..
+            } => Simulation::Ac {
+                fstart: fstart.clone(),
+                fstop: fstop.clone(),
+                variation: variation.clone(),
+                nx: nx.clone(),
+            }
+            .format(),
+
+            // Same as Stability, the port sources are inserted by the schematic
+            Simulation::Sp {
+                fstart,
+                fstop,
+                variation,
+                nx,
+                ..
+            } => Simulation::Ac {
+                fstart: fstart.clone(),
+                fstop: fstop.clone(),
+                variation: variation.clone(),
+                nx: nx.clone(),
+            }
+            .format(),
         }
     }
 
+    // Breakpoints in seconds, the ones outside of the simulated time would never be reached
+    pub fn get_breakpoints(&self) -> Vec<f64> {
+        let Simulation::Tran {
+            tstop,
+            tstart,
+            breakpoints,
+            ..
+        } = self
+        else {
+            return Vec::default();
+        };
+
+        let tstart = tstart.as_ref().map_or(0.0, |tstart| tstart.value());
+        let tstop = tstop.as_ref().map_or(f64::INFINITY, |tstop| tstop.value());
+
+        let mut breakpoints: Vec<f64> = breakpoints
+            .iter()
+            .map(|breakpoint| breakpoint.value())
+            .filter(|breakpoint| *breakpoint > tstart && *breakpoint < tstop)
+            .collect();
+        breakpoints.sort_by(|a, b| a.total_cmp(b));
+
+        breakpoints
+    }
+
     pub fn from_config(config: SimulationConfig) -> Result<Simulation, SimulatorError> {
         match config {
-            SimulationConfig::Op {} => Ok(Simulation::Op),
+            SimulationConfig::Op { .. } => Ok(Simulation::Op),
 
             SimulationConfig::Ac {
                 fstart,
                 fstop,
                 variation,
                 nx,
+                ..
             } => {
                 return Ok(Simulation::Ac {
                     fstart: fstart
@@ -1055,6 +1579,7 @@ impl Simulation {
                 variation,
                 nx,
                 f2overf1,
+                ..
             } => {
                 return Ok(Simulation::Disto {
                     fstart: fstart
@@ -1086,6 +1611,7 @@ impl Simulation {
                 fstart,
                 fstop,
                 pts_per_summary,
+                ..
             } => {
                 return Ok(Simulation::Noise {
                     output,
@@ -1117,6 +1643,8 @@ impl Simulation {
                 tstart,
                 tmax,
                 uic,
+                breakpoints,
+                ..
             } => {
                 return Ok(Simulation::Tran {
                     tstep: tstep
@@ -1148,6 +1676,15 @@ impl Simulation {
                         })
                         .transpose()?,
                     uic,
+                    breakpoints: breakpoints
+                        .unwrap_or_default()
+                        .into_iter()
+                        .map(|breakpoint| {
+                            Unit::from(breakpoint).map_err(|_| {
+                                SimulatorError::MalformedSimulationConfig("Tran".to_owned())
+                            })
+                        })
+                        .collect::<Result<Vec<Unit>, SimulatorError>>()?,
                 });
             }
 
@@ -1160,6 +1697,7 @@ impl Simulation {
                 start2,
                 stop2,
                 incr2,
+                ..
             } => {
                 return Ok(Simulation::Dc {
                     srcnam,
@@ -1217,6 +1755,7 @@ impl Simulation {
                 node4,
                 transfer_function,
                 analysis_type,
+                ..
             } => Ok(Simulation::Pz {
                 node1,
                 node2,
@@ -1234,6 +1773,7 @@ impl Simulation {
                 fstop,
                 variation,
                 nx,
+                ..
             } => match analysis_type {
                 Some(SensitivityAnalysisType::Dc) => Ok(Simulation::Sens {
                     output_type,
@@ -1272,6 +1812,110 @@ impl Simulation {
 
                 None => Err(SimulatorError::MalformedSimulationConfig("Sens".to_owned())),
             },
+
+            SimulationConfig::Tf {
+                output_type,
+                output,
+                oref,
+                src,
+                ..
+            } => {
+                if let (Some(output_type), Some(output), Some(src)) = (output_type, output, src) {
+                    Ok(Simulation::Tf {
+                        output_type: Some(output_type),
+                        output: Some(output),
+                        oref,
+                        src: Some(src),
+                    })
+                } else {
+                    Err(SimulatorError::MalformedSimulationConfig("Tf".to_owned()))
+                }
+            }
+
+            SimulationConfig::Stability {
+                element,
+                node,
+                fstart,
+                fstop,
+                variation,
+                nx,
+                injection,
+                ..
+            } => {
+                if let (Some(element), Some(node), Some(injection)) = (element, node, injection) {
+                    Ok(Simulation::Stability {
+                        element,
+                        node,
+                        injection,
+                        fstart: fstart
+                            .map(|fstart| {
+                                Unit::from(fstart).map_err(|_| {
+                                    SimulatorError::MalformedSimulationConfig(
+                                        "Stability".to_owned(),
+                                    )
+                                })
+                            })
+                            .transpose()?,
+                        fstop: fstop
+                            .map(|fstop| {
+                                Unit::from(fstop).map_err(|_| {
+                                    SimulatorError::MalformedSimulationConfig(
+                                        "Stability".to_owned(),
+                                    )
+                                })
+                            })
+                            .transpose()?,
+                        variation,
+                        nx,
+                    })
+                } else {
+                    Err(SimulatorError::MalformedSimulationConfig(
+                        "Stability".to_owned(),
+                    ))
+                }
+            }
+
+            SimulationConfig::Sp {
+                fstart,
+                fstop,
+                variation,
+                nx,
+                ports,
+                excitation,
+                ..
+            } => {
+                if let (Some(ports), Some(excitation)) = (ports, excitation) {
+                    if excitation >= ports.len() {
+                        return Err(SimulatorError::MalformedSimulationConfig("Sp".to_owned()));
+                    }
+
+                    Ok(Simulation::Sp {
+                        fstart: fstart
+                            .map(|fstart| {
+                                Unit::from(fstart).map_err(|_| {
+                                    SimulatorError::MalformedSimulationConfig("Sp".to_owned())
+                                })
+                            })
+                            .transpose()?,
+                        fstop: fstop
+                            .map(|fstop| {
+                                Unit::from(fstop).map_err(|_| {
+                                    SimulatorError::MalformedSimulationConfig("Sp".to_owned())
+                                })
+                            })
+                            .transpose()?,
+                        variation,
+                        nx,
+                        ports: ports
+                            .into_iter()
+                            .map(|port| SParameterPort::from_config(port))
+                            .collect::<Result<Vec<SParameterPort>, SimulatorError>>()?,
+                        excitation,
+                    })
+                } else {
+                    Err(SimulatorError::MalformedSimulationConfig("Sp".to_owned()))
+                }
+            }
         }
     }
 }
@@ -1,4 +1,6 @@
-use super::paprika::ngspice::types::PkVecvaluesall;
+use std::collections::HashMap;
+
+use super::paprika::ngspice::types::{PkVecinfoall, PkVecvaluesall};
 
 // the payload type must implement `Serialize` and `Clone`.
 #[derive(Clone, serde::Serialize)]
@@ -7,7 +9,74 @@ pub struct SimulationDataPayload {
     pub id: String,
 }
 
-#[derive(Debug, serde::Serialize, Clone)]
+// Layout version of the columns, first in the header
+const COLUMNS_VERSION: u32 = 1;
+
+impl SimulationDataPayload {
+    /// Packs the points column by column, so long transients skip serializing every value.
+    /// Little endian, a header of u32s: version, id length, point count and vector count.
+    /// Then the id, a byte per vector that is 1 when it has an imaginary column, and zeros
+    /// up to a multiple of 8. Then the f64 columns: computed, data index, and per vector in
+    /// the schema order its real parts followed by its imaginary parts if it has them.
+    ///
+    /// None when the points do not have the same vectors
+    pub fn to_columns(&self) -> Option<Vec<u8>> {
+        let vector_count = self.data.first().map(|it| it.values.len()).unwrap_or(0);
+
+        if self.data.iter().any(|it| it.values.len() != vector_count) {
+            return None;
+        }
+
+        // Only AC and the like have imaginary parts, transients never send theirs
+        let has_imag: Vec<bool> = (0..vector_count)
+            .map(|index| self.data.iter().any(|it| it.values[index].1 != 0.0))
+            .collect();
+
+        let id = self.id.as_bytes();
+        let header_len = (16 + id.len() + vector_count).div_ceil(8) * 8;
+        let column_count = 2 + vector_count + has_imag.iter().filter(|it| **it).count();
+
+        let mut bytes = Vec::with_capacity(header_len + column_count * self.data.len() * 8);
+        bytes.extend_from_slice(&COLUMNS_VERSION.to_le_bytes());
+        bytes.extend_from_slice(&(id.len() as u32).to_le_bytes());
+        bytes.extend_from_slice(&(self.data.len() as u32).to_le_bytes());
+        bytes.extend_from_slice(&(vector_count as u32).to_le_bytes());
+        bytes.extend_from_slice(id);
+        bytes.extend(has_imag.iter().map(|it| *it as u8));
+        bytes.resize(header_len, 0);
+
+        for it in self.data.iter() {
+            bytes.extend_from_slice(&(it.computed as f64).to_le_bytes());
+        }
+        for it in self.data.iter() {
+            bytes.extend_from_slice(&(it.data_index as f64).to_le_bytes());
+        }
+
+        for (index, has_imag) in has_imag.iter().enumerate() {
+            for it in self.data.iter() {
+                bytes.extend_from_slice(&it.values[index].0.to_le_bytes());
+            }
+
+            if *has_imag {
+                for it in self.data.iter() {
+                    bytes.extend_from_slice(&it.values[index].1.to_le_bytes());
+                }
+            }
+        }
+
+        Some(bytes)
+    }
+}
+
+// the payload type must implement `Serialize` and `Clone`.
+#[derive(Clone, serde::Serialize)]
+pub struct SimulationSchemaPayload {
+    pub schema: SimulationSchema,
+    pub id: String,
+}
+
+/// A vector with its name and flags, rebuilt from a point and the schema of its analysis
+#[derive(Debug, serde::Serialize, serde::Deserialize, Clone)]
 pub struct ComputedData {
     pub name: String,
     pub c_real: f64,
@@ -16,28 +85,194 @@ pub struct ComputedData {
     pub is_complex: bool,
 }
 
-#[derive(Debug, serde::Serialize, Clone)]
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub enum VectorKind {
+    Voltage,
+    Current,
+    Time,
+    Frequency,
+    // Sweeps, noise densities, poles and whatever else ngspice names on its own
+    Other,
+}
+
+impl VectorKind {
+    // ngspice names node voltages after the node and currents after the element
+    pub fn from_name(name: &str) -> VectorKind {
+        let name = name.to_lowercase();
+
+        match name.as_str() {
+            "time" => return VectorKind::Time,
+            "frequency" => return VectorKind::Frequency,
+            _ => {}
+        }
+
+        if name.ends_with("#branch")
+            || name.starts_with("i(")
+            || (name.starts_with('@') && name.contains("[i"))
+        {
+            return VectorKind::Current;
+        }
+
+        if name.starts_with("v(") {
+            return VectorKind::Voltage;
+        }
+
+        if name.ends_with("-sweep") || name.contains("noise") || name.contains('(') {
+            return VectorKind::Other;
+        }
+
+        VectorKind::Voltage
+    }
+}
+
+/// What one vector of the running analysis holds
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct VectorSchema {
+    pub name: String,
+    pub kind: VectorKind,
+    pub is_complex: bool,
+    pub is_scale: bool,
+    // Connection nodes of a node voltage, or the element node of a current
+    pub canvas_ids: Vec<String>,
+}
+
+impl VectorSchema {
+    fn new(name: &str, is_complex: bool, is_scale: bool) -> VectorSchema {
+        VectorSchema {
+            name: name.to_owned(),
+            kind: VectorKind::from_name(name),
+            is_complex,
+            is_scale,
+            canvas_ids: Vec::default(),
+        }
+    }
+
+    // Node or element the vector is named after, as the schematic names it
+    fn get_spice_name(&self) -> Option<String> {
+        let name = self.name.to_lowercase();
+
+        let spice_name = match self.kind {
+            VectorKind::Voltage => name
+                .strip_prefix("v(")
+                .and_then(|name| name.strip_suffix(')'))
+                .unwrap_or(&name),
+            VectorKind::Current => name
+                .strip_suffix("#branch")
+                .or_else(|| {
+                    name.strip_prefix("i(")
+                        .and_then(|name| name.strip_suffix(')'))
+                })
+                .or_else(|| {
+                    name.strip_prefix('@')
+                        .and_then(|name| name.split('[').next())
+                })
+                .unwrap_or(&name),
+            _ => return None,
+        };
+
+        Some(spice_name.to_owned())
+    }
+}
+
+/// Vectors of an analysis, sent once when it starts so its points only carry values,
+/// in the same order
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct SimulationSchema {
+    // ngspice plot, like tran1, and its type, like Transient Analysis
+    pub plot: String,
+    pub plot_type: String,
+    pub vectors: Vec<VectorSchema>,
+}
+
+impl SimulationSchema {
+    // SendInitData lists the vectors in the order SendData sends their values
+    pub fn new(pkvecinfoall: &PkVecinfoall) -> SimulationSchema {
+        SimulationSchema {
+            plot: pkvecinfoall.name.clone(),
+            plot_type: pkvecinfoall.stype.clone(),
+            vectors: pkvecinfoall
+                .vecs
+                .iter()
+                .map(|vecinfo| {
+                    VectorSchema::new(
+                        &vecinfo.name,
+                        !vecinfo.is_real,
+                        vecinfo.pdvec == vecinfo.pdvecscale,
+                    )
+                })
+                .collect(),
+        }
+    }
+
+    // For points ngspice sent without init data first, they carry names and flags
+    pub fn from_values(pkvecvaluesall: &PkVecvaluesall) -> SimulationSchema {
+        SimulationSchema {
+            plot: String::default(),
+            plot_type: String::default(),
+            vectors: pkvecvaluesall
+                .vecsa
+                .iter()
+                .map(|vecvalues| {
+                    VectorSchema::new(&vecvalues.name, vecvalues.is_complex, vecvalues.is_scale)
+                })
+                .collect(),
+        }
+    }
+
+    /// Maps the vectors back to the canvas, ids are by lowercase node and element name
+    pub fn with_canvas_ids(mut self, canvas_ids: &HashMap<String, Vec<String>>) -> Self {
+        for vector in self.vectors.iter_mut() {
+            vector.canvas_ids = vector
+                .get_spice_name()
+                .and_then(|spice_name| canvas_ids.get(&spice_name))
+                .cloned()
+                .unwrap_or_default();
+        }
+
+        self
+    }
+
+    pub fn get_scale_index(&self) -> Option<usize> {
+        self.vectors.iter().position(|vector| vector.is_scale)
+    }
+
+    /// Values of the point with the names and flags of their vectors
+    pub fn name_values(&self, simulation_data: &SimulationData) -> Vec<ComputedData> {
+        self.vectors
+            .iter()
+            .zip(simulation_data.get_values())
+            .map(|(vector, (c_real, c_imag))| ComputedData {
+                name: vector.name.clone(),
+                c_real: *c_real,
+                c_imag: *c_imag,
+                is_scale: vector.is_scale,
+                is_complex: vector.is_complex,
+            })
+            .collect()
+    }
+}
+
+#[derive(Debug, serde::Serialize, serde::Deserialize, Clone)]
 pub struct SimulationData {
     computed: i32,
     data_index: i32,
-    computed_values_for_index: Vec<ComputedData>,
+    // Real and imaginary parts, in the order of the vectors of the schema
+    values: Vec<(f64, f64)>,
 }
 
 impl SimulationData {
+    pub fn get_values(&self) -> &[(f64, f64)] {
+        &self.values
+    }
+
     pub fn new(spice_vec: PkVecvaluesall) -> SimulationData {
         return SimulationData {
             computed: spice_vec.count,
             data_index: spice_vec.index,
-            computed_values_for_index: spice_vec
+            values: spice_vec
                 .vecsa
                 .iter()
-                .map(|it| ComputedData {
-                    name: it.name.clone(),
-                    c_real: it.creal,
-                    c_imag: it.cimag,
-                    is_scale: it.is_scale,
-                    is_complex: it.is_complex,
-                })
+                .map(|it| (it.creal, it.cimag))
                 .collect(),
         };
     }
@@ -0,0 +1,359 @@
+use num_complex::Complex64;
+
+use super::{
+    paprika::ngspice::types::PkVectorinfo, sparameters::NetworkParameters,
+    stability::LoopGainResult,
+};
+
+// the payload type must implement `Serialize` and `Clone`.
+#[derive(Clone, serde::Serialize)]
+pub struct SimulationResultPayload {
+    pub result: SimulationResult,
+    pub id: String,
+}
+
+/// Results that are only available once ngspice is done with an analysis,
+/// they are read from the plot vectors instead of the streamed data
+#[derive(Debug, serde::Serialize, serde::Deserialize, Clone)]
+pub enum SimulationResult {
+    Tf(TransferFunctionResult),
+    Pz(PoleZeroResult),
+    Noise(NoiseSummary),
+    LoopGain(LoopGainResult),
+    Sp(NetworkParameters),
+}
+
+fn first_real_value(vector: &PkVectorinfo) -> Option<f64> {
+    if let Some(realdata) = &vector.realdata {
+        return realdata.first().copied();
+    }
+
+    if let Some(compdata) = &vector.compdata {
+        return compdata.first().map(|value| value.re);
+    }
+
+    None
+}
+
+fn first_complex_value(vector: &PkVectorinfo) -> Option<Complex64> {
+    if let Some(compdata) = &vector.compdata {
+        return compdata.first().copied();
+    }
+
+    if let Some(realdata) = &vector.realdata {
+        return realdata.first().map(|value| Complex64::new(*value, 0.0));
+    }
+
+    None
+}
+
+#[derive(Debug, serde::Serialize, serde::Deserialize, Clone)]
+pub struct TransferFunctionResult {
+    pub transfer_function: f64,
+    pub output_impedance: f64,
+    pub input_impedance: f64,
+}
+
+impl TransferFunctionResult {
+    // ngspice names the .tf vectors after the output and source, e.g:
+    // transfer_function, output_impedance_at_v(out), vin#input_impedance
+    pub fn from_vectors(vectors: &[PkVectorinfo]) -> Option<TransferFunctionResult> {
+        let mut transfer_function: Option<f64> = None;
+        let mut output_impedance: Option<f64> = None;
+        let mut input_impedance: Option<f64> = None;
+
+        for vector in vectors {
+            let name = vector.name.to_lowercase();
+
+            if name.contains("transfer_function") {
+                transfer_function = first_real_value(vector);
+            } else if name.contains("output_impedance") {
+                output_impedance = first_real_value(vector);
+            } else if name.contains("input_impedance") {
+                input_impedance = first_real_value(vector);
+            }
+        }
+
+        Some(TransferFunctionResult {
+            transfer_function: transfer_function?,
+            output_impedance: output_impedance?,
+            input_impedance: input_impedance?,
+        })
+    }
+}
+
+#[derive(Debug, serde::Serialize, serde::Deserialize, Clone)]
+pub struct Root {
+    pub real: f64,
+    pub imag: f64,
+    // Natural frequency, both in rad/s and Hz
+    pub omega_n: f64,
+    pub f_n: f64,
+    // Not defined for a root at the origin
+    pub damping_ratio: Option<f64>,
+    // Not defined for roots on the imaginary axis
+    pub q: Option<f64>,
+    pub is_right_half_plane: bool,
+}
+
+impl Root {
+    pub fn new(value: Complex64) -> Root {
+        let omega_n = value.norm();
+
+        let damping_ratio = if omega_n > 0.0 {
+            Some(-value.re / omega_n)
+        } else {
+            None
+        };
+
+        let q = damping_ratio.and_then(|damping_ratio| {
+            if damping_ratio != 0.0 {
+                Some(1.0 / (2.0 * damping_ratio))
+            } else {
+                None
+            }
+        });
+
+        Root {
+            real: value.re,
+            imag: value.im,
+            omega_n,
+            f_n: omega_n / (2.0 * std::f64::consts::PI),
+            damping_ratio,
+            q,
+            is_right_half_plane: value.re > 0.0,
+        }
+    }
+}
+
+#[derive(Debug, serde::Serialize, serde::Deserialize, Clone)]
+pub struct PoleZeroResult {
+    pub poles: Vec<Root>,
+    pub zeros: Vec<Root>,
+    pub has_right_half_plane_poles: bool,
+}
+
+impl PoleZeroResult {
+    // Index inside the parenthesis of ngspice's pole(n) and zero(n) vectors
+    fn root_index(name: &str) -> usize {
+        name.split_once('(')
+            .and_then(|(_, rest)| rest.split_once(')'))
+            .and_then(|(index, _)| index.parse().ok())
+            .unwrap_or(0)
+    }
+
+    fn collect_roots(vectors: &[PkVectorinfo], prefix: &str) -> Vec<Root> {
+        let mut roots: Vec<(usize, Complex64)> = vectors
+            .iter()
+            .filter(|vector| vector.name.to_lowercase().starts_with(prefix))
+            .filter_map(|vector| {
+                first_complex_value(vector)
+                    .map(|value| (PoleZeroResult::root_index(&vector.name), value))
+            })
+            .collect();
+
+        roots.sort_by_key(|(index, _)| *index);
+
+        roots
+            .into_iter()
+            .map(|(_, value)| Root::new(value))
+            .collect()
+    }
+
+    pub fn from_vectors(vectors: &[PkVectorinfo]) -> Option<PoleZeroResult> {
+        let poles = PoleZeroResult::collect_roots(vectors, "pole");
+        let zeros = PoleZeroResult::collect_roots(vectors, "zero");
+
+        if poles.is_empty() && zeros.is_empty() {
+            return None;
+        }
+
+        let has_right_half_plane_poles = poles.iter().any(|pole| pole.is_right_half_plane);
+
+        Some(PoleZeroResult {
+            poles,
+            zeros,
+            has_right_half_plane_poles,
+        })
+    }
+}
+
+#[derive(Debug, serde::Serialize, serde::Deserialize, Clone)]
+pub struct NoiseContribution {
+    pub device: String,
+    // Noise generators of the device as named by ngspice, e.g: q1_rb and q1_ic
+    pub generators: Vec<String>,
+    pub output_total: f64,
+    // Fraction of the total output noise power
+    pub share: f64,
+}
+
+#[derive(Debug, serde::Serialize, serde::Deserialize, Clone)]
+pub struct NoiseSummary {
+    pub output_total: f64,
+    pub input_total: f64,
+    // One per device, sorted by share, largest contribution first
+    pub contributions: Vec<NoiseContribution>,
+}
+
+fn is_noise_generator_of(name: &str, device: &str) -> bool {
+    name.len() > device.len() + 1
+        && (name.starts_with(&format!("{}_", device)) || name.ends_with(&format!("_{}", device)))
+}
+
+// ngspice names generators like q1_rb, or rd_m1 in some versions, next to the total of
+// the device, q1. That total is kept, devices without one add their generators in power
+fn group_noise_generators(generators: &[(String, f64)]) -> Vec<(String, Vec<String>, f64)> {
+    let is_device_total = |name: &str| {
+        generators
+            .iter()
+            .any(|(other, _)| is_noise_generator_of(other, name))
+    };
+
+    let get_device = |name: &str| -> String {
+        if is_device_total(name) {
+            return name.to_owned();
+        }
+
+        generators
+            .iter()
+            .map(|(other, _)| other.as_str())
+            .filter(|other| is_noise_generator_of(name, other))
+            .max_by_key(|other| other.len())
+            .map(|device| device.to_owned())
+            .unwrap_or_else(|| {
+                name.split_once('_')
+                    .map(|(device, _)| device.to_owned())
+                    .unwrap_or(name.to_owned())
+            })
+    };
+
+    // Device, its generators, its total and the power of its generators
+    let mut devices: Vec<(String, Vec<String>, Option<f64>, f64)> = Vec::default();
+
+    for (name, value) in generators {
+        let device = get_device(name);
+
+        let index = match devices.iter().position(|(other, ..)| *other == device) {
+            Some(index) => index,
+            None => {
+                devices.push((device.clone(), Vec::default(), None, 0.0));
+                devices.len() - 1
+            }
+        };
+
+        let entry = &mut devices[index];
+
+        if *name == device {
+            entry.2 = Some(*value);
+        } else {
+            entry.1.push(name.clone());
+            entry.3 += value * value;
+        }
+    }
+
+    devices
+        .into_iter()
+        .map(|(device, generators, total, power)| {
+            (device, generators, total.unwrap_or(power.sqrt()))
+        })
+        .collect()
+}
+
+impl NoiseSummary {
+    // Reads the integrated noise plot (noise2), per generator totals are only
+    // saved by ngspice when pts_per_summary is set in the .noise directive
+    pub fn from_vectors(vectors: &[PkVectorinfo]) -> Option<NoiseSummary> {
+        let mut output_total: Option<f64> = None;
+        let mut input_total: Option<f64> = None;
+        let mut generators: Vec<(String, f64)> = Vec::default();
+
+        for vector in vectors {
+            let name = vector.name.to_lowercase();
+
+            match name.as_str() {
+                "onoise_total" => output_total = first_real_value(vector),
+                "inoise_total" => input_total = first_real_value(vector),
+                _ => {
+                    if let Some(generator) = name.strip_prefix("onoise_total_") {
+                        if let Some(value) = first_real_value(vector) {
+                            generators.push((generator.to_owned(), value));
+                        }
+                    }
+                }
+            }
+        }
+
+        let output_total = output_total?;
+        let output_power = output_total * output_total;
+
+        let mut contributions: Vec<NoiseContribution> = group_noise_generators(&generators)
+            .into_iter()
+            .map(|(device, generators, value)| NoiseContribution {
+                device,
+                generators,
+                output_total: value,
+                share: if output_power > 0.0 {
+                    (value * value) / output_power
+                } else {
+                    0.0
+                },
+            })
+            .collect();
+
+        contributions.sort_by(|c1, c2| c2.share.total_cmp(&c1.share));
+
+        Some(NoiseSummary {
+            output_total,
+            input_total: input_total?,
+            contributions,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn generators(entries: &[(&str, f64)]) -> Vec<(String, f64)> {
+        entries
+            .iter()
+            .map(|(name, value)| (name.to_string(), *value))
+            .collect()
+    }
+
+    #[test]
+    fn noise_generators_are_grouped_under_the_device_total() {
+        let grouped = group_noise_generators(&generators(&[
+            ("q1_rb", 1.0),
+            ("q1_ic", 2.0),
+            ("q1", 3.0),
+            ("rd_m1", 0.5),
+            ("m1", 0.7),
+            ("r1", 4.0),
+        ]));
+
+        assert_eq!(
+            grouped,
+            vec![
+                (
+                    "q1".to_owned(),
+                    vec!["q1_rb".to_owned(), "q1_ic".to_owned()],
+                    3.0
+                ),
+                ("m1".to_owned(), vec!["rd_m1".to_owned()], 0.7),
+                ("r1".to_owned(), vec![], 4.0),
+            ]
+        );
+    }
+
+    #[test]
+    fn noise_generators_without_a_device_total_add_in_power() {
+        let grouped = group_noise_generators(&generators(&[("q2_rb", 3.0), ("q2_ic", 4.0)]));
+
+        assert_eq!(grouped.len(), 1);
+        assert_eq!(grouped[0].0, "q2");
+        assert_eq!(grouped[0].1, vec!["q2_rb".to_owned(), "q2_ic".to_owned()]);
+        assert!((grouped[0].2 - 5.0).abs() < 1e-12);
+    }
+}
@@ -1,6 +1,6 @@
-use tauri::{Manager, Window};
-
-use super::simulation::Simulation;
+use super::{
+    simulation::Simulation, simulation_data::ComputedData, simulator_error::SimulatorError,
+};
 
 // the payload type must implement `Serialize` and `Clone`.
 #[derive(Clone, serde::Serialize)]
@@ -15,7 +15,7 @@ pub enum SimulationStatusError {
     MalformedSimulationStatus,
 }
 
-#[derive(Debug, serde::Serialize, Clone)]
+#[derive(Debug, serde::Serialize, serde::Deserialize, Clone)]
 pub enum SimulationStatus {
     SourceDeck,
     Progress {
@@ -23,6 +23,16 @@ pub enum SimulationStatus {
         progress: f32,
     },
     Ready,
+    Paused,
+    // Picked up again after a pause or a breakpoint, until ngspice reports its progress
+    Running,
+    // Stopped at a transient breakpoint, with the value of every vector at that time
+    Breakpoint {
+        time: f64,
+        values: Vec<ComputedData>,
+    },
+    Cancelled,
+    Failed(SimulatorError),
 }
 
 fn contains_any(haystack: &str, needles: Vec<String>) -> Option<String> {
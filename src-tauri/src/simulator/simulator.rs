@@ -1,20 +1,21 @@
-use std::path::PathBuf;
+use std::collections::{HashMap, HashSet};
 use std::sync::{Arc, Mutex};
-use std::thread::sleep;
-use std::time::Duration;
-use std::{
-    collections::{HashMap, HashSet},
-    ffi::OsStr,
-};
 
-use super::paprika::spice::spice::Spice;
-use super::simulation_data::SimulationDataPayload;
-use super::simulation_status::SimulationStatusPayload;
+use super::paprika::ngspice::types::PkVectorinfo;
+use super::simulation_result::{
+    NoiseSummary, PoleZeroResult, SimulationResult, SimulationResultPayload, TransferFunctionResult,
+};
 use colored::Colorize;
-use tauri::ipc::Channel;
 
-use super::commands::{SecondaryThreadStatus, SimulationThreadOrchestrator};
-use super::simulation::Simulation;
+use super::external_sources::ExternalSourcesFactory;
+use super::orchestrator::{
+    get_simulation_id, SecondaryThreadStatus, SimulationThreadOrchestrator, ThreadCommand,
+};
+use super::resource_report::{ResourceReport, ResourceReportPayload};
+use super::result_store::{StoredPlot, StoredSimulation};
+use super::simulation::{LoopInjection, SParameterPort, Simulation};
+use super::sparameters::{get_sparameter_id, NetworkParameters, SParameterRun};
+use super::stability::{get_stability_id, LoopGainResult, LoopGainRun};
 use super::{
     circuit::{
         canvas::{CanvasEdge, CanvasNode, NodeData},
@@ -23,78 +24,157 @@ use super::{
     },
     manager::NGGSpiceManager,
     simulator_error::SimulatorError,
+    sink::ResultSink,
     unit_of_magnitude::UnitOfMagnitude,
+    worker::instance::{SpiceInstance, SpiceSource},
 };
-use libloading::Library;
 
-#[derive(Clone)]
 pub struct Simulator {
     id: usize,
-    spice: Spice<NGGSpiceManager>,
+    // None once ngspice failed to reload
+    spice: Option<SpiceInstance>,
+    source: SpiceSource,
+    // Kept to re-initialize ngspice after it exits
+    manager: NGGSpiceManager,
     schematic: Option<Schematic>,
     thread_orchestrator: Arc<Mutex<SimulationThreadOrchestrator>>,
+    sink: Arc<dyn ResultSink>,
+    // Simulation sent to ngspice, results are collected once it's done
+    ongoing_simulation: Option<(String, Simulation)>,
+    // Plots ngspice had before the ongoing simulation, the others are its own
+    previous_plots: Vec<String>,
+    // Netlist of the ongoing simulation, kept with its results
+    ongoing_netlist: String,
 }
 
 impl Simulator {
     pub fn init(
         id: usize,
         thread_orchestrator: Arc<Mutex<SimulationThreadOrchestrator>>,
-        lib: PathBuf,
-        data_update_channel: Channel<SimulationDataPayload>,
-        status_update_channel: Channel<SimulationStatusPayload>,
-    ) -> (Simulator, Library) {
+        source: SpiceSource,
+        sink: Arc<dyn ResultSink>,
+        external_sources: Option<ExternalSourcesFactory>,
+    ) -> Result<Simulator, SimulatorError> {
         let manager = NGGSpiceManager::new(
             id,
             Arc::clone(&thread_orchestrator),
-            data_update_channel,
-            status_update_channel,
+            Arc::clone(&sink),
+            external_sources,
         );
         log::info!("Manager created for thread {}", id);
 
-        let (spice, library) = Spice::init(OsStr::new(lib.as_os_str()), manager, id as i32)
-            // TODO: Better err handling
-            .unwrap();
+        let spice = SpiceInstance::load(&source, id, manager.clone())?;
 
         log::info!("Spice created for thread {}", id);
 
-        (
-            Simulator {
-                id,
-                spice,
-                schematic: None,
-                thread_orchestrator,
-            },
-            library,
-        )
+        Ok(Simulator {
+            id,
+            spice: Some(spice),
+            source,
+            manager,
+            schematic: None,
+            thread_orchestrator,
+            sink,
+            ongoing_simulation: None,
+            previous_plots: Vec::default(),
+            ongoing_netlist: String::default(),
+        })
+    }
+
+    // ngspice can not be used after it exits, it has to be unloaded and loaded again
+    fn restart(&mut self) -> Result<(), SimulatorError> {
+        if let Some(spice) = self.spice.take() {
+            spice.close(self.id);
+        }
+
+        let spice = SpiceInstance::load(&self.source, self.id, self.manager.clone())?;
+        self.spice = Some(spice);
+
+        log::info!("Thread {}: ngspice restarted", self.id);
+
+        Ok(())
+    }
+
+    pub fn close(self) {
+        if let Some(spice) = self.spice {
+            spice.close(self.id);
+        }
+    }
+
+    fn command(&self, command: &str) {
+        if let Some(spice) = &self.spice {
+            spice.command(command);
+        }
     }
 
     pub fn run(&mut self) {
         log::info!("Begin run on thread {}...", self.id);
 
         loop {
+            // Sleeps until ngspice finishes or a command comes in
             let orch_guard = self.thread_orchestrator.lock().unwrap();
+            let orch_guard = SimulationThreadOrchestrator::wait_for_thread(orch_guard, self.id);
             let status = orch_guard.get_thread_status(self.id);
             drop(orch_guard);
 
             match status {
                 SecondaryThreadStatus::Idle => {
+                    // The background thread is done with the previous simulation
+                    if let Some((simulation_id, simulation)) = self.ongoing_simulation.take() {
+                        let orch_guard = self.thread_orchestrator.lock().unwrap();
+                        let is_cancelled = orch_guard.is_cancelled(&simulation_id);
+                        drop(orch_guard);
+
+                        if !is_cancelled {
+                            self.send_resource_report(&simulation_id);
+                            self.store_results(&simulation_id);
+                            self.send_results(&simulation_id, &simulation);
+                        }
+                    }
+
                     let mut orch_guard = self.thread_orchestrator.lock().unwrap();
-                    let maybe_simulation = orch_guard.dequeue_simulation(self.id);
+                    let maybe_simulation = orch_guard.dequeue_simulation();
                     drop(orch_guard);
 
                     if let Some((new_simulation_id, new_simulation_config)) = maybe_simulation {
-                        let simulation = Simulation::from_config(new_simulation_config).unwrap();
+                        let limits = new_simulation_config.get_limits();
+                        let simulation = match Simulation::from_config(new_simulation_config) {
+                            Ok(simulation) => simulation,
+                            // The thread stays idle and goes on with the queue
+                            Err(error) => {
+                                log::error!("Thread {}: {:?}", self.id, error);
+
+                                let mut orch_guard = self.thread_orchestrator.lock().unwrap();
+                                orch_guard.fail_simulation(&new_simulation_id, error);
+                                drop(orch_guard);
+
+                                continue;
+                            }
+                        };
 
                         let mut orch_guard = self.thread_orchestrator.lock().unwrap();
 
-                        orch_guard.set_active_simulation(self.id, &new_simulation_id);
+                        orch_guard.set_active_simulation(self.id, &new_simulation_id, limits);
                         orch_guard.restart_timer(self.id);
                         log::info!("Thread {}: {:?}", self.id, SecondaryThreadStatus::Running);
                         orch_guard.set_thread_status(self.id, SecondaryThreadStatus::Running);
 
                         drop(orch_guard);
 
-                        self.simulate(simulation);
+                        self.ongoing_simulation =
+                            Some((new_simulation_id.clone(), simulation.clone()));
+                        self.manager
+                            .reset_external_sources(get_simulation_id(&new_simulation_id));
+                        self.previous_plots = self.get_all_plots();
+
+                        // Failing the simulation cancels it, the running thread then drops it
+                        if let Err(error) = self.simulate(simulation) {
+                            log::error!("Thread {}: {:?}", self.id, error);
+
+                            let mut orch_guard = self.thread_orchestrator.lock().unwrap();
+                            orch_guard.fail_simulation(&new_simulation_id, error);
+                            drop(orch_guard);
+                        }
                     } else {
                         let mut orch_guard = self.thread_orchestrator.lock().unwrap();
                         log::info!("Thread {}: {:?}", self.id, SecondaryThreadStatus::Done);
@@ -104,33 +184,140 @@ impl Simulator {
                 }
 
                 SecondaryThreadStatus::Running => {
-                    continue;
+                    let mut orch_guard = self.thread_orchestrator.lock().unwrap();
+
+                    match orch_guard.take_pending_command(self.id) {
+                        // Halted before halting ngspice, so its background thread
+                        // ending is not mistaken for the simulation being done
+                        Some(ThreadCommand::Pause) => {
+                            orch_guard.set_thread_status(self.id, SecondaryThreadStatus::Halted);
+                            drop(orch_guard);
+
+                            self.command("bg_halt");
+                        }
+
+                        Some(ThreadCommand::Cancel) => {
+                            orch_guard.set_thread_status(self.id, SecondaryThreadStatus::Halted);
+                            drop(orch_guard);
+
+                            self.command("bg_halt");
+                            self.drop_ongoing_simulation();
+                        }
+
+                        // Failing the simulation cancels it, the halted thread then drops it
+                        Some(ThreadCommand::Abort(error)) => {
+                            orch_guard.set_thread_status(self.id, SecondaryThreadStatus::Halted);
+                            drop(orch_guard);
+
+                            self.command("bg_halt");
+
+                            if let Some((simulation_id, _)) = self.ongoing_simulation.clone() {
+                                self.send_resource_report(&simulation_id);
+
+                                let mut orch_guard = self.thread_orchestrator.lock().unwrap();
+                                orch_guard.fail_simulation(&simulation_id, error);
+                                drop(orch_guard);
+                            }
+                        }
+
+                        _ => {
+                            drop(orch_guard);
+                        }
+                    }
                 }
 
-                SecondaryThreadStatus::Halted => {
-                    // TODO: Implement
+                // Halted by a pause or stopped at a breakpoint, ngspice picks up from there
+                SecondaryThreadStatus::Halted | SecondaryThreadStatus::Paused => {
+                    let mut orch_guard = self.thread_orchestrator.lock().unwrap();
+
+                    match orch_guard.take_pending_command(self.id) {
+                        Some(ThreadCommand::Resume) => {
+                            orch_guard.resume_thread(self.id);
+                            drop(orch_guard);
+
+                            self.command("bg_resume");
+                        }
+
+                        Some(ThreadCommand::Step(step)) => {
+                            drop(orch_guard);
+
+                            self.step(step);
+                        }
+
+                        Some(ThreadCommand::Cancel) => {
+                            drop(orch_guard);
+
+                            self.drop_ongoing_simulation();
+                        }
+
+                        _ => {
+                            drop(orch_guard);
+                        }
+                    }
                 }
 
                 SecondaryThreadStatus::Panic => {
-                    // TODO: Implement
+                    log::error!("Thread {}: ngspice exited", self.id);
+
+                    // Only the simulation that was running fails, the rest of the queue goes on
+                    if let Some((simulation_id, _)) = self.ongoing_simulation.take() {
+                        let mut orch_guard = self.thread_orchestrator.lock().unwrap();
+                        orch_guard.fail_simulation(&simulation_id, SimulatorError::SpiceExited);
+                        orch_guard.flush_simulation_data_buffer(self.id);
+                        drop(orch_guard);
+                    }
+
+                    let restarted = self.restart();
+
+                    let mut orch_guard = self.thread_orchestrator.lock().unwrap();
+
+                    match restarted {
+                        Ok(()) => {
+                            log::info!("Thread {}: {:?}", self.id, SecondaryThreadStatus::Idle);
+                            orch_guard.set_thread_status(self.id, SecondaryThreadStatus::Idle);
+                        }
+
+                        Err(error) => {
+                            orch_guard.fail_thread_simulations(self.id, error);
+                        }
+                    }
+
+                    drop(orch_guard);
                 }
 
                 SecondaryThreadStatus::Done => {
-                    // Need to free up resources
-                    self.clear_resources();
+                    // Need to free up resources, unless ngspice failed to reload
+                    if self.spice.is_some() {
+                        self.clear_resources();
+                    }
                     break;
                 }
             }
-
-            sleep(Duration::from_millis(50));
         }
     }
 
+    // Forgets the halted simulation without collecting its results, the thread
+    // goes back to idle and picks up the next queued one
+    fn drop_ongoing_simulation(&mut self) {
+        self.ongoing_simulation = None;
+
+        let mut orch_guard = self.thread_orchestrator.lock().unwrap();
+        orch_guard.flush_simulation_data_buffer(self.id);
+        log::info!("Thread {}: {:?}", self.id, SecondaryThreadStatus::Idle);
+        orch_guard.set_thread_status(self.id, SecondaryThreadStatus::Idle);
+        drop(orch_guard);
+    }
+
     pub fn is_running(&self) -> bool {
-        self.spice.is_running()
+        match &self.spice {
+            Some(spice) => spice.is_running(),
+            None => false,
+        }
     }
 
     pub fn load_schematic(&mut self, schematic: Schematic) {
+        self.manager
+            .set_canvas_ids(schematic.get_canvas_ids().clone());
         self.schematic = Some(schematic);
     }
 
@@ -165,6 +352,7 @@ impl Simulator {
                     }
 
                     _ => {
+                        schematic.insert_canvas_id(&target_alias, &edge.target);
                         node_connections.insert((edge.source_port, target_alias));
                     }
                 };
@@ -172,6 +360,9 @@ impl Simulator {
         }
 
         for node in nodes {
+            let node_id = node.id.clone();
+            let element_count = schematic.elements().len();
+
             let mut node_connections: Vec<(String, String)> = connections
                 .entry(node.id)
                 .or_default()
@@ -440,26 +631,281 @@ impl Simulator {
                 NodeData::Gnd { .. } => {}
                 NodeData::Node { .. } => {}
             }
+
+            // Currents are named after their element, they map back to its canvas node
+            if let Some(element) = schematic.elements().get(element_count) {
+                let name = element.name().to_owned();
+                schematic.insert_canvas_id(&name, &node_id);
+            }
         }
 
         Ok(schematic)
     }
 
     pub fn simulate(&mut self, sim_config: Simulation) -> Result<(), SimulatorError> {
+        let breakpoints = sim_config.get_breakpoints();
+
         let netlist = match &self.schematic {
             Some(schematic) => schematic.build_netlist(sim_config),
             None => return Err(SimulatorError::NoSchematicFound),
         }?;
 
         log::info!("{}", netlist.green());
+        self.ongoing_netlist = netlist.clone();
 
-        for line in netlist.lines() {
-            self.spice.command(&format!("circbyline {}", line));
+        // Known before ngspice runs, so stopping on the first one is not taken for the end
+        let mut orch_guard = self.thread_orchestrator.lock().unwrap();
+        for breakpoint in breakpoints.iter() {
+            orch_guard.add_breakpoint(self.id, *breakpoint);
         }
+        drop(orch_guard);
 
-        self.spice.command("bg_run");
+        self.command_simulation(&netlist, &breakpoints)
+    }
 
-        Ok(())
+    fn command_simulation(&self, netlist: &str, breakpoints: &[f64]) -> Result<(), SimulatorError> {
+        match &self.spice {
+            Some(spice) => spice.simulate(netlist, breakpoints, &self.manager),
+            None => Ok(()),
+        }
+    }
+
+    fn set_breakpoint(&self, time: f64) {
+        if let Some(spice) = &self.spice {
+            spice.set_breakpoint(time);
+        }
+    }
+
+    // Runs the stopped transient one step further, any other analysis just resumes
+    fn step(&self, step: Option<f64>) {
+        if let Some((_, Simulation::Tran { tstep, .. })) = &self.ongoing_simulation {
+            if let Some(step) = step.or(tstep.as_ref().map(|tstep| tstep.value())) {
+                let mut orch_guard = self.thread_orchestrator.lock().unwrap();
+                let target = orch_guard.get_current_time(self.id).unwrap_or_default() + step;
+                orch_guard.add_breakpoint(self.id, target);
+                drop(orch_guard);
+
+                self.set_breakpoint(target);
+            }
+        }
+
+        let mut orch_guard = self.thread_orchestrator.lock().unwrap();
+        orch_guard.set_thread_status(self.id, SecondaryThreadStatus::Running);
+        drop(orch_guard);
+
+        self.command("bg_resume");
+    }
+
+    fn get_plot_vectors(&self, plot: &str) -> Vec<PkVectorinfo> {
+        match &self.spice {
+            Some(spice) => spice.get_plot_vectors(plot),
+            None => Vec::default(),
+        }
+    }
+
+    fn get_current_plot_vectors(&self) -> Vec<PkVectorinfo> {
+        match &self.spice {
+            Some(spice) => self.get_plot_vectors(&spice.get_cur_plot()),
+            None => Vec::default(),
+        }
+    }
+
+    fn get_all_plots(&self) -> Vec<String> {
+        match &self.spice {
+            Some(spice) => spice.get_all_plots(),
+            None => Vec::default(),
+        }
+    }
+
+    // .noise generates two plots, the spectral densities (noiseN) and the integrated
+    // noise (noiseN+1), the most recent one holding the totals is the one we want
+    fn get_integrated_noise_vectors(&self) -> Vec<PkVectorinfo> {
+        for plot in self.get_all_plots() {
+            if !plot.starts_with("noise") {
+                continue;
+            }
+
+            let vectors = self.get_plot_vectors(&plot);
+
+            if vectors
+                .iter()
+                .any(|vector| vector.name.to_lowercase() == "onoise_total")
+            {
+                return vectors;
+            }
+        }
+
+        Vec::default()
+    }
+
+    pub fn collect_results(&self, simulation: &Simulation) -> Option<SimulationResult> {
+        match simulation {
+            Simulation::Tf { .. } => {
+                TransferFunctionResult::from_vectors(&self.get_current_plot_vectors())
+                    .map(|result| SimulationResult::Tf(result))
+            }
+
+            Simulation::Pz { .. } => PoleZeroResult::from_vectors(&self.get_current_plot_vectors())
+                .map(|result| SimulationResult::Pz(result)),
+
+            Simulation::Noise { .. } => {
+                NoiseSummary::from_vectors(&self.get_integrated_noise_vectors())
+                    .map(|result| SimulationResult::Noise(result))
+            }
+
+            _ => None,
+        }
+    }
+
+    // Each stability analysis runs twice, the loop gain is only available
+    // once the second injection is done, possibly on another thread
+    fn collect_loop_gain(
+        &self,
+        simulation_id: &str,
+        injection: &LoopInjection,
+    ) -> Option<(String, SimulationResult)> {
+        let maybe_run = LoopGainRun::from_vectors(&self.get_current_plot_vectors());
+        let stability_id = get_stability_id(simulation_id);
+
+        let mut orch_guard = self.thread_orchestrator.lock().unwrap();
+
+        // The other injection would wait for this one forever
+        let Some(run) = maybe_run else {
+            orch_guard.fail_simulation(
+                &stability_id,
+                SimulatorError::IncompleteAnalysis(simulation_id.to_owned()),
+            );
+            drop(orch_guard);
+
+            return None;
+        };
+
+        let maybe_runs = orch_guard.complete_loop_gain_run(&stability_id, injection.clone(), run);
+        drop(orch_guard);
+
+        let (voltage_run, current_run) = maybe_runs?;
+
+        Some((
+            stability_id,
+            SimulationResult::LoopGain(LoopGainResult::from_runs(&voltage_run, &current_run)),
+        ))
+    }
+
+    // Same as the loop gain, the network parameters need every port run
+    fn collect_network_parameters(
+        &self,
+        simulation_id: &str,
+        ports: &[SParameterPort],
+        excitation: usize,
+    ) -> Option<(String, SimulationResult)> {
+        let maybe_run = SParameterRun::from_vectors(&self.get_current_plot_vectors(), ports.len());
+        let sparameter_id = get_sparameter_id(simulation_id);
+
+        let mut orch_guard = self.thread_orchestrator.lock().unwrap();
+
+        // The other port runs would wait for this one forever
+        let Some(run) = maybe_run else {
+            orch_guard.fail_simulation(
+                &sparameter_id,
+                SimulatorError::IncompleteAnalysis(simulation_id.to_owned()),
+            );
+            drop(orch_guard);
+
+            return None;
+        };
+
+        let maybe_runs =
+            orch_guard.complete_sparameter_run(&sparameter_id, excitation, ports.len(), run);
+        drop(orch_guard);
+
+        let network = NetworkParameters::from_runs(
+            &maybe_runs?,
+            ports.iter().map(|port| port.node.to_owned()).collect(),
+            ports.iter().map(|port| port.impedance.value()).collect(),
+        )?;
+
+        Some((sparameter_id, SimulationResult::Sp(network)))
+    }
+
+    // Keeps every vector of the plots the simulation made, for querying once it's done
+    fn store_results(&self, simulation_id: &str) {
+        let plots: Vec<StoredPlot> = self
+            .get_all_plots()
+            .into_iter()
+            .filter(|plot| !self.previous_plots.contains(plot))
+            .map(|plot| StoredPlot::new(&plot, &self.get_plot_vectors(&plot)))
+            .collect();
+
+        log::info!(
+            "Thread {}: storing {} plots of {}",
+            self.id,
+            plots.len(),
+            simulation_id
+        );
+
+        let simulation = StoredSimulation {
+            netlist: self.ongoing_netlist.clone(),
+            plots,
+        };
+
+        let mut orch_guard = self.thread_orchestrator.lock().unwrap();
+        orch_guard.store_simulation(simulation_id, simulation);
+        drop(orch_guard);
+    }
+
+    fn send_results(&self, simulation_id: &str, simulation: &Simulation) {
+        let maybe_result = match simulation {
+            Simulation::Stability { injection, .. } => {
+                self.collect_loop_gain(simulation_id, injection)
+            }
+
+            Simulation::Sp {
+                ports, excitation, ..
+            } => self.collect_network_parameters(simulation_id, ports, *excitation),
+
+            _ => self
+                .collect_results(simulation)
+                .map(|result| (simulation_id.to_owned(), result)),
+        };
+
+        if let Some((id, result)) = maybe_result {
+            let mut orch_guard = self.thread_orchestrator.lock().unwrap();
+            orch_guard.store_result(&id, result.clone());
+            drop(orch_guard);
+
+            if let Err(_) = self
+                .sink
+                .send_result(SimulationResultPayload { result, id })
+            {
+                log::error!("Thread {}: failed to send results", self.id);
+            }
+        }
+    }
+
+    fn command_output(&self, command: &str) -> Vec<String> {
+        match &self.spice {
+            Some(spice) => spice.command_output(command, &self.manager),
+            None => Vec::default(),
+        }
+    }
+
+    // Sends what the simulation that just ended took, read before its circuit is removed
+    fn send_resource_report(&self, simulation_id: &str) {
+        let rusage_output = self.command_output("rusage");
+
+        let orch_guard = self.thread_orchestrator.lock().unwrap();
+        let (run_time, points) = orch_guard.get_simulation_usage(self.id);
+        drop(orch_guard);
+
+        let report = ResourceReport::new(run_time, points, &rusage_output);
+        log::info!("Thread {}: {} took {:?}", self.id, simulation_id, report);
+
+        if let Err(_) = self.sink.send_report(ResourceReportPayload {
+            report,
+            id: simulation_id.to_owned(),
+        }) {
+            log::error!("Thread {}: failed to send resource report", self.id);
+        }
     }
 
     pub fn clear_resources(&self) {
@@ -468,9 +914,377 @@ impl Simulator {
         // dl_ngSpice_Command("rusage");
         // dl_ngSpice_Command("resetco");
 
-        self.spice.command("destroy all");
-        self.spice.command("remcirc");
-        self.spice.command("rusage");
-        self.spice.command("resetco");
+        self.command("destroy all");
+        self.command("remcirc");
+        self.command("rusage");
+        self.command("resetco");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        thread,
+        time::{Duration, Instant},
+    };
+
+    use super::super::{
+        paprika::{
+            ngspice::types::{PkVecvalues, PkVecvaluesall},
+            spice::fake::{FakeEvent, FakeScript},
+        },
+        simulation::SimulationConfig,
+        simulation_data::{SimulationDataPayload, SimulationSchemaPayload},
+        simulation_status::{SimulationStatus, SimulationStatusPayload},
+        sink::SinkClosed,
+    };
+    use super::*;
+
+    // Everything the threads sent, in order
+    #[derive(Debug, Clone)]
+    enum SinkEvent {
+        Schema,
+        Data(Vec<f64>),
+        Status(SimulationStatus),
+        Result,
+        Report,
+    }
+
+    #[derive(Default)]
+    struct RecordingSink {
+        events: Mutex<Vec<SinkEvent>>,
+    }
+
+    impl RecordingSink {
+        fn events(&self) -> Vec<SinkEvent> {
+            self.events.lock().unwrap().clone()
+        }
+
+        fn statuses(&self) -> Vec<SimulationStatus> {
+            self.events()
+                .into_iter()
+                .filter_map(|event| match event {
+                    SinkEvent::Status(status) => Some(status),
+                    _ => None,
+                })
+                .collect()
+        }
+
+        // Times of every point sent, one vec per payload
+        fn data(&self) -> Vec<Vec<f64>> {
+            self.events()
+                .into_iter()
+                .filter_map(|event| match event {
+                    SinkEvent::Data(times) => Some(times),
+                    _ => None,
+                })
+                .collect()
+        }
+
+        fn push(&self, event: SinkEvent) -> Result<(), SinkClosed> {
+            self.events.lock().unwrap().push(event);
+            Ok(())
+        }
+    }
+
+    impl ResultSink for RecordingSink {
+        fn send_schema(&self, _payload: SimulationSchemaPayload) -> Result<(), SinkClosed> {
+            self.push(SinkEvent::Schema)
+        }
+
+        fn send_data(&self, payload: SimulationDataPayload) -> Result<(), SinkClosed> {
+            let times = payload
+                .data
+                .iter()
+                .map(|point| point.get_values()[0].0)
+                .collect();
+
+            self.push(SinkEvent::Data(times))
+        }
+
+        fn send_status(&self, payload: SimulationStatusPayload) -> Result<(), SinkClosed> {
+            self.push(SinkEvent::Status(payload.status))
+        }
+
+        fn send_result(&self, _payload: SimulationResultPayload) -> Result<(), SinkClosed> {
+            self.push(SinkEvent::Result)
+        }
+
+        fn send_report(&self, _payload: ResourceReportPayload) -> Result<(), SinkClosed> {
+            self.push(SinkEvent::Report)
+        }
+    }
+
+    fn point(time: f64) -> FakeEvent {
+        FakeEvent::SendData(PkVecvaluesall {
+            count: 2,
+            index: 0,
+            vecsa: vec![
+                Box::new(PkVecvalues {
+                    name: "time".to_owned(),
+                    creal: time,
+                    cimag: 0.0,
+                    is_scale: true,
+                    is_complex: false,
+                }),
+                Box::new(PkVecvalues {
+                    name: "V(out)".to_owned(),
+                    creal: time * 2.0,
+                    cimag: 0.0,
+                    is_scale: false,
+                    is_complex: false,
+                }),
+            ],
+        })
+    }
+
+    // A point every `interval`, at times 1 to count
+    fn points(count: usize, interval: Duration) -> Vec<FakeEvent> {
+        (1..=count)
+            .flat_map(|time| [point(time as f64), FakeEvent::Wait(interval)])
+            .collect()
+    }
+
+    fn tran() -> SimulationConfig {
+        SimulationConfig::Tran {
+            tstep: Some("1m".to_owned()),
+            tstop: Some("5m".to_owned()),
+            tstart: None,
+            tmax: None,
+            uic: None,
+            limits: None,
+            breakpoints: None,
+        }
+    }
+
+    // Runs the simulations on a single thread replaying the script, like the runner does
+    fn start(
+        script: FakeScript,
+        simulation_ids: &[&str],
+    ) -> (
+        Arc<Mutex<SimulationThreadOrchestrator>>,
+        Arc<RecordingSink>,
+        thread::JoinHandle<()>,
+    ) {
+        let sink = Arc::new(RecordingSink::default());
+        let simulations = simulation_ids
+            .iter()
+            .map(|simulation_id| (simulation_id.to_string(), tran()))
+            .collect();
+
+        let orchestrator = Arc::new(Mutex::new(SimulationThreadOrchestrator::new(
+            simulations,
+            sink.clone(),
+            1,
+            None,
+        )));
+
+        let t_orchestrator = Arc::clone(&orchestrator);
+        let t_sink: Arc<dyn ResultSink> = sink.clone();
+
+        let handle = thread::spawn(move || {
+            let mut simulator =
+                Simulator::init(0, t_orchestrator, SpiceSource::Fake(script), t_sink, None)
+                    .unwrap();
+
+            simulator.load_schematic(Schematic::new());
+            simulator.run();
+            simulator.close();
+        });
+
+        (orchestrator, sink, handle)
+    }
+
+    fn wait_until(mut condition: impl FnMut() -> bool) {
+        let deadline = Instant::now() + Duration::from_secs(5);
+
+        while !condition() {
+            assert!(Instant::now() < deadline, "Timed out waiting");
+            thread::sleep(Duration::from_millis(5));
+        }
+    }
+
+    fn point_count(orchestrator: &Arc<Mutex<SimulationThreadOrchestrator>>) -> usize {
+        orchestrator.lock().unwrap().get_simulation_usage(0).1
+    }
+
+    #[test]
+    fn data_is_flushed_once_the_threshold_elapsed_and_when_done() {
+        let events = vec![
+            point(1.0),
+            point(2.0),
+            point(3.0),
+            FakeEvent::Wait(Duration::from_millis(200)),
+            point(4.0),
+            point(5.0),
+            point(6.0),
+        ];
+
+        let (_, sink, handle) = start(
+            FakeScript {
+                events,
+                ..Default::default()
+            },
+            &["tran"],
+        );
+        handle.join().unwrap();
+
+        // The fourth point comes in past the threshold and flushes the buffer,
+        // the rest goes out when ngspice is done
+        assert_eq!(sink.data(), vec![vec![1.0, 2.0, 3.0, 4.0], vec![5.0, 6.0]]);
+    }
+
+    #[test]
+    fn finished_background_thread_moves_the_thread_to_idle_and_sends_ready() {
+        let sink = Arc::new(RecordingSink::default());
+        let orchestrator = Arc::new(Mutex::new(SimulationThreadOrchestrator::new(
+            HashMap::from([("tran".to_owned(), tran())]),
+            sink.clone(),
+            1,
+            None,
+        )));
+
+        let mut orch_guard = orchestrator.lock().unwrap();
+        orch_guard.dequeue_simulation();
+        orch_guard.set_active_simulation(0, "tran", None);
+        orch_guard.restart_timer(0);
+        orch_guard.set_thread_status(0, SecondaryThreadStatus::Running);
+        drop(orch_guard);
+
+        let manager = NGGSpiceManager::new(0, Arc::clone(&orchestrator), sink.clone(), None);
+        let spice = SpiceInstance::load(
+            &SpiceSource::Fake(FakeScript {
+                events: points(2, Duration::ZERO),
+                ..Default::default()
+            }),
+            0,
+            manager.clone(),
+        )
+        .unwrap();
+
+        spice.simulate("* netlist", &[], &manager).unwrap();
+        wait_until(|| !spice.is_running());
+
+        let orch_guard = orchestrator.lock().unwrap();
+        assert!(matches!(
+            orch_guard.get_thread_status(0),
+            SecondaryThreadStatus::Idle
+        ));
+        drop(orch_guard);
+
+        assert_eq!(sink.data(), vec![vec![1.0, 2.0]]);
+        assert!(matches!(
+            sink.statuses().as_slice(),
+            [SimulationStatus::Ready]
+        ));
+
+        spice.close(0);
+    }
+
+    #[test]
+    fn paused_simulations_resume_where_they_stopped() {
+        let (orchestrator, sink, handle) = start(
+            FakeScript {
+                events: points(6, Duration::from_millis(30)),
+                ..Default::default()
+            },
+            &["tran"],
+        );
+
+        wait_until(|| point_count(&orchestrator) >= 2);
+        orchestrator.lock().unwrap().pause_simulation("tran");
+
+        wait_until(|| {
+            let orch_guard = orchestrator.lock().unwrap();
+            matches!(
+                orch_guard.get_thread_status(0),
+                SecondaryThreadStatus::Halted
+            )
+        });
+        let paused_at = point_count(&orchestrator);
+        thread::sleep(Duration::from_millis(100));
+        assert_eq!(point_count(&orchestrator), paused_at);
+
+        orchestrator.lock().unwrap().resume_simulation("tran");
+        handle.join().unwrap();
+
+        assert!(matches!(
+            sink.statuses().as_slice(),
+            [
+                SimulationStatus::Paused,
+                SimulationStatus::Running,
+                SimulationStatus::Ready
+            ]
+        ));
+
+        // No point is replayed nor skipped across the pause
+        let times: Vec<f64> = sink.data().concat();
+        assert_eq!(times, vec![1.0, 2.0, 3.0, 4.0, 5.0, 6.0]);
+    }
+
+    #[test]
+    fn cancelled_simulations_are_dropped_without_results() {
+        let (orchestrator, sink, handle) = start(
+            FakeScript {
+                events: points(20, Duration::from_millis(20)),
+                ..Default::default()
+            },
+            &["tran"],
+        );
+
+        wait_until(|| point_count(&orchestrator) >= 2);
+        orchestrator.lock().unwrap().cancel_simulation("tran");
+        handle.join().unwrap();
+
+        assert!(matches!(
+            sink.statuses().as_slice(),
+            [SimulationStatus::Cancelled]
+        ));
+        assert!(!sink
+            .events()
+            .iter()
+            .any(|event| matches!(event, SinkEvent::Report | SinkEvent::Result)));
+
+        let orch_guard = orchestrator.lock().unwrap();
+        assert!(orch_guard.get_results().lock().unwrap().is_empty());
+        assert!(matches!(
+            orch_guard.get_thread_status(0),
+            SecondaryThreadStatus::Done
+        ));
+        drop(orch_guard);
+    }
+
+    #[test]
+    fn exited_spice_fails_the_running_simulation_and_restarts_for_the_next() {
+        let mut events = points(1, Duration::ZERO);
+        events.push(FakeEvent::ControlledExit {
+            status: 1,
+            is_immediate: false,
+            is_quit: false,
+        });
+
+        // Every run exits, the second one only runs if ngspice was loaded again
+        let (orchestrator, sink, handle) = start(
+            FakeScript {
+                events,
+                ..Default::default()
+            },
+            &["tran1", "tran2"],
+        );
+        handle.join().unwrap();
+
+        let statuses = sink.statuses();
+        assert_eq!(statuses.len(), 2);
+        assert!(statuses.iter().all(|status| matches!(
+            status,
+            SimulationStatus::Failed(SimulatorError::SpiceExited)
+        )));
+
+        let orch_guard = orchestrator.lock().unwrap();
+        assert!(matches!(
+            orch_guard.get_thread_status(0),
+            SecondaryThreadStatus::Done
+        ));
+        drop(orch_guard);
     }
 }
@@ -2,7 +2,7 @@ use serde::{Deserialize, Serialize};
 
 use super::unit_of_magnitude::UnitOfMagnitudeError;
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum SimulatorError {
     FloatingNode(String),
     UnconfiguredElement(String),
@@ -11,4 +11,45 @@ pub enum SimulatorError {
     UnitError(UnitOfMagnitudeError),
     MalformedSimulationConfig(String),
     FailedToSaveGraphicSpiceFile,
+    // The project file could not be opened or parsed
+    FailedToLoadGraphicSpiceFile(String),
+    // The loop break element is missing or not connected to the marked node
+    InvalidLoopBreak(String),
+    // The port node is not connected to any element or is ground
+    InvalidPort(String),
+    FailedToExportTouchstone,
+    // libngspice could not be loaded or initialized from the given path
+    SpiceFailedToLoad(String),
+    // ngspice exited in the middle of a simulation
+    SpiceExited,
+    // No library was selected, bundled or found installed, or the selected one is missing
+    SpiceLibraryNotFound,
+    FailedToCopySpiceLibrary(String),
+    // The library loaded but its `version` output could not be read
+    UnknownSpiceVersion(String),
+    // The spice_worker binary could not be started
+    FailedToStartWorker(String),
+    // The worker did not answer within the seconds, it is considered stuck
+    WorkerTimedOut(u64),
+    // ngspice could not parse the netlist, holds what it printed to stderr
+    FailedToLoadCircuit(String),
+    // Stopped once its run time in milliseconds went over the configured timeout
+    SimulationTimedOut(u64),
+    // Stopped once ngspice sent more points than allowed
+    PointLimitExceeded(usize),
+    // Estimated over the max thresholds before it was dispatched, memory in bytes
+    SimulationTooLarge { points: usize, memory: usize },
+    // External sources are computed in the app, a worker process can not call back for them
+    ExternalSourcesInWorker,
+    // The run could not be written next to the project
+    FailedToSaveRun,
+    // The project has no saved run with the id
+    RunNotFound(String),
+    // One of the runs does not have the simulation, or they ran different analyses
+    IncomparableRuns(String),
+    // The simulation, or the plot asked for, has no results kept to export
+    NoResultsToExport(String),
+    FailedToExportResults,
+    // A run of a stability or S-parameter analysis left no vectors to combine with the others
+    IncompleteAnalysis(String),
 }
@@ -0,0 +1,25 @@
+use super::{
+    resource_report::ResourceReportPayload,
+    simulation_data::{SimulationDataPayload, SimulationSchemaPayload},
+    simulation_result::SimulationResultPayload,
+    simulation_status::SimulationStatusPayload,
+};
+
+/// Whoever listens to the simulations went away, nothing else can be sent
+#[derive(Debug, Clone)]
+pub struct SinkClosed;
+
+/// Receives everything the simulation threads produce. The app forwards it to the
+/// frontend through tauri channels, the command line runner collects it into files
+pub trait ResultSink: Send + Sync {
+    // Sent before the data of each analysis, its points only carry the values
+    fn send_schema(&self, payload: SimulationSchemaPayload) -> Result<(), SinkClosed>;
+
+    fn send_data(&self, payload: SimulationDataPayload) -> Result<(), SinkClosed>;
+
+    fn send_status(&self, payload: SimulationStatusPayload) -> Result<(), SinkClosed>;
+
+    fn send_result(&self, payload: SimulationResultPayload) -> Result<(), SinkClosed>;
+
+    fn send_report(&self, payload: ResourceReportPayload) -> Result<(), SinkClosed>;
+}
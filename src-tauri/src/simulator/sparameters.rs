@@ -0,0 +1,627 @@
+use std::collections::HashMap;
+
+use num_complex::Complex64;
+
+use super::{paprika::ngspice::types::PkVectorinfo, simulation::SimulationConfig};
+
+const PORT_RUN_SUFFIX: &str = "#port";
+
+type Matrix = Vec<Vec<Complex64>>;
+
+// Port k is driven by Vspk, in series with its reference impedance Rspk
+pub fn get_port_name(index: usize) -> String {
+    format!("sp{}", index + 1)
+}
+
+// Node between the port source and its reference impedance
+pub fn get_port_source_node(index: usize) -> String {
+    format!("{}_src", get_port_name(index))
+}
+
+/// A N-port network needs N AC runs, each one driving a single port while the
+/// others are terminated in their reference impedance
+pub fn expand_sparameter_configs(
+    configs: HashMap<String, SimulationConfig>,
+) -> HashMap<String, SimulationConfig> {
+    let mut expanded: HashMap<String, SimulationConfig> = HashMap::default();
+
+    for (id, config) in configs {
+        match config {
+            SimulationConfig::Sp {
+                fstart,
+                fstop,
+                variation,
+                nx,
+                ports: Some(ports),
+                limits,
+                ..
+            } => {
+                for excitation in 0..ports.len() {
+                    expanded.insert(
+                        format!("{}{}{}", id, PORT_RUN_SUFFIX, excitation + 1),
+                        SimulationConfig::Sp {
+                            fstart: fstart.clone(),
+                            fstop: fstop.clone(),
+                            variation: variation.clone(),
+                            nx,
+                            ports: Some(ports.clone()),
+                            excitation: Some(excitation),
+                            limits: limits.clone(),
+                        },
+                    );
+                }
+            }
+
+            _ => {
+                expanded.insert(id, config);
+            }
+        }
+    }
+
+    expanded
+}
+
+// Id of the S-parameter analysis a port run belongs to
+pub fn get_sparameter_id(run_id: &str) -> String {
+    run_id
+        .rsplit_once(PORT_RUN_SUFFIX)
+        .map(|(id, _)| id)
+        .unwrap_or(run_id)
+        .to_owned()
+}
+
+#[derive(Debug, Clone)]
+pub struct SParameterRun {
+    frequency: Vec<f64>,
+    // Branch current of every port source, indexed by port
+    port_currents: Vec<Vec<Complex64>>,
+}
+
+impl SParameterRun {
+    fn complex_values(vector: &PkVectorinfo) -> Vec<Complex64> {
+        if let Some(compdata) = &vector.compdata {
+            return compdata.clone();
+        }
+
+        if let Some(realdata) = &vector.realdata {
+            return realdata
+                .iter()
+                .map(|value| Complex64::new(*value, 0.0))
+                .collect();
+        }
+
+        Vec::default()
+    }
+
+    pub fn from_vectors(vectors: &[PkVectorinfo], port_count: usize) -> Option<SParameterRun> {
+        let mut frequency: Option<Vec<f64>> = None;
+        let mut port_currents: Vec<Option<Vec<Complex64>>> = vec![None; port_count];
+
+        for vector in vectors {
+            let name = vector.name.to_lowercase();
+
+            if name == "frequency" {
+                frequency = Some(
+                    SParameterRun::complex_values(vector)
+                        .into_iter()
+                        .map(|value| value.re)
+                        .collect(),
+                );
+                continue;
+            }
+
+            for (index, port_current) in port_currents.iter_mut().enumerate() {
+                if name == format!("v{}#branch", get_port_name(index)) {
+                    *port_current = Some(SParameterRun::complex_values(vector));
+                }
+            }
+        }
+
+        Some(SParameterRun {
+            frequency: frequency?,
+            port_currents: port_currents
+                .into_iter()
+                .collect::<Option<Vec<Vec<Complex64>>>>()?,
+        })
+    }
+}
+
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct ComplexValue {
+    pub real: f64,
+    pub imag: f64,
+}
+
+impl ComplexValue {
+    fn to_complex(&self) -> Complex64 {
+        Complex64::new(self.real, self.imag)
+    }
+}
+
+// One matrix per frequency point, indexed as [row][column]
+pub type NetworkMatrices = Vec<Vec<Vec<ComplexValue>>>;
+
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub enum NetworkParameter {
+    S,
+    Y,
+    Z,
+    H,
+}
+
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct NetworkParameters {
+    // Node of each port, in port order
+    pub ports: Vec<String>,
+    pub reference_impedances: Vec<f64>,
+    pub frequency: Vec<f64>,
+    pub s: NetworkMatrices,
+    // Not defined when the matrices to invert are singular, e.g: Z for a shorted port
+    pub y: Option<NetworkMatrices>,
+    pub z: Option<NetworkMatrices>,
+    // Only defined for two-ports
+    pub h: Option<NetworkMatrices>,
+}
+
+fn identity(size: usize) -> Matrix {
+    (0..size)
+        .map(|row| {
+            (0..size)
+                .map(|column| {
+                    if row == column {
+                        Complex64::new(1.0, 0.0)
+                    } else {
+                        Complex64::new(0.0, 0.0)
+                    }
+                })
+                .collect()
+        })
+        .collect()
+}
+
+fn add(a: &Matrix, b: &Matrix, sign: f64) -> Matrix {
+    a.iter()
+        .zip(b.iter())
+        .map(|(a_row, b_row)| {
+            a_row
+                .iter()
+                .zip(b_row.iter())
+                .map(|(a_value, b_value)| a_value + b_value * sign)
+                .collect()
+        })
+        .collect()
+}
+
+fn multiply(a: &Matrix, b: &Matrix) -> Matrix {
+    (0..a.len())
+        .map(|row| {
+            (0..b[0].len())
+                .map(|column| (0..b.len()).map(|k| a[row][k] * b[k][column]).sum())
+                .collect()
+        })
+        .collect()
+}
+
+// diag(left) * matrix * diag(right)
+fn scale(left: &[f64], matrix: &Matrix, right: &[f64]) -> Matrix {
+    matrix
+        .iter()
+        .enumerate()
+        .map(|(row, values)| {
+            values
+                .iter()
+                .enumerate()
+                .map(|(column, value)| value * left[row] * right[column])
+                .collect()
+        })
+        .collect()
+}
+
+// Gauss-Jordan elimination with partial pivoting
+fn invert(matrix: &Matrix) -> Option<Matrix> {
+    let size = matrix.len();
+    let mut left = matrix.clone();
+    let mut right = identity(size);
+
+    for column in 0..size {
+        let pivot = (column..size).max_by(|r1, r2| {
+            left[*r1][column]
+                .norm()
+                .total_cmp(&left[*r2][column].norm())
+        })?;
+
+        if left[pivot][column].norm() < 1e-15 {
+            return None;
+        }
+
+        left.swap(column, pivot);
+        right.swap(column, pivot);
+
+        let divisor = left[column][column];
+
+        for index in 0..size {
+            left[column][index] = left[column][index] / divisor;
+            right[column][index] = right[column][index] / divisor;
+        }
+
+        for row in 0..size {
+            if row == column {
+                continue;
+            }
+
+            let factor = left[row][column];
+
+            for index in 0..size {
+                left[row][index] = left[row][index] - factor * left[column][index];
+                right[row][index] = right[row][index] - factor * right[column][index];
+            }
+        }
+    }
+
+    Some(right)
+}
+
+fn to_values(matrix: &Matrix) -> Vec<Vec<ComplexValue>> {
+    matrix
+        .iter()
+        .map(|row| {
+            row.iter()
+                .map(|value| ComplexValue {
+                    real: value.re,
+                    imag: value.im,
+                })
+                .collect()
+        })
+        .collect()
+}
+
+fn from_values(values: &[Vec<ComplexValue>]) -> Matrix {
+    values
+        .iter()
+        .map(|row| row.iter().map(|value| value.to_complex()).collect())
+        .collect()
+}
+
+impl NetworkParameters {
+    // Z = D (I + S) (I - S)^-1 D, with D = diag(sqrt(Z0))
+    fn s_to_z(s: &Matrix, sqrt_z0: &[f64]) -> Option<Matrix> {
+        let size = s.len();
+        let inverse = invert(&add(&identity(size), s, -1.0))?;
+
+        Some(scale(
+            sqrt_z0,
+            &multiply(&add(&identity(size), s, 1.0), &inverse),
+            sqrt_z0,
+        ))
+    }
+
+    // Y = D^-1 (I - S) (I + S)^-1 D^-1
+    fn s_to_y(s: &Matrix, sqrt_z0: &[f64]) -> Option<Matrix> {
+        let size = s.len();
+        let inverse = invert(&add(&identity(size), s, 1.0))?;
+        let inverse_sqrt_z0: Vec<f64> = sqrt_z0.iter().map(|value| 1.0 / value).collect();
+
+        Some(scale(
+            &inverse_sqrt_z0,
+            &multiply(&add(&identity(size), s, -1.0), &inverse),
+            &inverse_sqrt_z0,
+        ))
+    }
+
+    // Hybrid parameters of a two-port, from its admittance matrix
+    fn y_to_h(y: &Matrix) -> Option<Matrix> {
+        if y.len() != 2 || y[0][0].norm() < 1e-15 {
+            return None;
+        }
+
+        let determinant = y[0][0] * y[1][1] - y[0][1] * y[1][0];
+
+        Some(vec![
+            vec![1.0 / y[0][0], -y[0][1] / y[0][0]],
+            vec![y[1][0] / y[0][0], determinant / y[0][0]],
+        ])
+    }
+
+    // With port j driven by a unit source behind Z0j and I_k the current into port k,
+    // S_kj = (δ_kj - 2 Z0k I_k) sqrt(Z0j / Z0k). The port source branch current
+    // flows out of the port, so I_k = -i(Vspk)
+    pub fn from_runs(
+        runs: &[SParameterRun],
+        ports: Vec<String>,
+        reference_impedances: Vec<f64>,
+    ) -> Option<NetworkParameters> {
+        let size = runs.len();
+        let frequency = runs.first()?.frequency.clone();
+
+        let points = runs
+            .iter()
+            .flat_map(|run| run.port_currents.iter().map(|currents| currents.len()))
+            .min()?
+            .min(frequency.len());
+
+        let sqrt_z0: Vec<f64> = reference_impedances
+            .iter()
+            .map(|impedance| impedance.sqrt())
+            .collect();
+
+        let mut s_matrices: Vec<Matrix> = Vec::with_capacity(points);
+
+        for point in 0..points {
+            let mut s = identity(size);
+
+            for (excitation, run) in runs.iter().enumerate() {
+                for port in 0..size {
+                    let current = -run.port_currents[port][point];
+                    let delta = if port == excitation { 1.0 } else { 0.0 };
+
+                    s[port][excitation] = (Complex64::new(delta, 0.0)
+                        - current * 2.0 * reference_impedances[port])
+                        * (sqrt_z0[excitation] / sqrt_z0[port]);
+                }
+            }
+
+            s_matrices.push(s);
+        }
+
+        let y_matrices: Option<Vec<Matrix>> = s_matrices
+            .iter()
+            .map(|s| NetworkParameters::s_to_y(s, &sqrt_z0))
+            .collect();
+
+        let z_matrices: Option<Vec<Matrix>> = s_matrices
+            .iter()
+            .map(|s| NetworkParameters::s_to_z(s, &sqrt_z0))
+            .collect();
+
+        let h_matrices: Option<Vec<Matrix>> = y_matrices.as_ref().and_then(|y_matrices| {
+            y_matrices
+                .iter()
+                .map(|y| NetworkParameters::y_to_h(y))
+                .collect()
+        });
+
+        let to_network_matrices =
+            |matrices: &Vec<Matrix>| matrices.iter().map(|matrix| to_values(matrix)).collect();
+
+        Some(NetworkParameters {
+            ports,
+            reference_impedances,
+            frequency: frequency.into_iter().take(points).collect(),
+            s: to_network_matrices(&s_matrices),
+            y: y_matrices.as_ref().map(to_network_matrices),
+            z: z_matrices.as_ref().map(to_network_matrices),
+            h: h_matrices.as_ref().map(to_network_matrices),
+        })
+    }
+
+    // Touchstone 1.x only has a single reference impedance, S is renormalized
+    // to the first port's one whenever the ports differ
+    fn touchstone_s(&self, reference: f64) -> Option<Vec<Matrix>> {
+        if self
+            .reference_impedances
+            .iter()
+            .all(|impedance| *impedance == reference)
+        {
+            return Some(self.s.iter().map(|s| from_values(s)).collect());
+        }
+
+        let z = self.z.as_ref()?;
+
+        z.iter()
+            .map(|z| {
+                let size = z.len();
+                let normalized: Matrix = from_values(z)
+                    .into_iter()
+                    .map(|row| row.into_iter().map(|value| value / reference).collect())
+                    .collect();
+
+                let inverse = invert(&add(&normalized, &identity(size), 1.0))?;
+
+                Some(multiply(&add(&normalized, &identity(size), -1.0), &inverse))
+            })
+            .collect()
+    }
+
+    /// Touchstone (.sNp) representation of the given parameter, values are in real/imaginary
+    /// format and Y, Z and H are normalized to the reference impedance as in Touchstone 1.x
+    pub fn to_touchstone(&self, parameter: &NetworkParameter) -> Option<String> {
+        let reference = *self.reference_impedances.first()?;
+
+        let (letter, matrices): (&str, Vec<Matrix>) = match parameter {
+            NetworkParameter::S => ("S", self.touchstone_s(reference)?),
+            NetworkParameter::Y => (
+                "Y",
+                self.y
+                    .as_ref()?
+                    .iter()
+                    .map(|y| {
+                        scale(
+                            &vec![reference; y.len()],
+                            &from_values(y),
+                            &vec![1.0; y.len()],
+                        )
+                    })
+                    .collect(),
+            ),
+            NetworkParameter::Z => (
+                "Z",
+                self.z
+                    .as_ref()?
+                    .iter()
+                    .map(|z| {
+                        scale(
+                            &vec![1.0 / reference; z.len()],
+                            &from_values(z),
+                            &vec![1.0; z.len()],
+                        )
+                    })
+                    .collect(),
+            ),
+            NetworkParameter::H => (
+                "H",
+                self.h
+                    .as_ref()?
+                    .iter()
+                    .map(|h| {
+                        let mut h = from_values(h);
+                        h[0][0] = h[0][0] / reference;
+                        h[1][1] = h[1][1] * reference;
+                        h
+                    })
+                    .collect(),
+            ),
+        };
+
+        let size = self.ports.len();
+
+        let mut formatted = format!("! Graphic Spice {}-port {} parameters\n", size, letter);
+
+        for (index, port) in self.ports.iter().enumerate() {
+            formatted.push_str(&format!(
+                "! Port {}: {}, Z0 = {}\n",
+                index + 1,
+                port,
+                self.reference_impedances[index]
+            ));
+        }
+
+        formatted.push_str(&format!("# Hz {} RI R {}\n", letter, reference));
+
+        let format_value = |value: &Complex64| format!(" {:e} {:e}", value.re, value.im);
+
+        for (frequency, matrix) in self.frequency.iter().zip(matrices.iter()) {
+            formatted.push_str(&format!("{:e}", frequency));
+
+            // Two-ports are written in column order, N11 N21 N12 N22, on a single line
+            if size <= 2 {
+                for column in 0..size {
+                    for row in 0..size {
+                        formatted.push_str(&format_value(&matrix[row][column]));
+                    }
+                }
+
+                formatted.push('\n');
+                continue;
+            }
+
+            // Larger networks write a row per line, with at most 4 values per line
+            for row in matrix {
+                for (index, chunk) in row.chunks(4).enumerate() {
+                    if index > 0 {
+                        formatted.push_str("\n ");
+                    }
+
+                    for value in chunk {
+                        formatted.push_str(&format_value(value));
+                    }
+                }
+
+                formatted.push('\n');
+            }
+        }
+
+        Some(formatted)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_close(value: &ComplexValue, real: f64) {
+        assert!(
+            (value.real - real).abs() < 1e-9 && value.imag.abs() < 1e-9,
+            "{:?} is not {}",
+            value,
+            real
+        );
+    }
+
+    // Branch currents of the port sources, flowing out of the ports
+    fn run(port_currents: Vec<f64>) -> SParameterRun {
+        SParameterRun {
+            frequency: vec![1e6],
+            port_currents: port_currents
+                .into_iter()
+                .map(|current| vec![Complex64::new(current, 0.0)])
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn shunt_resistor_converts_to_its_impedance_and_admittance() {
+        // 1V behind 50 ohms into 100 ohms to ground
+        let parameters = NetworkParameters::from_runs(
+            &[run(vec![-1.0 / 150.0])],
+            vec!["out".to_owned()],
+            vec![50.0],
+        )
+        .unwrap();
+
+        assert_close(&parameters.s[0][0][0], 1.0 / 3.0);
+        assert_close(&parameters.z.as_ref().unwrap()[0][0][0], 100.0);
+        assert_close(&parameters.y.as_ref().unwrap()[0][0][0], 0.01);
+        assert!(parameters.h.is_none());
+    }
+
+    #[test]
+    fn series_resistor_has_no_impedance_matrix() {
+        // 50 ohms in between two 50 ohms ports, driven from either side
+        let parameters = NetworkParameters::from_runs(
+            &[
+                run(vec![-1.0 / 150.0, 1.0 / 150.0]),
+                run(vec![1.0 / 150.0, -1.0 / 150.0]),
+            ],
+            vec!["in".to_owned(), "out".to_owned()],
+            vec![50.0, 50.0],
+        )
+        .unwrap();
+
+        let s = &parameters.s[0];
+        assert_close(&s[0][0], 1.0 / 3.0);
+        assert_close(&s[1][0], 2.0 / 3.0);
+        assert_close(&s[0][1], 2.0 / 3.0);
+        assert_close(&s[1][1], 1.0 / 3.0);
+
+        let y = &parameters.y.as_ref().unwrap()[0];
+        assert_close(&y[0][0], 0.02);
+        assert_close(&y[0][1], -0.02);
+        assert_close(&y[1][0], -0.02);
+        assert_close(&y[1][1], 0.02);
+
+        // I - S is singular, the ports can not be left open
+        assert!(parameters.z.is_none());
+    }
+
+    #[test]
+    fn touchstone_is_normalized_to_the_reference_impedance() {
+        let parameters = NetworkParameters::from_runs(
+            &[run(vec![-1.0 / 150.0])],
+            vec!["out".to_owned()],
+            vec![50.0],
+        )
+        .unwrap();
+
+        let touchstone = parameters.to_touchstone(&NetworkParameter::Z).unwrap();
+        let lines: Vec<&str> = touchstone.lines().collect();
+
+        assert_eq!(lines[0], "! Graphic Spice 1-port Z parameters");
+        assert_eq!(lines[1], "! Port 1: out, Z0 = 50");
+        assert_eq!(lines[2], "# Hz Z RI R 50");
+
+        let values: Vec<f64> = lines[3]
+            .split_whitespace()
+            .map(|value| value.parse().unwrap())
+            .collect();
+        assert_eq!(values.len(), 3);
+        assert_eq!(values[0], 1e6);
+        assert!((values[1] - 2.0).abs() < 1e-9);
+        assert_eq!(values[2], 0.0);
+    }
+
+    #[test]
+    fn port_runs_belong_to_their_analysis() {
+        assert_eq!(get_sparameter_id("sp#port2"), "sp");
+        assert_eq!(get_sparameter_id("sp"), "sp");
+    }
+}
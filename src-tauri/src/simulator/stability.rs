@@ -0,0 +1,361 @@
+use std::collections::HashMap;
+
+use num_complex::Complex64;
+
+use super::{
+    paprika::ngspice::types::PkVectorinfo,
+    simulation::{LoopInjection, SimulationConfig},
+};
+
+// Net created between the probe and the marked element when breaking the loop
+pub const LOOP_BREAK_NODE: &str = "loop_break";
+
+// Both the voltage and current probe sources share this name
+pub const LOOP_PROBE_NAME: &str = "loopprobe";
+
+const VOLTAGE_RUN_SUFFIX: &str = "#voltage";
+const CURRENT_RUN_SUFFIX: &str = "#current";
+
+/// Middlebrook's double injection needs two AC runs over the same circuit,
+/// every stability analysis is replaced by its voltage and current runs
+pub fn expand_stability_configs(
+    configs: HashMap<String, SimulationConfig>,
+) -> HashMap<String, SimulationConfig> {
+    let mut expanded: HashMap<String, SimulationConfig> = HashMap::default();
+
+    for (id, config) in configs {
+        match config {
+            SimulationConfig::Stability {
+                element,
+                node,
+                fstart,
+                fstop,
+                variation,
+                nx,
+                limits,
+                ..
+            } => {
+                for (suffix, injection) in [
+                    (VOLTAGE_RUN_SUFFIX, LoopInjection::Voltage),
+                    (CURRENT_RUN_SUFFIX, LoopInjection::Current),
+                ] {
+                    expanded.insert(
+                        format!("{}{}", id, suffix),
+                        SimulationConfig::Stability {
+                            element: element.clone(),
+                            node: node.clone(),
+                            fstart: fstart.clone(),
+                            fstop: fstop.clone(),
+                            variation: variation.clone(),
+                            nx,
+                            injection: Some(injection),
+                            limits: limits.clone(),
+                        },
+                    );
+                }
+            }
+
+            _ => {
+                expanded.insert(id, config);
+            }
+        }
+    }
+
+    expanded
+}
+
+// Id of the stability analysis a voltage or current run belongs to
+pub fn get_stability_id(run_id: &str) -> String {
+    run_id
+        .strip_suffix(VOLTAGE_RUN_SUFFIX)
+        .or(run_id.strip_suffix(CURRENT_RUN_SUFFIX))
+        .unwrap_or(run_id)
+        .to_owned()
+}
+
+#[derive(Debug, Clone)]
+pub struct LoopGainRun {
+    frequency: Vec<f64>,
+    // Voltage at the loop break node
+    v_break: Vec<Complex64>,
+    // Current through the voltage probe, from the loop break node to the original net
+    i_probe: Vec<Complex64>,
+}
+
+impl LoopGainRun {
+    fn complex_values(vector: &PkVectorinfo) -> Vec<Complex64> {
+        if let Some(compdata) = &vector.compdata {
+            return compdata.clone();
+        }
+
+        if let Some(realdata) = &vector.realdata {
+            return realdata
+                .iter()
+                .map(|value| Complex64::new(*value, 0.0))
+                .collect();
+        }
+
+        Vec::default()
+    }
+
+    pub fn from_vectors(vectors: &[PkVectorinfo]) -> Option<LoopGainRun> {
+        let probe_branch = format!("v{}#branch", LOOP_PROBE_NAME);
+
+        let mut frequency: Option<Vec<f64>> = None;
+        let mut v_break: Option<Vec<Complex64>> = None;
+        let mut i_probe: Option<Vec<Complex64>> = None;
+
+        for vector in vectors {
+            let name = vector.name.to_lowercase();
+
+            if name == "frequency" {
+                frequency = Some(
+                    LoopGainRun::complex_values(vector)
+                        .into_iter()
+                        .map(|value| value.re)
+                        .collect(),
+                );
+            } else if name == LOOP_BREAK_NODE {
+                v_break = Some(LoopGainRun::complex_values(vector));
+            } else if name == probe_branch {
+                i_probe = Some(LoopGainRun::complex_values(vector));
+            }
+        }
+
+        Some(LoopGainRun {
+            frequency: frequency?,
+            v_break: v_break?,
+            i_probe: i_probe?,
+        })
+    }
+}
+
+#[derive(Debug, serde::Serialize, serde::Deserialize, Clone)]
+pub struct LoopGainResult {
+    pub frequency: Vec<f64>,
+    pub gain_db: Vec<f64>,
+    // Unwrapped, in degrees
+    pub phase: Vec<f64>,
+    // Frequency where the loop gain crosses 0 dB
+    pub crossover_frequency: Option<f64>,
+    pub phase_margin: Option<f64>,
+    // Frequency where the loop phase crosses -180 degrees
+    pub phase_crossover_frequency: Option<f64>,
+    pub gain_margin: Option<f64>,
+}
+
+impl LoopGainResult {
+    fn unwrap_phase(phase: Vec<f64>) -> Vec<f64> {
+        let mut unwrapped: Vec<f64> = Vec::with_capacity(phase.len());
+        let mut offset = 0.0;
+
+        for (index, value) in phase.iter().enumerate() {
+            if index > 0 {
+                let delta = value - phase[index - 1];
+
+                if delta > 180.0 {
+                    offset -= 360.0;
+                } else if delta < -180.0 {
+                    offset += 360.0;
+                }
+            }
+
+            unwrapped.push(value + offset);
+        }
+
+        unwrapped
+    }
+
+    // First crossing of target, interpolated over log frequency.
+    // Returns the crossing frequency and the index of the point right after it
+    fn find_crossing(frequency: &[f64], values: &[f64], target: f64) -> Option<(f64, usize)> {
+        for index in 1..values.len() {
+            let (v1, v2) = (values[index - 1] - target, values[index] - target);
+
+            if v1 == 0.0 {
+                return Some((frequency[index - 1], index - 1));
+            }
+
+            if v1.signum() != v2.signum() {
+                let ratio = v1 / (v1 - v2);
+
+                let (f1, f2) = (frequency[index - 1], frequency[index]);
+
+                let crossing = if f1 > 0.0 && f2 > 0.0 {
+                    10f64.powf(f1.log10() + ratio * (f2.log10() - f1.log10()))
+                } else {
+                    f1 + ratio * (f2 - f1)
+                };
+
+                return Some((crossing, index));
+            }
+        }
+
+        None
+    }
+
+    fn interpolate(frequency: &[f64], values: &[f64], index: usize, at: f64) -> f64 {
+        if index == 0 || frequency[index] == frequency[index - 1] {
+            return values[index];
+        }
+
+        let (f1, f2) = (frequency[index - 1], frequency[index]);
+
+        let ratio = if f1 > 0.0 && f2 > 0.0 && at > 0.0 {
+            (at.log10() - f1.log10()) / (f2.log10() - f1.log10())
+        } else {
+            (at - f1) / (f2 - f1)
+        };
+
+        values[index - 1] + ratio * (values[index] - values[index - 1])
+    }
+
+    // Tv = (1 - Vx) / Vx from the voltage run, Ti = I / (1 - I) from the current run,
+    // combined as T = (Tv * Ti - 1) / (Tv + Ti + 2)
+    pub fn from_runs(voltage_run: &LoopGainRun, current_run: &LoopGainRun) -> LoopGainResult {
+        let one = Complex64::new(1.0, 0.0);
+        let two = Complex64::new(2.0, 0.0);
+
+        let loop_gain: Vec<Complex64> = voltage_run
+            .v_break
+            .iter()
+            .zip(current_run.i_probe.iter())
+            .map(|(v_break, i_probe)| {
+                let tv = (one - v_break) / v_break;
+                let ti = i_probe / (one - i_probe);
+
+                (tv * ti - one) / (tv + ti + two)
+            })
+            .collect();
+
+        let frequency: Vec<f64> = voltage_run
+            .frequency
+            .iter()
+            .take(loop_gain.len())
+            .copied()
+            .collect();
+
+        let gain_db: Vec<f64> = loop_gain
+            .iter()
+            .map(|value| 20.0 * value.norm().log10())
+            .collect();
+
+        let phase = LoopGainResult::unwrap_phase(
+            loop_gain
+                .iter()
+                .map(|value| value.arg().to_degrees())
+                .collect(),
+        );
+
+        let mut crossover_frequency: Option<f64> = None;
+        let mut phase_margin: Option<f64> = None;
+
+        if let Some((crossing, index)) = LoopGainResult::find_crossing(&frequency, &gain_db, 0.0) {
+            crossover_frequency = Some(crossing);
+            phase_margin =
+                Some(180.0 + LoopGainResult::interpolate(&frequency, &phase, index, crossing));
+        }
+
+        let mut phase_crossover_frequency: Option<f64> = None;
+        let mut gain_margin: Option<f64> = None;
+
+        if let Some((crossing, index)) = LoopGainResult::find_crossing(&frequency, &phase, -180.0) {
+            phase_crossover_frequency = Some(crossing);
+            gain_margin = Some(-LoopGainResult::interpolate(
+                &frequency, &gain_db, index, crossing,
+            ));
+        }
+
+        LoopGainResult {
+            frequency,
+            gain_db,
+            phase,
+            crossover_frequency,
+            phase_margin,
+            phase_crossover_frequency,
+            gain_margin,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Runs measured with the loop broken between a source and load impedance of the
+    // given ratio, Tv = T (1 + r) + r and Ti = T (1 + 1 / r) + 1 / r
+    fn runs(
+        frequency: Vec<f64>,
+        loop_gain: &[Complex64],
+        ratio: f64,
+    ) -> (LoopGainRun, LoopGainRun) {
+        let one = Complex64::new(1.0, 0.0);
+
+        let tv: Vec<Complex64> = loop_gain
+            .iter()
+            .map(|t| t * (1.0 + ratio) + ratio)
+            .collect();
+        let ti: Vec<Complex64> = loop_gain
+            .iter()
+            .map(|t| t * (1.0 + 1.0 / ratio) + 1.0 / ratio)
+            .collect();
+
+        let voltage_run = LoopGainRun {
+            frequency: frequency.clone(),
+            v_break: tv.iter().map(|tv| one / (one + tv)).collect(),
+            i_probe: vec![Complex64::default(); frequency.len()],
+        };
+        let current_run = LoopGainRun {
+            frequency: frequency.clone(),
+            v_break: vec![Complex64::default(); frequency.len()],
+            i_probe: ti.iter().map(|ti| ti / (one + ti)).collect(),
+        };
+
+        (voltage_run, current_run)
+    }
+
+    #[test]
+    fn both_injections_combine_into_the_loop_gain_whatever_the_loading() {
+        let loop_gain = [Complex64::from_polar(10.0, -90f64.to_radians())];
+
+        for ratio in [0.01, 1.0, 100.0] {
+            let (voltage_run, current_run) = runs(vec![1e3], &loop_gain, ratio);
+            let result = LoopGainResult::from_runs(&voltage_run, &current_run);
+
+            assert!((result.gain_db[0] - 20.0).abs() < 1e-9);
+            assert!((result.phase[0] + 90.0).abs() < 1e-9);
+        }
+    }
+
+    #[test]
+    fn phase_margin_is_read_at_the_crossover() {
+        // 20 dB down per decade with a constant -135 degrees, 0 dB at 1 kHz
+        let frequency = vec![100.0, 10e3];
+        let loop_gain: Vec<Complex64> = frequency
+            .iter()
+            .map(|f| Complex64::from_polar(1e3 / f, -135f64.to_radians()))
+            .collect();
+
+        let (voltage_run, current_run) = runs(frequency, &loop_gain, 0.5);
+        let result = LoopGainResult::from_runs(&voltage_run, &current_run);
+
+        assert!((result.crossover_frequency.unwrap() - 1e3).abs() < 1e-6);
+        assert!((result.phase_margin.unwrap() - 45.0).abs() < 1e-9);
+        assert!(result.phase_crossover_frequency.is_none());
+        assert!(result.gain_margin.is_none());
+    }
+
+    #[test]
+    fn phase_is_unwrapped_past_180_degrees() {
+        let unwrapped = LoopGainResult::unwrap_phase(vec![-170.0, 175.0, 160.0]);
+
+        assert_eq!(unwrapped, vec![-170.0, -185.0, -200.0]);
+    }
+
+    #[test]
+    fn voltage_and_current_runs_belong_to_their_analysis() {
+        assert_eq!(get_stability_id("loop#voltage"), "loop");
+        assert_eq!(get_stability_id("loop#current"), "loop");
+        assert_eq!(get_stability_id("loop"), "loop");
+    }
+}
@@ -1,6 +1,6 @@
 use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum UnitOfMagnitudeError {
     FailedToParseBase,
     IncorrectValuePassed,
@@ -42,6 +42,23 @@ impl UnitOfMagnitude {
         }
     }
 
+    // Value in base units, mil is a thousandth of an inch as in SPICE
+    pub fn value(&self) -> f64 {
+        match &self {
+            UnitOfMagnitude::Tera(base) => base * 1e12,
+            UnitOfMagnitude::Giga(base) => base * 1e9,
+            UnitOfMagnitude::Mega(base) => base * 1e6,
+            UnitOfMagnitude::Kilo(base) => base * 1e3,
+            UnitOfMagnitude::Mil(base) => base * 25.4e-6,
+            UnitOfMagnitude::Mili(base) => base * 1e-3,
+            UnitOfMagnitude::Micro(base) => base * 1e-6,
+            UnitOfMagnitude::Nano(base) => base * 1e-9,
+            UnitOfMagnitude::Pico(base) => base * 1e-12,
+            UnitOfMagnitude::Femto(base) => base * 1e-15,
+            UnitOfMagnitude::Base(base) => *base,
+        }
+    }
+
     fn format_with_exponential(base: &f64) -> String {
         if *base >= 10e3 || *base <= 1e-3 {
             format!("{:e}", base)
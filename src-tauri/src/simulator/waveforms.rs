@@ -0,0 +1,95 @@
+use std::collections::HashMap;
+
+use super::{
+    decimation::decimate,
+    simulation_data::{SimulationData, SimulationDataPayload, SimulationSchema},
+};
+
+#[derive(Debug, Clone, Default)]
+struct Waveform {
+    schema: Option<SimulationSchema>,
+    data: Vec<SimulationData>,
+}
+
+/// Every point of the simulations of a run, by simulation id. The frontend only gets them
+/// decimated while they run and asks for ranges of them again to zoom in
+#[derive(Debug, Default)]
+pub struct WaveformStore {
+    waveforms: HashMap<String, Waveform>,
+}
+
+impl WaveformStore {
+    // A new plot of the simulation starts over, like the frontend does with its schema
+    pub fn set_schema(&mut self, simulation_id: &str, schema: SimulationSchema) {
+        self.waveforms.insert(
+            simulation_id.to_owned(),
+            Waveform {
+                schema: Some(schema),
+                data: Vec::default(),
+            },
+        );
+    }
+
+    pub fn extend(&mut self, simulation_id: &str, data: &[SimulationData]) {
+        self.waveforms
+            .entry(simulation_id.to_owned())
+            .or_default()
+            .data
+            .extend_from_slice(data);
+    }
+
+    pub fn get_schema(&self, simulation_id: &str) -> Option<&SimulationSchema> {
+        self.waveforms
+            .get(simulation_id)
+            .and_then(|waveform| waveform.schema.as_ref())
+    }
+
+    pub fn get_data(&self, simulation_id: &str) -> Option<&[SimulationData]> {
+        self.waveforms
+            .get(simulation_id)
+            .map(|waveform| &waveform.data[..])
+    }
+
+    /// Points with their scale, time or frequency, between `from` and `to`, plus the ones
+    /// right outside so lines reach the edges. Decimated down to `max_points`
+    pub fn get_range(
+        &self,
+        simulation_id: &str,
+        from: f64,
+        to: f64,
+        max_points: usize,
+    ) -> Option<SimulationDataPayload> {
+        let waveform = self.waveforms.get(simulation_id)?;
+        let scale_index = waveform
+            .schema
+            .as_ref()
+            .and_then(|schema| schema.get_scale_index());
+
+        let data = match scale_index {
+            Some(scale_index) => {
+                let scale = |point: &SimulationData| {
+                    point
+                        .get_values()
+                        .get(scale_index)
+                        .map(|(c_real, _)| *c_real)
+                        .unwrap_or_default()
+                };
+
+                let start = waveform
+                    .data
+                    .partition_point(|point| scale(point) < from)
+                    .saturating_sub(1);
+                let end = (waveform.data.partition_point(|point| scale(point) <= to) + 1)
+                    .min(waveform.data.len());
+
+                &waveform.data[start..end.max(start)]
+            }
+            None => &waveform.data[..],
+        };
+
+        Some(SimulationDataPayload {
+            data: decimate(data, scale_index, max_points),
+            id: simulation_id.to_owned(),
+        })
+    }
+}
@@ -0,0 +1,311 @@
+use std::{
+    path::{Path, PathBuf},
+    process::{Child, ChildStdin, Command, Stdio},
+    sync::{
+        atomic::{AtomicBool, AtomicU64, Ordering},
+        mpsc::{self, Receiver, RecvTimeoutError},
+        Arc, Mutex,
+    },
+    thread::{self, JoinHandle},
+    time::{Duration, Instant},
+};
+
+use crate::{
+    paprika::ngspice::types::PkVectorinfo,
+    simulator::{manager::NGGSpiceManager, simulator_error::SimulatorError},
+};
+
+use super::protocol::{
+    read_frame, write_frame, WorkerEvent, WorkerReply, WorkerRequest, WorkerRequestFrame,
+    LOADED_REPLY_ID,
+};
+
+const WORKER_NAME: &str = "spice_worker";
+
+// Queries are answered right away, a worker taking longer is considered stuck
+const REPLY_TIMEOUT: Duration = Duration::from_secs(10);
+
+// Loading the netlist can take a while on large circuits, the analysis then runs
+// in the background
+const SIMULATE_TIMEOUT: Duration = Duration::from_secs(120);
+
+// Built next to the app by cargo, see src/bin/spice_worker.rs, bundles put it there
+// as an external binary, see tauri.bundle.conf.json
+fn get_worker_path() -> Result<PathBuf, SimulatorError> {
+    let worker_name = format!("{}{}", WORKER_NAME, std::env::consts::EXE_SUFFIX);
+
+    std::env::current_exe()
+        .map(|app_path| app_path.with_file_name(&worker_name))
+        .map_err(|_| SimulatorError::FailedToStartWorker(worker_name))
+}
+
+// Waits for the reply to the request with the id. Replies to requests that timed out
+// before are dropped as they come in. Disconnected once the worker ended
+fn receive_reply(
+    replies: &Receiver<(u64, WorkerReply)>,
+    id: u64,
+    timeout: Duration,
+) -> Result<WorkerReply, RecvTimeoutError> {
+    let deadline = Instant::now() + timeout;
+
+    loop {
+        let time_left = deadline.saturating_duration_since(Instant::now());
+
+        match replies.recv_timeout(time_left) {
+            Ok((reply_id, reply)) if reply_id == id => return Ok(reply),
+            Ok((reply_id, _)) => log::error!("Dropping late worker reply to request {}", reply_id),
+            Err(error) => return Err(error),
+        }
+    }
+}
+
+/// A spice_worker child process running ngspice on behalf of a simulation thread
+pub struct WorkerProcess {
+    id: usize,
+    child: Child,
+    stdin: Mutex<ChildStdin>,
+    replies: Mutex<Receiver<(u64, WorkerReply)>>,
+    next_request_id: AtomicU64,
+    // Only set while the worker is loaded and not being stopped on purpose,
+    // so it ending is reported as a crash
+    supervised: Arc<AtomicBool>,
+    reader: Option<JoinHandle<()>>,
+}
+
+impl WorkerProcess {
+    pub fn spawn(
+        id: usize,
+        lib_path: &Path,
+        manager: NGGSpiceManager,
+    ) -> Result<WorkerProcess, SimulatorError> {
+        let worker_path = get_worker_path()?;
+        let failed_to_start =
+            || SimulatorError::FailedToStartWorker(worker_path.to_string_lossy().to_string());
+
+        let mut child = Command::new(&worker_path)
+            .arg(lib_path)
+            .arg(id.to_string())
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::inherit())
+            .spawn()
+            .map_err(|error| {
+                log::error!("Thread {}: failed to start worker {:?}", id, error);
+                failed_to_start()
+            })?;
+
+        let stdin = child.stdin.take().ok_or_else(failed_to_start)?;
+        let mut stdout = child.stdout.take().ok_or_else(failed_to_start)?;
+
+        let (reply_sender, reply_receiver) = mpsc::channel::<(u64, WorkerReply)>();
+        let supervised = Arc::new(AtomicBool::new(false));
+
+        let t_supervised = Arc::clone(&supervised);
+        let mut t_manager = manager;
+
+        // Turns the worker events back into the callbacks the in-process ngspice would call
+        let reader = thread::spawn(move || {
+            let mut has_exited = false;
+
+            loop {
+                match read_frame::<WorkerEvent>(&mut stdout) {
+                    Ok(Some(WorkerEvent::Status(status))) => t_manager.handle_status(status),
+                    Ok(Some(WorkerEvent::Schema(schema))) => t_manager.handle_schema(schema),
+                    Ok(Some(WorkerEvent::Data(data))) => t_manager.handle_data(data),
+                    Ok(Some(WorkerEvent::BackgroundThread { is_fin })) => {
+                        t_manager.handle_bgt_state(is_fin)
+                    }
+                    Ok(Some(WorkerEvent::Exited)) => {
+                        has_exited = true;
+                        t_manager.handle_exit();
+                    }
+                    Ok(Some(WorkerEvent::Reply { id, reply })) => {
+                        let _ = reply_sender.send((id, reply));
+                    }
+
+                    Ok(None) | Err(_) => break,
+                }
+            }
+
+            // The worker crashed, same as ngspice exiting in process
+            if !has_exited && t_supervised.load(Ordering::SeqCst) {
+                log::error!("Thread {}: worker process ended unexpectedly", id);
+                t_manager.handle_exit();
+            }
+        });
+
+        let worker = WorkerProcess {
+            id,
+            child,
+            stdin: Mutex::new(stdin),
+            replies: Mutex::new(reply_receiver),
+            next_request_id: AtomicU64::new(LOADED_REPLY_ID + 1),
+            supervised,
+            reader: Some(reader),
+        };
+
+        match worker.wait_reply(LOADED_REPLY_ID, REPLY_TIMEOUT) {
+            Ok(WorkerReply::Loaded(true)) => {
+                worker.supervised.store(true, Ordering::SeqCst);
+                Ok(worker)
+            }
+            _ => {
+                worker.close();
+                Err(SimulatorError::SpiceFailedToLoad(
+                    lib_path.to_string_lossy().to_string(),
+                ))
+            }
+        }
+    }
+
+    // Id of the request its reply comes back with, None if the worker could not be reached
+    fn send(&self, request: WorkerRequest) -> Option<u64> {
+        let id = self.next_request_id.fetch_add(1, Ordering::SeqCst);
+        let mut stdin = self.stdin.lock().unwrap();
+
+        match write_frame(&mut *stdin, &WorkerRequestFrame { id, request }) {
+            Ok(()) => Some(id),
+            Err(error) => {
+                log::error!("Thread {}: failed to reach worker {:?}", self.id, error);
+                None
+            }
+        }
+    }
+
+    fn wait_reply(&self, id: u64, timeout: Duration) -> Result<WorkerReply, RecvTimeoutError> {
+        let replies = self.replies.lock().unwrap();
+
+        receive_reply(&replies, id, timeout)
+    }
+
+    fn request(&self, request: WorkerRequest) -> Option<WorkerReply> {
+        let id = self.send(request)?;
+
+        self.wait_reply(id, REPLY_TIMEOUT).ok()
+    }
+
+    // A worker that died without replying is restarted by its supervision, which
+    // fails the simulation, so only a load failure or no reply in time is an error here
+    pub fn simulate(&self, netlist: &str, breakpoints: &[f64]) -> Result<(), SimulatorError> {
+        let Some(id) = self.send(WorkerRequest::Simulate {
+            netlist: netlist.to_owned(),
+            breakpoints: breakpoints.to_vec(),
+        }) else {
+            return Ok(());
+        };
+
+        match self.wait_reply(id, SIMULATE_TIMEOUT) {
+            Ok(WorkerReply::CircuitLoaded(loaded)) => {
+                loaded.map_err(SimulatorError::FailedToLoadCircuit)
+            }
+            Ok(_) | Err(RecvTimeoutError::Disconnected) => Ok(()),
+            Err(RecvTimeoutError::Timeout) => {
+                log::error!(
+                    "Thread {}: worker did not load the netlist in time",
+                    self.id
+                );
+                Err(SimulatorError::WorkerTimedOut(SIMULATE_TIMEOUT.as_secs()))
+            }
+        }
+    }
+
+    pub fn set_breakpoint(&self, time: f64) {
+        self.send(WorkerRequest::SetBreakpoint(time));
+    }
+
+    pub fn command(&self, command: &str) {
+        self.send(WorkerRequest::Command(command.to_owned()));
+    }
+
+    pub fn get_cur_plot(&self) -> String {
+        match self.request(WorkerRequest::GetCurrentPlot) {
+            Some(WorkerReply::CurrentPlot(plot)) => plot,
+            _ => String::default(),
+        }
+    }
+
+    pub fn get_all_plots(&self) -> Vec<String> {
+        match self.request(WorkerRequest::GetAllPlots) {
+            Some(WorkerReply::AllPlots(plots)) => plots,
+            _ => Vec::default(),
+        }
+    }
+
+    pub fn get_plot_vectors(&self, plot: &str) -> Vec<PkVectorinfo> {
+        match self.request(WorkerRequest::GetPlotVectors(plot.to_owned())) {
+            Some(WorkerReply::PlotVectors(vectors)) => vectors,
+            _ => Vec::default(),
+        }
+    }
+
+    pub fn command_output(&self, command: &str) -> Vec<String> {
+        match self.request(WorkerRequest::CommandOutput(command.to_owned())) {
+            Some(WorkerReply::Output(output)) => output,
+            _ => Vec::default(),
+        }
+    }
+
+    pub fn close(mut self) {
+        self.supervised.store(false, Ordering::SeqCst);
+
+        // A crashed worker can not quit by itself
+        if self.send(WorkerRequest::Quit).is_none() {
+            let _ = self.child.kill();
+        }
+
+        if let Err(error) = self.child.wait() {
+            log::error!("Thread {}: failed to wait for worker {:?}", self.id, error);
+        }
+
+        if let Some(reader) = self.reader.take() {
+            let _ = reader.join();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn late_replies_do_not_answer_the_next_request() {
+        let (sender, receiver) = mpsc::channel::<(u64, WorkerReply)>();
+
+        // Request 1 timed out, its reply comes in while request 2 waits
+        sender
+            .send((1, WorkerReply::CurrentPlot("tran1".to_owned())))
+            .unwrap();
+        sender
+            .send((2, WorkerReply::CurrentPlot("ac1".to_owned())))
+            .unwrap();
+
+        match receive_reply(&receiver, 2, Duration::from_millis(100)) {
+            Ok(WorkerReply::CurrentPlot(plot)) => assert_eq!(plot, "ac1"),
+            reply => panic!("Unexpected reply {:?}", reply),
+        }
+    }
+
+    #[test]
+    fn no_reply_with_the_id_times_out() {
+        let (sender, receiver) = mpsc::channel::<(u64, WorkerReply)>();
+
+        sender.send((1, WorkerReply::AllPlots(vec![]))).unwrap();
+
+        assert!(matches!(
+            receive_reply(&receiver, 2, Duration::from_millis(20)),
+            Err(RecvTimeoutError::Timeout)
+        ));
+    }
+
+    #[test]
+    fn an_ended_worker_is_not_a_timeout() {
+        let (sender, receiver) = mpsc::channel::<(u64, WorkerReply)>();
+
+        drop(sender);
+
+        assert!(matches!(
+            receive_reply(&receiver, 2, Duration::from_secs(10)),
+            Err(RecvTimeoutError::Disconnected)
+        ));
+    }
+}
@@ -0,0 +1,189 @@
+use std::{ffi::OsStr, path::PathBuf, thread::sleep, time::Duration};
+
+use libloading::Library;
+
+use crate::{
+    paprika::{
+        ngspice::types::PkVectorinfo,
+        spice::{
+            backend::SpiceBackend,
+            fake::{FakeScript, FakeSpice},
+            spice::Spice,
+        },
+    },
+    simulator::{manager::NGGSpiceManager, simulator_error::SimulatorError},
+};
+
+use super::{check_circuit_output, client::WorkerProcess, WorkerMode};
+
+/// Where a simulation thread gets its ngspice from, kept to load it again after it exits
+#[derive(Debug, Clone)]
+pub enum SpiceSource {
+    Library { mode: WorkerMode, path: PathBuf },
+    // Replays the script instead of simulating, nothing is loaded
+    Fake(FakeScript),
+}
+
+/// The ngspice a simulation thread drives, either loaded here or behind a worker process
+pub enum SpiceInstance {
+    // Library needs to live until its done being used, fakes have none
+    InProcess {
+        spice: Box<dyn SpiceBackend>,
+        library: Option<Library>,
+    },
+    Process(WorkerProcess),
+}
+
+impl SpiceInstance {
+    pub fn load(
+        source: &SpiceSource,
+        id: usize,
+        manager: NGGSpiceManager,
+    ) -> Result<SpiceInstance, SimulatorError> {
+        match source {
+            SpiceSource::Library {
+                mode: WorkerMode::InProcess,
+                path,
+            } => {
+                let (spice, library) =
+                    Spice::init(OsStr::new(path.as_os_str()), manager, id as i32).map_err(
+                        |error| {
+                            log::error!("Thread {}: failed to load ngspice {:?}", id, error);
+                            SimulatorError::SpiceFailedToLoad(path.to_string_lossy().to_string())
+                        },
+                    )?;
+
+                Ok(SpiceInstance::InProcess {
+                    spice: Box::new(spice),
+                    library: Some(library),
+                })
+            }
+
+            SpiceSource::Library {
+                mode: WorkerMode::Process,
+                path,
+            } => Ok(SpiceInstance::Process(WorkerProcess::spawn(
+                id, path, manager,
+            )?)),
+
+            SpiceSource::Fake(script) => Ok(SpiceInstance::InProcess {
+                spice: Box::new(FakeSpice::init(script.clone(), manager, id as i32)),
+                library: None,
+            }),
+        }
+    }
+
+    pub fn command(&self, command: &str) {
+        match self {
+            SpiceInstance::InProcess { spice, .. } => {
+                spice.command(command);
+            }
+            SpiceInstance::Process(worker) => worker.command(command),
+        }
+    }
+
+    // Lines ngspice printed while running the command
+    pub fn command_output(&self, command: &str, manager: &NGGSpiceManager) -> Vec<String> {
+        match self {
+            SpiceInstance::InProcess { spice, .. } => {
+                manager.take_output();
+                spice.command(command);
+                manager.take_output()
+            }
+            SpiceInstance::Process(worker) => worker.command_output(command),
+        }
+    }
+
+    // Loads the netlist and runs it in the background, nothing runs if it fails to load.
+    // Breakpoints need the circuit, they are set in between
+    pub fn simulate(
+        &self,
+        netlist: &str,
+        breakpoints: &[f64],
+        manager: &NGGSpiceManager,
+    ) -> Result<(), SimulatorError> {
+        match self {
+            SpiceInstance::InProcess { spice, .. } => {
+                manager.take_output();
+                let has_failed = spice.circ(&netlist.lines().collect::<Vec<&str>>());
+                let loaded = check_circuit_output(has_failed, &manager.take_output());
+
+                if loaded.is_ok() {
+                    for breakpoint in breakpoints {
+                        spice.set_breakpoint(*breakpoint);
+                    }
+
+                    spice.command("bg_run");
+                }
+
+                loaded.map_err(SimulatorError::FailedToLoadCircuit)
+            }
+            SpiceInstance::Process(worker) => worker.simulate(netlist, breakpoints),
+        }
+    }
+
+    pub fn set_breakpoint(&self, time: f64) {
+        match self {
+            SpiceInstance::InProcess { spice, .. } => {
+                spice.set_breakpoint(time);
+            }
+            SpiceInstance::Process(worker) => worker.set_breakpoint(time),
+        }
+    }
+
+    pub fn is_running(&self) -> bool {
+        match self {
+            SpiceInstance::InProcess { spice, .. } => spice.is_running(),
+            // The worker keeps its own background thread, nothing runs in this process
+            SpiceInstance::Process(_) => false,
+        }
+    }
+
+    pub fn get_cur_plot(&self) -> String {
+        match self {
+            SpiceInstance::InProcess { spice, .. } => spice.get_cur_plot(),
+            SpiceInstance::Process(worker) => worker.get_cur_plot(),
+        }
+    }
+
+    pub fn get_all_plots(&self) -> Vec<String> {
+        match self {
+            SpiceInstance::InProcess { spice, .. } => spice.get_all_plots(),
+            SpiceInstance::Process(worker) => worker.get_all_plots(),
+        }
+    }
+
+    pub fn get_plot_vectors(&self, plot: &str) -> Vec<PkVectorinfo> {
+        match self {
+            SpiceInstance::InProcess { spice, .. } => spice
+                .get_all_vecs(plot)
+                .into_iter()
+                .filter_map(|vector| spice.get_vec_info(&format!("{}.{}", plot, vector)))
+                .collect(),
+            SpiceInstance::Process(worker) => worker.get_plot_vectors(plot),
+        }
+    }
+
+    pub fn close(self, id: usize) {
+        match self {
+            SpiceInstance::InProcess { spice, library } => {
+                // Gives the background thread a chance to wind down before unloading
+                for _ in 0..100 {
+                    if !spice.is_running() {
+                        break;
+                    }
+
+                    sleep(Duration::from_millis(10));
+                }
+
+                drop(spice);
+
+                if let Some(Err(error)) = library.map(|library| library.close()) {
+                    log::error!("Thread {}: failed to unload ngspice {:?}", id, error);
+                }
+            }
+
+            SpiceInstance::Process(worker) => worker.close(),
+        }
+    }
+}
@@ -0,0 +1,35 @@
+pub mod client;
+pub mod instance;
+pub mod protocol;
+pub mod server;
+
+use serde::{Deserialize, Serialize};
+
+/// Where each simulation thread runs ngspice
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub enum WorkerMode {
+    // Loaded into the app through libloading, fastest but a crash takes the app down
+    #[default]
+    InProcess,
+    // Loaded by a spice_worker child process, a crash only takes down that worker
+    Process,
+}
+
+// ngspice prints what is wrong with the netlist to stderr, without always making
+// ngSpice_Circ fail for it. Err holds those lines
+pub fn check_circuit_output(has_failed: bool, output: &[String]) -> Result<(), String> {
+    let stderr: Vec<&str> = output
+        .iter()
+        .filter_map(|line| line.strip_prefix("stderr "))
+        .collect();
+
+    let has_errors = stderr
+        .iter()
+        .any(|line| line.trim_start().to_lowercase().starts_with("error"));
+
+    if !has_failed && !has_errors {
+        return Ok(());
+    }
+
+    Err(stderr.join("\n"))
+}
@@ -0,0 +1,170 @@
+use std::io::{self, ErrorKind, Read, Write};
+
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+
+use crate::{
+    paprika::ngspice::types::PkVectorinfo,
+    simulator::{
+        simulation_data::{SimulationData, SimulationSchema},
+        simulation_status::SimulationStatus,
+    },
+};
+
+// Frames are a little endian u32 length followed by that many bytes of JSON
+
+// Anything bigger is taken as a corrupted stream rather than allocated
+const MAX_FRAME_LENGTH: u32 = 512 * 1024 * 1024;
+
+/// Id of the reply the worker sends once ngspice is loaded, before any request.
+/// Requests are numbered from the next one
+pub const LOADED_REPLY_ID: u64 = 0;
+
+/// Sent by the app to the worker through its stdin
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum WorkerRequest {
+    // Loads the netlist and runs it in the background, replies once it is loaded
+    Simulate {
+        netlist: String,
+        breakpoints: Vec<f64>,
+    },
+    SetBreakpoint(f64),
+    Command(String),
+    GetCurrentPlot,
+    GetAllPlots,
+    GetPlotVectors(String),
+    // Runs the command and replies with what ngspice printed meanwhile
+    CommandOutput(String),
+    Quit,
+}
+
+/// A request with the id its reply comes back with. Replies and events share stdout,
+/// a reply that comes after its request timed out must not answer the next one
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WorkerRequestFrame {
+    pub id: u64,
+    pub request: WorkerRequest,
+}
+
+/// Answers to the requests that expect one
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum WorkerReply {
+    Loaded(bool),
+    CurrentPlot(String),
+    AllPlots(Vec<String>),
+    PlotVectors(Vec<PkVectorinfo>),
+    Output(Vec<String>),
+    // What ngspice printed to stderr when the netlist failed to load
+    CircuitLoaded(Result<(), String>),
+}
+
+/// Sent by the worker to the app through its stdout, mirrors the ngspice callbacks
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum WorkerEvent {
+    Status(SimulationStatus),
+    Schema(SimulationSchema),
+    Data(SimulationData),
+    BackgroundThread { is_fin: bool },
+    Exited,
+    Reply { id: u64, reply: WorkerReply },
+}
+
+pub fn write_frame<T: Serialize>(writer: &mut impl Write, message: &T) -> io::Result<()> {
+    let body = serde_json::to_vec(message)?;
+
+    writer.write_all(&(body.len() as u32).to_le_bytes())?;
+    writer.write_all(&body)?;
+    writer.flush()
+}
+
+/// None once the other end closed the stream
+pub fn read_frame<T: DeserializeOwned>(reader: &mut impl Read) -> io::Result<Option<T>> {
+    let mut length = [0u8; 4];
+
+    match reader.read_exact(&mut length) {
+        Ok(()) => {}
+        Err(error) if error.kind() == ErrorKind::UnexpectedEof => return Ok(None),
+        Err(error) => return Err(error),
+    }
+
+    let length = u32::from_le_bytes(length);
+
+    if length > MAX_FRAME_LENGTH {
+        return Err(io::Error::new(
+            ErrorKind::InvalidData,
+            format!("Frame of {} bytes", length),
+        ));
+    }
+
+    let mut body = vec![0u8; length as usize];
+    reader.read_exact(&mut body)?;
+
+    Ok(Some(serde_json::from_slice(&body)?))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use super::*;
+
+    #[test]
+    fn frames_are_read_back_in_order() {
+        let mut stream: Vec<u8> = Vec::default();
+
+        write_frame(
+            &mut stream,
+            &WorkerRequestFrame {
+                id: 1,
+                request: WorkerRequest::Command("bg_run".to_owned()),
+            },
+        )
+        .unwrap();
+        write_frame(
+            &mut stream,
+            &WorkerRequestFrame {
+                id: 2,
+                request: WorkerRequest::Quit,
+            },
+        )
+        .unwrap();
+
+        let mut reader = Cursor::new(stream);
+
+        match read_frame::<WorkerRequestFrame>(&mut reader).unwrap() {
+            Some(WorkerRequestFrame {
+                id: 1,
+                request: WorkerRequest::Command(command),
+            }) => assert_eq!(command, "bg_run"),
+            frame => panic!("Unexpected frame {:?}", frame),
+        }
+
+        match read_frame::<WorkerRequestFrame>(&mut reader).unwrap() {
+            Some(WorkerRequestFrame {
+                id: 2,
+                request: WorkerRequest::Quit,
+            }) => {}
+            frame => panic!("Unexpected frame {:?}", frame),
+        }
+
+        assert!(read_frame::<WorkerRequestFrame>(&mut reader)
+            .unwrap()
+            .is_none());
+    }
+
+    #[test]
+    fn oversized_frames_are_refused() {
+        let mut reader = Cursor::new((MAX_FRAME_LENGTH + 1).to_le_bytes().to_vec());
+
+        let error = read_frame::<WorkerEvent>(&mut reader).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn truncated_frames_are_an_error() {
+        let mut stream: Vec<u8> = Vec::default();
+        write_frame(&mut stream, &WorkerEvent::Exited).unwrap();
+        stream.truncate(stream.len() - 1);
+
+        assert!(read_frame::<WorkerEvent>(&mut Cursor::new(stream)).is_err());
+    }
+}
@@ -0,0 +1,188 @@
+use std::{
+    ffi::OsStr,
+    io::{self, Stdout},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex,
+    },
+};
+
+use crate::{
+    paprika::{
+        ngspice::types::{PkVecinfoall, PkVecvaluesall},
+        spice::{manager::SpiceManager, spice::Spice},
+    },
+    simulator::{
+        simulation_data::{SimulationData, SimulationSchema},
+        simulation_status::SimulationStatus,
+    },
+};
+
+use super::{
+    check_circuit_output,
+    protocol::{
+        read_frame, write_frame, WorkerEvent, WorkerReply, WorkerRequest, WorkerRequestFrame,
+        LOADED_REPLY_ID,
+    },
+};
+
+// Forwards the ngspice callbacks to the app, they come from ngspice's own threads
+#[derive(Clone)]
+struct WorkerManager {
+    stdout: Arc<Mutex<Stdout>>,
+    // Set while a CommandOutput or Simulate request runs
+    captured_output: Arc<Mutex<Option<Vec<String>>>>,
+    // Whether the running analysis had its schema sent, reset by every Simulate request
+    has_schema: Arc<AtomicBool>,
+}
+
+impl WorkerManager {
+    fn send(&self, event: WorkerEvent) {
+        let mut stdout = self.stdout.lock().unwrap();
+
+        if let Err(error) = write_frame(&mut *stdout, &event) {
+            eprintln!("Worker failed to send event {:?}", error);
+        }
+    }
+
+    fn reply(&self, id: u64, reply: WorkerReply) {
+        self.send(WorkerEvent::Reply { id, reply });
+    }
+}
+
+impl SpiceManager for WorkerManager {
+    fn cb_send_char(&mut self, msg: String, _id: i32) {
+        if let Some(output) = self.captured_output.lock().unwrap().as_mut() {
+            output.push(msg.clone());
+        }
+
+        // stdout carries the frames, ngspice output goes to stderr with the app's
+        eprintln!("{}", msg);
+    }
+    fn cb_send_stat(&mut self, msg: String, _id: i32) {
+        if let Ok(status) = SimulationStatus::new(&msg) {
+            self.send(WorkerEvent::Status(status));
+        }
+    }
+    fn cb_ctrldexit(&mut self, _status: i32, _is_immediate: bool, _is_quit: bool, _id: i32) {
+        self.send(WorkerEvent::Exited);
+    }
+    fn cb_send_data(&mut self, pkvecvaluesall: PkVecvaluesall, _count: i32, _id: i32) {
+        if !self.has_schema.swap(true, Ordering::SeqCst) {
+            self.send(WorkerEvent::Schema(SimulationSchema::from_values(
+                &pkvecvaluesall,
+            )));
+        }
+
+        self.send(WorkerEvent::Data(SimulationData::new(pkvecvaluesall)));
+    }
+    fn cb_send_init(&mut self, pkvecinfoall: PkVecinfoall, _id: i32) {
+        self.has_schema.store(true, Ordering::SeqCst);
+        self.send(WorkerEvent::Schema(SimulationSchema::new(&pkvecinfoall)));
+    }
+    fn cb_bgt_state(&mut self, is_fin: bool, _id: i32) {
+        self.send(WorkerEvent::BackgroundThread { is_fin });
+    }
+}
+
+/// Entry point of the spice_worker binary, drives one ngspice instance until the app
+/// sends Quit or closes stdin
+pub fn run_worker(lib_path: &OsStr, id: i32) {
+    let manager = WorkerManager {
+        stdout: Arc::new(Mutex::new(io::stdout())),
+        captured_output: Arc::new(Mutex::new(None)),
+        has_schema: Arc::new(AtomicBool::new(false)),
+    };
+
+    let (spice, library) = match Spice::init(lib_path, manager.clone(), id) {
+        Ok(loaded) => loaded,
+        Err(error) => {
+            eprintln!("Worker {} failed to load ngspice {:?}", id, error);
+            manager.reply(LOADED_REPLY_ID, WorkerReply::Loaded(false));
+            return;
+        }
+    };
+
+    manager.reply(LOADED_REPLY_ID, WorkerReply::Loaded(true));
+
+    let mut stdin = io::stdin().lock();
+
+    loop {
+        let WorkerRequestFrame {
+            id: request_id,
+            request,
+        } = match read_frame::<WorkerRequestFrame>(&mut stdin) {
+            Ok(Some(frame)) => frame,
+            Ok(None) => break,
+            Err(error) => {
+                eprintln!("Worker {} failed to read request {:?}", id, error);
+                break;
+            }
+        };
+
+        match request {
+            WorkerRequest::Simulate {
+                netlist,
+                breakpoints,
+            } => {
+                manager.has_schema.store(false, Ordering::SeqCst);
+                *manager.captured_output.lock().unwrap() = Some(Vec::default());
+                let has_failed = spice.circ(&netlist.lines().collect::<Vec<&str>>());
+                let output = manager.captured_output.lock().unwrap().take();
+
+                let loaded = check_circuit_output(has_failed, &output.unwrap_or_default());
+
+                if loaded.is_ok() {
+                    for breakpoint in breakpoints {
+                        spice.set_breakpoint(breakpoint);
+                    }
+
+                    spice.command("bg_run");
+                }
+
+                manager.reply(request_id, WorkerReply::CircuitLoaded(loaded));
+            }
+
+            WorkerRequest::SetBreakpoint(time) => {
+                spice.set_breakpoint(time);
+            }
+
+            WorkerRequest::Command(command) => {
+                spice.command(&command);
+            }
+
+            WorkerRequest::GetCurrentPlot => {
+                manager.reply(request_id, WorkerReply::CurrentPlot(spice.get_cur_plot()));
+            }
+
+            WorkerRequest::GetAllPlots => {
+                manager.reply(request_id, WorkerReply::AllPlots(spice.get_all_plots()));
+            }
+
+            WorkerRequest::GetPlotVectors(plot) => {
+                let vectors = spice
+                    .get_all_vecs(&plot)
+                    .into_iter()
+                    .filter_map(|vector| spice.get_vec_info(&format!("{}.{}", plot, vector)))
+                    .collect();
+
+                manager.reply(request_id, WorkerReply::PlotVectors(vectors));
+            }
+
+            WorkerRequest::CommandOutput(command) => {
+                *manager.captured_output.lock().unwrap() = Some(Vec::default());
+                spice.command(&command);
+                let output = manager.captured_output.lock().unwrap().take();
+
+                manager.reply(request_id, WorkerReply::Output(output.unwrap_or_default()));
+            }
+
+            WorkerRequest::Quit => break,
+        }
+    }
+
+    drop(spice);
+    if let Err(error) = library.close() {
+        eprintln!("Worker {} failed to unload ngspice {:?}", id, error);
+    }
+}
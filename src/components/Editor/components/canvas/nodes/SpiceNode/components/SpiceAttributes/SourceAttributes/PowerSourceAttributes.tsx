@@ -32,6 +32,7 @@ import TimeDomainSine from "./TimeDomain/TimeDomainSine";
 import TimeDomainExponential from "./TimeDomain/TimeDomainExponential";
 import TimeDomainFm from "./TimeDomain/TimeDomainFm";
 import TimeDomainAm from "./TimeDomain/TimeDomainAm";
+import TimeDomainExternal from "./TimeDomain/TimeDomainExternal";
 import SmallSignal from "./SmallSignal";
 import { isEmpty } from "lodash";
 
@@ -58,6 +59,7 @@ const getTimeDomainFromAttributes = (
     return TimeDomainAnalysis.SingleFrequencyFM;
   if (TimeDomainAnalysis.AmplitudeModulated in data)
     return TimeDomainAnalysis.AmplitudeModulated;
+  if (TimeDomainAnalysis.External in data) return TimeDomainAnalysis.External;
 
   return TimeDomainAnalysis.DC;
 };
@@ -185,6 +187,14 @@ const PowerSourceAttributes: FC<PowerSourceAttributesProps> = ({
                       unregister={unregister}
                     />
                   ))
+                  .with(TimeDomainAnalysis.External, () => (
+                    <TimeDomainExternal
+                      type={type}
+                      errors={errors}
+                      register={register}
+                      unregister={unregister}
+                    />
+                  ))
                   .otherwise(() => null)}
               </div>
             </div>
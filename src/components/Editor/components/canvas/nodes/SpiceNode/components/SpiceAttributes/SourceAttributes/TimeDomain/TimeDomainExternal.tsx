@@ -0,0 +1,44 @@
+import FieldContainer from "@/components/ui/FieldContainer";
+import { Input } from "@/components/ui/input";
+import { Units } from "@/constants/units";
+import { FC, useEffect } from "react";
+import { TimeDomainAnalysisProps } from "./types";
+import { isUnit } from "@/utils/validations";
+import _ from "lodash";
+import { match } from "ts-pattern";
+import { SpiceInstanceName } from "@/components/context/SpiceContext/SpiceContext";
+
+const TimeDomainExternal: FC<TimeDomainAnalysisProps> = ({
+  register,
+  unregister,
+  errors,
+  type
+}) => {
+  useEffect(() => () => unregister("time_domain.External"), []);
+
+  const unit = match(type)
+    .with(SpiceInstanceName.VoltageSource, () => Units.Voltage)
+    .otherwise(() => Units.Current);
+
+  return (
+    <>
+      <FieldContainer
+        error={_.get(errors, "time_domain.External.value")}
+        postfix={unit}
+        tooltip="Value for the operating point, while running the source is set by the co-simulation."
+      >
+        <Input
+          placeholder="DC value"
+          {...register("time_domain.External.value", {
+            setValueAs: (value) => value || undefined,
+            validate: {
+              isUnit: isUnit("dc")
+            }
+          })}
+        />
+      </FieldContainer>
+    </>
+  );
+};
+
+export default TimeDomainExternal;
@@ -64,6 +64,27 @@ const PowerSourceTag: FC<PowerSourceTagProps> = ({ name, data, type }) => {
             </Tooltip>
           )
         )
+        .with(
+          { [TimeDomainAnalysis.External]: P.nonNullable },
+          ({ External: { value } }) => (
+            <Tooltip>
+              <TooltipContent>
+                {"External: (DC value), set by the co-simulation while running"}
+              </TooltipContent>
+              <TooltipTrigger>
+                <Badge className="flex gap-2 items-center">
+                  <Typography
+                    className="font-semibold tracking-tight border-r-2 border-primary-foreground pr-2"
+                    variant="xsmall"
+                  >
+                    EXT
+                  </Typography>
+                  {mapTruthyToLabel([value ? `${value}${powerUnit}` : ""])}
+                </Badge>
+              </TooltipTrigger>
+            </Tooltip>
+          )
+        )
         .with(
           { [TimeDomainAnalysis.Pulse]: P.nonNullable },
           ({ Pulse: { initial_value, final_value, rise_time } }) => (
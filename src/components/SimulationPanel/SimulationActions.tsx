@@ -1,17 +1,28 @@
 import { FC } from "react";
 import useSimulationPanel from "./useSimulationPanel";
 import { Button } from "../ui/Button";
-import { AudioWaveform } from "lucide-react";
-import { anySimulationRunning } from "@/utils/simulation";
+import { AudioWaveform, Pause, Play, Square, StepForward } from "lucide-react";
+import { anySimulationRunning, isSimulationPaused } from "@/utils/simulation";
 
 const SimulationActions: FC = () => {
-  const { simulate, simulationStatus, simulationsToRun } = useSimulationPanel();
+  const {
+    simulate,
+    cancelSimulations,
+    pauseSimulations,
+    resumeSimulations,
+    stepSimulations,
+    simulationStatus,
+    simulationsToRun
+  } = useSimulationPanel();
 
   const hasAnySimulationRunning = anySimulationRunning(simulationStatus);
   const hasNoSimulations = simulationsToRun.size === 0;
+  const hasAnySimulationPaused = Array.from(simulationStatus.values()).some(
+    isSimulationPaused
+  );
 
   return (
-    <div className="w-full mt-auto flex flex-row-reverse">
+    <div className="w-full mt-auto flex flex-row-reverse gap-2">
       <Button
         disabled={hasNoSimulations || hasAnySimulationRunning}
         onClick={simulate}
@@ -21,6 +32,39 @@ const SimulationActions: FC = () => {
           <AudioWaveform className="p-0.5" />
         </div>
       </Button>
+      {hasAnySimulationRunning && (
+        <>
+          <Button variant="outline" onClick={cancelSimulations}>
+            <div className="flex items-center gap-1">
+              Cancel
+              <Square className="p-0.5" />
+            </div>
+          </Button>
+          {hasAnySimulationPaused && (
+            <Button variant="outline" onClick={stepSimulations}>
+              <div className="flex items-center gap-1">
+                Step
+                <StepForward className="p-0.5" />
+              </div>
+            </Button>
+          )}
+          {hasAnySimulationPaused ? (
+            <Button variant="outline" onClick={resumeSimulations}>
+              <div className="flex items-center gap-1">
+                Resume
+                <Play className="p-0.5" />
+              </div>
+            </Button>
+          ) : (
+            <Button variant="outline" onClick={pauseSimulations}>
+              <div className="flex items-center gap-1">
+                Pause
+                <Pause className="p-0.5" />
+              </div>
+            </Button>
+          )}
+        </>
+      )}
     </div>
   );
 };
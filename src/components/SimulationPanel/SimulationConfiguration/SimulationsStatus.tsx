@@ -3,7 +3,13 @@ import { FC, ReactNode } from "react";
 import { match, P } from "ts-pattern";
 import { Typography } from "@/components/ui/Typography";
 import { Progress } from "@/components/ui/progress";
-import { Bolt, CircleCheck, LoaderCircle } from "lucide-react";
+import {
+  Bolt,
+  CircleCheck,
+  CircleX,
+  LoaderCircle,
+  Pause
+} from "lucide-react";
 import clsx from "clsx";
 
 interface SimulationStatusBadgeProps {
@@ -100,6 +106,69 @@ const SimulationStatus: FC<SimulationStatusProps> = ({ status, compact }) => {
             progress={100}
           />
         ))
+        .with({ status: "Paused" }, () => (
+          <SimulationStatusBadge
+            compact={compact}
+            icon={
+              <Pause size={compact ? 15 : 25} className="stroke-primary" />
+            }
+            status="Paused"
+            progress={0}
+          />
+        ))
+        .with({ status: "Running" }, () => (
+          <SimulationStatusBadge
+            compact={compact}
+            icon={
+              <LoaderCircle
+                size={compact ? 15 : 25}
+                className="stroke-primary animate-spin"
+              />
+            }
+            status="Running"
+            progress={0}
+          />
+        ))
+        .with(
+          { status: { Breakpoint: { time: P.number } } },
+          ({
+            status: {
+              Breakpoint: { time }
+            }
+          }) => (
+            <SimulationStatusBadge
+              compact={compact}
+              icon={
+                <Pause size={compact ? 15 : 25} className="stroke-primary" />
+              }
+              status={`Stopped at ${time.toExponential(3)}s`}
+              progress={0}
+            />
+          )
+        )
+        .with({ status: { Failed: P._ } }, () => (
+          <SimulationStatusBadge
+            compact={compact}
+            icon={
+              <CircleX size={compact ? 15 : 25} className="stroke-destructive" />
+            }
+            status="Failed"
+            progress={0}
+          />
+        ))
+        .with({ status: "Cancelled" }, () => (
+          <SimulationStatusBadge
+            compact={compact}
+            icon={
+              <CircleX
+                size={compact ? 15 : 25}
+                className="stroke-muted-foreground"
+              />
+            }
+            status="Cancelled"
+            progress={0}
+          />
+        ))
         .with(
           {
             status: {
@@ -167,6 +167,31 @@ export const Content: FC = () => {
             className="grow w-full"
           />
         </FieldContainer>
+        <FieldContainer
+          tooltip="Times, separated by spaces, at which the simulation stops
+                and shows the current values. It can then be stepped forward
+                or resumed."
+          prefix="Breakpoints"
+        >
+          <Input
+            defaultValue={configuration?.Tran.breakpoints?.join(" ")}
+            {...register("breakpoints", {
+              setValueAs: (value: string | string[] | undefined) =>
+                Array.isArray(value)
+                  ? value
+                  : value?.split(/\s+/).filter(Boolean) || undefined,
+              validate: {
+                isUnit: (breakpoints) =>
+                  (breakpoints || []).every(
+                    (breakpoint) => isUnit("time")(breakpoint) === true
+                  ) || "Breakpoints must be times."
+              }
+            })}
+            disabled={isRunning}
+            placeholder="None"
+            className="grow w-full"
+          />
+        </FieldContainer>
         <FieldContainer
           tooltip=" An optional keyword that indicates that the user does not want
                 ngspice to solve for the quiescent operating point before
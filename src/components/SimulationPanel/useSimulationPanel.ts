@@ -4,9 +4,20 @@ import { useCallback } from "react";
 import { AppNode, NodeType } from "../Editor/components/canvas/nodes/types";
 import { AppEdge } from "../Editor/components/canvas/edges/types";
 import {
+  ComplexFormat,
+  LoadedRun,
+  ResourceReportPayload,
+  RunComparison,
+  RunSummary,
   SimulationDataPayload,
+  SimulationEstimate,
+  SimulationResultPayload,
+  SimulationSchemaPayload,
   SimulationStatusPayload,
-  SimulatorError
+  SimulatorError,
+  StoredPlotInfo,
+  VectorRange,
+  VectorSlice
 } from "@/types/simulation";
 import { useSimulationStore } from "@/store/simulation";
 import {
@@ -15,11 +26,18 @@ import {
   ContractSimulationsToRun
 } from "@/utils/contract";
 import toast from "react-hot-toast";
-import { getToastMessageFromSimulatorError } from "@/utils/simulation";
+import {
+  decodeSimulationData,
+  getToastMessageFromCostWarning,
+  getToastMessageFromSimulatorError,
+  isSimulationRunning,
+  nameSimulationData
+} from "@/utils/simulation";
 import { match, P } from "ts-pattern";
 
 const useSimulationPanel = () => {
   const simulationStatus = useSimulationStore.use.simulationStatus();
+  const simulationSchemas = useSimulationStore.use.simulationSchemas();
 
   const resetSimulations = useSimulationStore.use.resetSimulations();
   const setValidationError = useSimulationStore.use.setValidationError();
@@ -28,7 +46,10 @@ const useSimulationPanel = () => {
   const updateSimulationStatus =
     useSimulationStore.use.updateSimulationStatus();
 
+  const setSimulationSchema = useSimulationStore.use.setSimulationSchema();
   const pushSimulationData = useSimulationStore.use.pushSimulationData();
+  const setSimulationResult = useSimulationStore.use.setSimulationResult();
+  const setSimulationReport = useSimulationStore.use.setSimulationReport();
 
   const simulationsToRun = useSimulationStore.use.simulationsToRun();
 
@@ -57,25 +78,95 @@ const useSimulationPanel = () => {
 
     resetSimulations();
 
+    const schemaUpdateChannel = new Channel<SimulationSchemaPayload>();
+    schemaUpdateChannel.onmessage = setSimulationSchema;
+
     const dataUpdateChannel = new Channel<SimulationDataPayload>();
     dataUpdateChannel.onmessage = (data) => {
       console.log(data);
       pushSimulationData(data);
     };
 
+    // Points come packed in columns, the json channel above is only the fallback
+    const dataColumnsChannel = new Channel<ArrayBuffer>();
+    dataColumnsChannel.onmessage = (buffer) => {
+      pushSimulationData(decodeSimulationData(buffer));
+    };
+
+    // simulate returns once the simulations are started, they are done once all of
+    // them reached a final status
+    const simulationIds = Array.from(simulationsToRun.keys());
+    let isDone = false;
+
     const statusUpdateChannel = new Channel<SimulationStatusPayload>();
-    statusUpdateChannel.onmessage = updateSimulationStatus;
+    statusUpdateChannel.onmessage = (status) => {
+      updateSimulationStatus(status);
+
+      const { simulationStatus } = useSimulationStore.getState();
+      if (
+        !isDone &&
+        simulationIds.every(
+          (id) =>
+            simulationStatus.has(id) &&
+            !isSimulationRunning(simulationStatus.get(id))
+        )
+      ) {
+        isDone = true;
+        toast.success("All simulations done!");
+      }
+
+      match(status)
+        .with({ status: { Failed: P._ } }, ({ status: { Failed } }) => {
+          toast.error(getToastMessageFromSimulatorError(Failed), {
+            duration: 5000
+          });
+        })
+        .otherwise(() => {});
+    };
+
+    const resultUpdateChannel = new Channel<SimulationResultPayload>();
+    resultUpdateChannel.onmessage = setSimulationResult;
+
+    const reportUpdateChannel = new Channel<ResourceReportPayload>();
+    reportUpdateChannel.onmessage = setSimulationReport;
+
+    const contractNodes = ContractNode.toContract(nodes);
+    const contractEdges = ContractEdge.toContract(edges, connectionNodesMap);
+    const contractConfig = Object.fromEntries(
+      simulationsToRun
+    ) as ContractSimulationsToRun;
+
+    // Refused simulations come back as failed statuses, only warnings are shown here
+    invoke<Record<string, SimulationEstimate>>("estimate_simulations", {
+      nodes: contractNodes,
+      edges: contractEdges,
+      config: contractConfig
+    })
+      .then((estimates) => {
+        Object.entries(estimates)
+          .filter(([, { refused }]) => !refused)
+          .forEach(([id, { warnings, suggestion }]) => {
+            warnings.forEach((warning) => {
+              toast(getToastMessageFromCostWarning(id, warning, suggestion), {
+                icon: "⚠️",
+                duration: 5000
+              });
+            });
+          });
+      })
+      .catch(() => {});
 
     invoke<void>("simulate", {
-      nodes: ContractNode.toContract(nodes),
-      edges: ContractEdge.toContract(edges, connectionNodesMap),
-      config: Object.fromEntries(simulationsToRun) as ContractSimulationsToRun,
+      nodes: contractNodes,
+      edges: contractEdges,
+      config: contractConfig,
+      schemaUpdateChannel,
       dataUpdateChannel,
-      statusUpdateChannel
+      dataColumnsChannel,
+      statusUpdateChannel,
+      resultUpdateChannel,
+      reportUpdateChannel
     })
-      .then(() => {
-        toast.success("All simulations done!");
-      })
       .catch((e: SimulatorError) => {
         toast.error(getToastMessageFromSimulatorError(e), { duration: 5000 });
         match(e)
@@ -103,8 +194,158 @@ const useSimulationPanel = () => {
       });
   }, [simulationsToRun]);
 
+  const runningSimulationIds = useCallback(
+    () =>
+      Array.from(simulationStatus.entries())
+        .filter(([, status]) => isSimulationRunning(status))
+        .map(([id]) => id),
+    [simulationStatus]
+  );
+
+  const cancelSimulations = useCallback(() => {
+    for (const id of runningSimulationIds()) {
+      invoke<void>("cancel_simulation", { id });
+    }
+  }, [runningSimulationIds]);
+
+  const pauseSimulations = useCallback(() => {
+    for (const id of runningSimulationIds()) {
+      invoke<void>("pause_simulation", { id });
+    }
+  }, [runningSimulationIds]);
+
+  const resumeSimulations = useCallback(() => {
+    for (const id of runningSimulationIds()) {
+      invoke<void>("resume_simulation", { id });
+    }
+  }, [runningSimulationIds]);
+
+  // Runs paused transients one tstep further
+  const stepSimulations = useCallback(() => {
+    for (const id of runningSimulationIds()) {
+      invoke<void>("step_simulation", { id, step: null });
+    }
+  }, [runningSimulationIds]);
+
+  // Points are decimated while they stream, the backend keeps all of them to zoom in
+  const getSimulationRange = useCallback(
+    async (id: string, from: number, to: number, maxPoints: number) => {
+      const payload = await invoke<SimulationDataPayload | null>(
+        "get_simulation_range",
+        { id, from, to, maxPoints }
+      );
+      const schema = simulationSchemas.get(id);
+
+      if (!payload || !schema) {
+        return [];
+      }
+
+      return nameSimulationData(schema, payload.data);
+    },
+    [simulationSchemas]
+  );
+
+  // Vectors ngspice kept once a simulation is done, at the resolution asked for
+  const getResultPlots = useCallback(
+    (id: string) =>
+      invoke<StoredPlotInfo[] | null>("get_result_plots", { id }),
+    []
+  );
+
+  const getResultVector = useCallback(
+    (
+      id: string,
+      plot: string,
+      vector: string,
+      range: VectorRange,
+      maxPoints: number
+    ) =>
+      invoke<VectorSlice | null>("get_result_vector", {
+        id,
+        plot,
+        vector,
+        range,
+        maxPoints
+      }),
+    []
+  );
+
+  // The next simulations run ngspice again even if nothing changed
+  const clearResultCache = useCallback(
+    () => invoke<void>("clear_result_cache"),
+    []
+  );
+
+  // Writes the vectors the backend kept for a finished simulation, csv holds a single plot
+  const exportResults = useCallback(
+    (
+      id: string,
+      format: "csv" | "json",
+      complexFormat: ComplexFormat,
+      filePath: string,
+      plot?: string
+    ) =>
+      invoke<void>(
+        format === "csv" ? "export_results_csv" : "export_results_json",
+        format === "csv"
+          ? { id, plot: plot ?? null, complexFormat, filePath }
+          : { id, complexFormat, filePath }
+      ).catch((e: SimulatorError) => {
+        toast.error(getToastMessageFromSimulatorError(e), { duration: 5000 });
+      }),
+    []
+  );
+
+  // Runs are saved next to the project once they finish
+  const listRuns = useCallback(() => invoke<RunSummary[]>("list_runs"), []);
+
+  // Shows a past run again, its vectors are then queried like a finished one
+  const loadRun = useCallback(async (id: string) => {
+    const run = await invoke<LoadedRun>("load_run", { id }).catch(
+      (e: SimulatorError) => {
+        toast.error(getToastMessageFromSimulatorError(e), { duration: 5000 });
+        return null;
+      }
+    );
+
+    if (run) {
+      resetSimulations();
+      Object.entries(run.results).forEach(([resultId, result]) => {
+        setSimulationResult({ id: resultId, result });
+      });
+    }
+
+    return run;
+  }, []);
+
+  const compareRuns = useCallback(
+    (a: string, b: string, simulationId: string, maxPoints: number) =>
+      invoke<RunComparison>("compare_runs", {
+        a,
+        b,
+        simulationId,
+        maxPoints
+      }).catch((e: SimulatorError) => {
+        toast.error(getToastMessageFromSimulatorError(e), { duration: 5000 });
+        return null;
+      }),
+    []
+  );
+
   return {
     simulate,
+    cancelSimulations,
+    pauseSimulations,
+    resumeSimulations,
+    stepSimulations,
+    getSimulationRange,
+    getResultPlots,
+    getResultVector,
+    clearResultCache,
+    exportResults,
+    listRuns,
+    loadRun,
+    compareRuns,
     simulationStatus,
     simulationsToRun
   };
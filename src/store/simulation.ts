@@ -1,13 +1,20 @@
 import {
+  ResourceReport,
+  ResourceReportPayload,
+  RawSimulationData,
   SimulationConfig,
   SimulationData,
   SimulationDataPayload,
+  SimulationResult,
+  SimulationResultPayload,
+  SimulationSchema,
+  SimulationSchemaPayload,
   SimulationStatusPayload
 } from "@/types/simulation";
 import { create } from "zustand";
 import { v4 as uuidv4 } from "uuid";
 import { createSelectors } from "@/utils/zustand";
-import { getIdOfType } from "@/utils/simulation";
+import { getIdOfType, nameSimulationData } from "@/utils/simulation";
 import { subscribeWithSelector } from "zustand/middleware";
 
 interface SimulationStore {
@@ -23,12 +30,20 @@ interface SimulationStore {
   dequeueSimulation(simulationToRemove: string): void;
 
   simulationStatus: Map<string, SimulationStatusPayload>;
+  simulationSchemas: Map<string, SimulationSchema>;
+  // Points that came in before the schema of their simulation
+  pendingSimulationData: Map<string, Array<RawSimulationData>>;
   simulationData: Map<string, Array<SimulationData>>;
+  simulationResults: Map<string, SimulationResult>;
+  simulationReports: Map<string, ResourceReport>;
 
   updateSimulationStatus(newStatus: SimulationStatusPayload): void;
   resetSimulations(): void;
 
+  setSimulationSchema(newSchema: SimulationSchemaPayload): void;
   pushSimulationData(newDataItem: SimulationDataPayload): void;
+  setSimulationResult(newResult: SimulationResultPayload): void;
+  setSimulationReport(newReport: ResourceReportPayload): void;
 
   validationError: [string, string] | null;
   setValidationError(elementName: string, error: string): void;
@@ -45,8 +60,12 @@ const useSimulationStoreBase = create<SimulationStore>()(
         return { simulationsToRun: new Map(Object.entries(simulationsToRun)) };
       }),
 
+    simulationSchemas: new Map(),
+    pendingSimulationData: new Map(),
     simulationData: new Map(),
     simulationStatus: new Map(),
+    simulationResults: new Map(),
+    simulationReports: new Map(),
 
     enqueueSimulation: (newSimulationConfig, isOfType) =>
       set((state) => {
@@ -71,20 +90,75 @@ const useSimulationStoreBase = create<SimulationStore>()(
         return { simulationStatus: new Map(state.simulationStatus) };
       }),
 
+    setSimulationSchema: (newSchema) =>
+      set((state) => {
+        state.simulationSchemas.set(newSchema.id, newSchema.schema);
+
+        const pending = state.pendingSimulationData.get(newSchema.id) || [];
+        state.pendingSimulationData.delete(newSchema.id);
+
+        if (pending.length > 0) {
+          const current = state.simulationData.get(newSchema.id) || [];
+
+          current.push(...nameSimulationData(newSchema.schema, pending));
+
+          state.simulationData.set(newSchema.id, current);
+        }
+
+        return {
+          simulationSchemas: new Map(state.simulationSchemas),
+          simulationData: new Map(state.simulationData)
+        };
+      }),
+
     pushSimulationData: (newDataItem) =>
       set((state) => {
+        const schema = state.simulationSchemas.get(newDataItem.id);
+
+        if (!schema) {
+          const pending =
+            state.pendingSimulationData.get(newDataItem.id) || [];
+
+          pending.push(...newDataItem.data);
+
+          state.pendingSimulationData.set(newDataItem.id, pending);
+
+          return {};
+        }
+
         const current = state.simulationData.get(newDataItem.id) || [];
 
-        current.push(...newDataItem.data);
+        current.push(...nameSimulationData(schema, newDataItem.data));
 
         state.simulationData.set(newDataItem.id, current);
 
         return { simulationData: new Map(state.simulationData) };
       }),
 
+    setSimulationResult: (newResult) =>
+      set((state) => {
+        state.simulationResults.set(newResult.id, newResult.result);
+
+        return { simulationResults: new Map(state.simulationResults) };
+      }),
+
+    setSimulationReport: (newReport) =>
+      set((state) => {
+        state.simulationReports.set(newReport.id, newReport.report);
+
+        return { simulationReports: new Map(state.simulationReports) };
+      }),
+
     resetSimulations: () =>
       set(() => {
-        return { simulationStatus: new Map(), simulationData: new Map() };
+        return {
+          simulationStatus: new Map(),
+          simulationSchemas: new Map(),
+          pendingSimulationData: new Map(),
+          simulationData: new Map(),
+          simulationResults: new Map(),
+          simulationReports: new Map()
+        };
       }),
 
     validationError: null,
@@ -94,7 +168,11 @@ const useSimulationStoreBase = create<SimulationStore>()(
 
     clearStoredData: () =>
       set(() => ({
+        simulationSchemas: new Map(),
+        pendingSimulationData: new Map(),
         simulationData: new Map(),
+        simulationResults: new Map(),
+        simulationReports: new Map(),
         simulationsToRun: new Map(),
         simulationStatus: new Map(),
         validationError: null
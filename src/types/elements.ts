@@ -4,7 +4,8 @@ export enum TimeDomainAnalysis {
   Sine = "Sin",
   Exponential = "Exp",
   SingleFrequencyFM = "Sffm",
-  AmplitudeModulated = "Am"
+  AmplitudeModulated = "Am",
+  External = "External"
 }
 
 export enum TimeDomainAnalysisDisplay {
@@ -13,7 +14,8 @@ export enum TimeDomainAnalysisDisplay {
   Sin = "Sinusoidal",
   Exp = "Exponential",
   Sffm = "Single Frequency FM",
-  Am = "Amplitude Modulated"
+  Am = "Amplitude Modulated",
+  External = "External"
 }
 
 export type TimeDomainParameters =
@@ -69,6 +71,11 @@ export type TimeDomainParameters =
         carrier_frequency: string;
         delay: string;
       };
+    }
+  | {
+      [TimeDomainAnalysis.External]: {
+        value?: string;
+      };
     };
 
 export type SmallsignalParameters = {
@@ -11,7 +11,10 @@ export enum Simulation {
   Distortion = "disto",
   Noise = "noise",
   PoleZero = "pz",
-  Sensitivity = "sens"
+  Sensitivity = "sens",
+  TransferFunction = "tf",
+  Stability = "stability",
+  SParameters = "sp"
 }
 
 export enum SimulationDisplay {
@@ -22,7 +25,10 @@ export enum SimulationDisplay {
   disto = "Distortion analysis",
   noise = "Noise analysis",
   pz = "Pole-Zero analysis",
-  sens = "Senitivity analysis"
+  sens = "Senitivity analysis",
+  tf = "Transfer function analysis",
+  stability = "Stability analysis",
+  sp = "S-parameter analysis"
 }
 
 type BaseSimulationEvent = {
@@ -46,7 +52,43 @@ type Progress = {
   };
 };
 
-export type SimulationStatus = SourceDeck | Ready | Progress;
+type Paused = {
+  status: "Paused";
+};
+
+// Resumed after a pause or a breakpoint, until its progress comes in
+type Running = {
+  status: "Running";
+};
+
+type Breakpoint = {
+  status: {
+    Breakpoint: {
+      time: number;
+      values: ComputedData[];
+    };
+  };
+};
+
+type Cancelled = {
+  status: "Cancelled";
+};
+
+type Failed = {
+  status: {
+    Failed: SimulatorError;
+  };
+};
+
+export type SimulationStatus =
+  | SourceDeck
+  | Ready
+  | Progress
+  | Paused
+  | Running
+  | Breakpoint
+  | Cancelled
+  | Failed;
 
 export type SimulationStatusPayload = BaseSimulationEvent & SimulationStatus;
 
@@ -76,12 +118,233 @@ export interface SimulationData {
   computed_values_for_index: Array<ComputedData>;
 }
 
+export type VectorKind = "Voltage" | "Current" | "Time" | "Frequency" | "Other";
+
+export interface VectorSchema {
+  name: string;
+  kind: VectorKind;
+  is_complex: boolean;
+  is_scale: boolean;
+  // Connection nodes of a node voltage, or the element node of a current
+  canvas_ids: string[];
+}
+
+export interface SimulationSchema {
+  plot: string;
+  plot_type: string;
+  vectors: VectorSchema[];
+}
+
+export type SimulationSchemaPayload = BaseSimulationEvent & {
+  schema: SimulationSchema;
+};
+
+// Points as they are sent, real and imaginary parts in the order of the schema vectors
+export interface RawSimulationData {
+  computed: number;
+  data_index: number;
+  values: Array<[number, number]>;
+}
+
 export type SimulationDataPayload = BaseSimulationEvent & {
-  data: Array<SimulationData>;
+  data: Array<RawSimulationData>;
+};
+
+export interface StoredVectorInfo {
+  name: string;
+  kind: VectorKind;
+  is_complex: boolean;
+  length: number;
+}
+
+// A plot ngspice kept for a finished simulation, like tran1
+export interface StoredPlotInfo {
+  name: string;
+  scale: string | null;
+  vectors: StoredVectorInfo[];
+}
+
+// By index with the end excluded, or by time or frequency
+export type VectorRange =
+  | { Index: { start: number; end: number } }
+  | { Scale: { from: number; to: number } };
+
+// How exported complex vectors are split into columns, phase in degrees
+export type ComplexFormat = "RealImaginary" | "MagnitudePhase";
+
+export interface VectorSlice {
+  name: string;
+  // Index of each point in the whole vector
+  indices: number[];
+  scale: number[] | null;
+  real: number[];
+  imag: number[] | null;
+}
+
+export interface TransferFunctionResult {
+  transfer_function: number;
+  output_impedance: number;
+  input_impedance: number;
+}
+
+export interface Root {
+  real: number;
+  imag: number;
+  omega_n: number;
+  f_n: number;
+  damping_ratio: number | null;
+  q: number | null;
+  is_right_half_plane: boolean;
+}
+
+export interface PoleZeroResult {
+  poles: Array<Root>;
+  zeros: Array<Root>;
+  has_right_half_plane_poles: boolean;
+}
+
+export interface NoiseContribution {
+  device: string;
+  // Noise generators of the device as named by ngspice
+  generators: Array<string>;
+  output_total: number;
+  share: number;
+}
+
+export interface NoiseSummary {
+  output_total: number;
+  input_total: number;
+  contributions: Array<NoiseContribution>;
+}
+
+export interface LoopGainResult {
+  frequency: Array<number>;
+  gain_db: Array<number>;
+  // Unwrapped, in degrees
+  phase: Array<number>;
+  crossover_frequency?: number;
+  phase_margin?: number;
+  phase_crossover_frequency?: number;
+  gain_margin?: number;
+}
+
+export interface ComplexValue {
+  real: number;
+  imag: number;
+}
+
+// One matrix per frequency point, indexed as [row][column]
+export type NetworkMatrices = Array<Array<Array<ComplexValue>>>;
+
+export enum NetworkParameter {
+  S = "S",
+  Y = "Y",
+  Z = "Z",
+  H = "H"
+}
+
+export interface NetworkParameters {
+  ports: Array<string>;
+  reference_impedances: Array<number>;
+  frequency: Array<number>;
+  s: NetworkMatrices;
+  // Missing when the matrices to invert are singular
+  y?: NetworkMatrices;
+  z?: NetworkMatrices;
+  // Only defined for two-ports
+  h?: NetworkMatrices;
+}
+
+export type SimulationResult =
+  | { Tf: TransferFunctionResult }
+  | { Pz: PoleZeroResult }
+  | { Noise: NoiseSummary }
+  | { LoopGain: LoopGainResult }
+  | { Sp: NetworkParameters };
+
+export type SimulationResultPayload = BaseSimulationEvent & {
+  result: SimulationResult;
 };
 
+// A finished simulate call saved next to the project, created_at in unix seconds
+export interface RunSummary {
+  id: string;
+  created_at: number;
+  config: Record<string, SimulationConfig>;
+  simulations: string[];
+}
+
+export interface LoadedRun {
+  summary: RunSummary;
+  results: Record<string, SimulationResult>;
+}
+
+// Each run against its own scale, their steps may differ
+export interface VectorComparison {
+  name: string;
+  plot: number;
+  a: VectorSlice;
+  b: VectorSlice;
+}
+
+export interface RunComparison {
+  simulation_id: string;
+  a: string;
+  b: string;
+  config_changed: boolean;
+  netlist_changed: boolean;
+  vectors: VectorComparison[];
+}
+
+// Runtime and memory a simulation took, fields read from rusage may be missing
+export interface ResourceReport {
+  run_time: number;
+  points: number;
+  analysis_time: number | null;
+  elapsed_time: number | null;
+  max_program_size: number | null;
+  current_program_size: number | null;
+  accepted_timepoints: number | null;
+  rejected_timepoints: number | null;
+  iterations: number | null;
+}
+
+export type ResourceReportPayload = BaseSimulationEvent & {
+  report: ResourceReport;
+};
+
+export type CostWarning =
+  | { TooManyPoints: number }
+  | { TooMuchMemory: number }
+  | { CoarseTimestep: { max_step: number; feature: number } };
+
+export interface TimestepSuggestion {
+  tstep: string;
+  tmax: string;
+}
+
+// Expected size of a simulation, computed before it runs
+export interface SimulationEstimate {
+  points: number | null;
+  vectors: number;
+  // Bytes
+  memory: number | null;
+  warnings: CostWarning[];
+  refused: boolean;
+  suggestion: TimestepSuggestion | null;
+}
+
+// Stops a simulation that runs away, e.g. a transient with a far too small tstep
+export interface SimulationLimits {
+  // Wall-clock milliseconds, time spent paused is not counted
+  timeout?: number;
+  max_points?: number;
+}
+
 export type OperatingPointConfig = {
-  Op: {};
+  Op: {
+    limits?: SimulationLimits;
+  };
 };
 
 export type TransientAnalysisConfig = {
@@ -91,6 +354,9 @@ export type TransientAnalysisConfig = {
     tstart?: string;
     tmax?: string;
     uic?: boolean;
+    breakpoints?: string[];
+
+    limits?: SimulationLimits;
   };
 };
 
@@ -154,6 +420,8 @@ export type SmallSignalACAnalysisConfig = {
     fstop: string;
     variation: FrequencyVariation;
     nx: number;
+
+    limits?: SimulationLimits;
   };
 };
 
@@ -173,6 +441,8 @@ export type DCAnalysisConfig = {
     start2?: string;
     stop2?: string;
     incr2?: string;
+
+    limits?: SimulationLimits;
   };
 };
 
@@ -184,6 +454,8 @@ export type DistortionAnalysisConfig = {
     nx: number;
     // If the optional f2overf1 parameter is specified, it should be a real number between (and not equal to) 0.0 and 1.0
     f2overf1?: number;
+
+    limits?: SimulationLimits;
   };
 };
 
@@ -201,6 +473,8 @@ export type NoiseAnalysisConfig = {
     fstop: string;
 
     pts_per_summary?: number;
+
+    limits?: SimulationLimits;
   };
 };
 
@@ -212,6 +486,8 @@ export type PoleZeroAnalysisConfig = {
     node4: string;
     transfer_function: TransferFunction;
     analysis_type: PoleZeroAnalysisType;
+
+    limits?: SimulationLimits;
   };
 };
 
@@ -226,6 +502,59 @@ export type SensitivityAnalysisConfig = {
     fstop?: string;
     variation?: FrequencyVariation;
     nx?: number;
+
+    limits?: SimulationLimits;
+  };
+};
+
+export type TransferFunctionAnalysisConfig = {
+  Tf: {
+    output_type: CurrentOrVoltage;
+    output: string;
+    // Defaults to GND, only used for voltage outputs
+    oref?: string;
+    src: string;
+
+    limits?: SimulationLimits;
+  };
+};
+
+export type StabilityAnalysisConfig = {
+  Stability: {
+    // Element on the loop input side of the break
+    element: string;
+    // Net where the loop is broken
+    node: string;
+
+    // Same as Ac
+    fstart: string;
+    fstop: string;
+    variation: FrequencyVariation;
+    nx: number;
+
+    limits?: SimulationLimits;
+  };
+};
+
+export type SParameterPortConfig = {
+  node: string;
+  // Defaults to GND
+  reference?: string;
+  // Defaults to 50 ohm
+  impedance?: string;
+};
+
+export type SParameterAnalysisConfig = {
+  Sp: {
+    // Same as Ac
+    fstart: string;
+    fstop: string;
+    variation: FrequencyVariation;
+    nx: number;
+
+    ports: Array<SParameterPortConfig>;
+
+    limits?: SimulationLimits;
   };
 };
 
@@ -237,7 +566,10 @@ export type SimulationConfig =
   | DistortionAnalysisConfig
   | NoiseAnalysisConfig
   | PoleZeroAnalysisConfig
-  | SensitivityAnalysisConfig;
+  | SensitivityAnalysisConfig
+  | TransferFunctionAnalysisConfig
+  | StabilityAnalysisConfig
+  | SParameterAnalysisConfig;
 
 export enum SimulationBasis {
   Time = "time",
@@ -265,6 +597,57 @@ export type SimulatorError =
         | "Disto"
         | "Noise"
         | "Pz"
-        | "Sens";
+        | "Sens"
+        | "Tf"
+        | "Stability"
+        | "Sp";
     }
-  | "FailedToSaveGraphicSpiceFile";
+  | "FailedToSaveGraphicSpiceFile"
+  | { FailedToLoadGraphicSpiceFile: string }
+  | { InvalidLoopBreak: string }
+  | { InvalidPort: string }
+  | "FailedToExportTouchstone"
+  | { SpiceFailedToLoad: string }
+  | "SpiceExited"
+  | "SpiceLibraryNotFound"
+  | { FailedToCopySpiceLibrary: string }
+  | { UnknownSpiceVersion: string }
+  | { FailedToStartWorker: string }
+  | { WorkerTimedOut: number }
+  | { FailedToLoadCircuit: string }
+  | { SimulationTimedOut: number }
+  | { PointLimitExceeded: number }
+  | { SimulationTooLarge: { points: number; memory: number } }
+  | "ExternalSourcesInWorker"
+  | "FailedToSaveRun"
+  | { RunNotFound: string }
+  | { IncomparableRuns: string }
+  | { NoResultsToExport: string }
+  | "FailedToExportResults"
+  | { IncompleteAnalysis: string };
+
+export type WorkerMode = "InProcess" | "Process";
+
+// Memory in bytes, a null max never refuses
+export interface CostThresholds {
+  warn_points: number;
+  warn_memory: number;
+  max_points: number | null;
+  max_memory: number | null;
+}
+
+export interface SpiceSettings {
+  library_path: string | null;
+  pool_size: number | null;
+  worker_mode: WorkerMode;
+  cost_thresholds: CostThresholds;
+  // Points per second streamed for live display, 2000 when null
+  display_points_per_second: number | null;
+  // Unchanged analyses are served from the result cache, unless false
+  cache_results: boolean | null;
+}
+
+export interface SpiceLibraryInfo {
+  path: string;
+  version: string;
+}
@@ -9,8 +9,17 @@ import {
   NoiseAnalysisConfig,
   PoleZeroAnalysisConfig,
   SensitivityAnalysisConfig,
+  TransferFunctionAnalysisConfig,
+  StabilityAnalysisConfig,
+  SParameterAnalysisConfig,
   SimulatorError,
-  SimulationDisplay
+  SimulationDisplay,
+  CostWarning,
+  TimestepSuggestion,
+  RawSimulationData,
+  SimulationData,
+  SimulationDataPayload,
+  SimulationSchema
 } from "@/types/simulation";
 
 import _ from "lodash";
@@ -58,6 +67,24 @@ export const isSensitivityAnalysis = (
   return "Sens" in config;
 };
 
+export const isTransferFunctionAnalysis = (
+  config: SimulationConfig
+): config is TransferFunctionAnalysisConfig => {
+  return "Tf" in config;
+};
+
+export const isStabilityAnalysis = (
+  config: SimulationConfig
+): config is StabilityAnalysisConfig => {
+  return "Stability" in config;
+};
+
+export const isSParameterAnalysis = (
+  config: SimulationConfig
+): config is SParameterAnalysisConfig => {
+  return "Sp" in config;
+};
+
 export const isDCAnalysis = (
   config: SimulationConfig
 ): config is DCAnalysisConfig => {
@@ -70,8 +97,77 @@ export const isSimulationRunning = (
   match(status)
     .with(P.nullish, () => false)
     .with({ status: "Ready" }, () => false)
+    .with({ status: "Cancelled" }, () => false)
+    .with({ status: { Failed: P._ } }, () => false)
     .otherwise(() => true);
 
+// Gives the values of the points back the names and flags of their vectors
+export const nameSimulationData = (
+  schema: SimulationSchema,
+  data: RawSimulationData[]
+): SimulationData[] =>
+  data.map(({ computed, data_index, values }) => ({
+    computed,
+    data_index,
+    computed_values_for_index: schema.vectors.map(
+      ({ name, is_scale, is_complex }, index) => ({
+        name,
+        c_real: values[index]?.[0] ?? 0,
+        c_imag: values[index]?.[1] ?? 0,
+        is_scale,
+        is_complex
+      })
+    )
+  }));
+
+// Reads the columns the simulate command packs the points into, see `to_columns`
+export const decodeSimulationData = (
+  buffer: ArrayBuffer
+): SimulationDataPayload => {
+  const view = new DataView(buffer);
+  const idLength = view.getUint32(4, true);
+  const pointCount = view.getUint32(8, true);
+  const vectorCount = view.getUint32(12, true);
+
+  const id = new TextDecoder().decode(new Uint8Array(buffer, 16, idLength));
+  const hasImag = new Uint8Array(buffer, 16 + idLength, vectorCount);
+  const headerLength = Math.ceil((16 + idLength + vectorCount) / 8) * 8;
+
+  let offset = headerLength;
+  const nextColumn = () => {
+    const column = new Float64Array(buffer, offset, pointCount);
+    offset += pointCount * 8;
+    return column;
+  };
+
+  const computed = nextColumn();
+  const dataIndex = nextColumn();
+  const vectors = Array.from(hasImag, (imag) => ({
+    real: nextColumn(),
+    imag: imag ? nextColumn() : undefined
+  }));
+
+  return {
+    id,
+    data: Array.from({ length: pointCount }, (_, point) => ({
+      computed: computed[point],
+      data_index: dataIndex[point],
+      values: vectors.map(({ real, imag }): [number, number] => [
+        real[point],
+        imag?.[point] ?? 0
+      ])
+    }))
+  };
+};
+
+export const isSimulationPaused = (
+  status: SimulationStatusPayload | undefined
+) =>
+  match(status)
+    .with({ status: "Paused" }, () => true)
+    .with({ status: { Breakpoint: P._ } }, () => true)
+    .otherwise(() => false);
+
 export const anySimulationRunning = (
   statusMap: Map<string, SimulationStatusPayload>
 ) => {
@@ -124,6 +220,34 @@ export const getIdOfType = <T extends SimulationConfig>(
   return null;
 };
 
+const formatBytes = (bytes: number): string => {
+  if (bytes >= 1024 ** 3) {
+    return `${(bytes / 1024 ** 3).toFixed(1)} GB`;
+  }
+
+  return `${(bytes / 1024 ** 2).toFixed(1)} MB`;
+};
+
+export const getToastMessageFromCostWarning = (
+  id: string,
+  warning: CostWarning,
+  suggestion: TimestepSuggestion | null
+): string => {
+  return match(warning)
+    .with({ TooManyPoints: P.number }, ({ TooManyPoints }) => {
+      return `${id} will output about ${TooManyPoints} points.`;
+    })
+    .with({ TooMuchMemory: P.number }, ({ TooMuchMemory }) => {
+      return `${id} will take about ${formatBytes(TooMuchMemory)} of results.`;
+    })
+    .with({ CoarseTimestep: P._ }, () => {
+      return suggestion
+        ? `${id} steps over its fastest source, try tstep ${suggestion.tstep} and tmax ${suggestion.tmax}.`
+        : `${id} steps over its fastest source.`;
+    })
+    .exhaustive();
+};
+
 export const getToastMessageFromSimulatorError = (
   e: SimulatorError
 ): string => {
@@ -143,6 +267,81 @@ export const getToastMessageFromSimulatorError = (
     .with("FailedToSaveGraphicSpiceFile", () => {
       return `Failed to save graphic spice file. Please try again.`;
     })
+    .with(
+      { FailedToLoadGraphicSpiceFile: P.string },
+      ({ FailedToLoadGraphicSpiceFile }) => {
+        return `Failed to load graphic spice file ${FailedToLoadGraphicSpiceFile}.`;
+      },
+    )
+    .with({ InvalidPort: P.string }, ({ InvalidPort }) => {
+      return `Port ${InvalidPort} must be connected to a non ground node.`;
+    })
+    .with("FailedToExportTouchstone", () => {
+      return `Failed to export Touchstone file.`;
+    })
+    .with({ SpiceFailedToLoad: P.string }, ({ SpiceFailedToLoad }) => {
+      return `Failed to load ngspice from ${SpiceFailedToLoad}.`;
+    })
+    .with("SpiceExited", () => {
+      return `Ngspice exited during the simulation, it was restarted.`;
+    })
+    .with("SpiceLibraryNotFound", () => {
+      return `No ngspice library found, please select one in the settings.`;
+    })
+    .with(
+      { FailedToCopySpiceLibrary: P.string },
+      ({ FailedToCopySpiceLibrary }) => {
+        return `Failed to prepare ngspice from ${FailedToCopySpiceLibrary}.`;
+      }
+    )
+    .with({ UnknownSpiceVersion: P.string }, ({ UnknownSpiceVersion }) => {
+      return `Could not detect the ngspice version of ${UnknownSpiceVersion}.`;
+    })
+    .with({ FailedToStartWorker: P.string }, ({ FailedToStartWorker }) => {
+      return `Failed to start the simulation worker ${FailedToStartWorker}.`;
+    })
+    .with({ WorkerTimedOut: P.number }, ({ WorkerTimedOut }) => {
+      return `The simulation worker did not answer within ${WorkerTimedOut} s.`;
+    })
+    .with({ FailedToLoadCircuit: P.string }, ({ FailedToLoadCircuit }) => {
+      return `ngspice failed to load the netlist: ${FailedToLoadCircuit}`;
+    })
+    .with({ SimulationTimedOut: P.number }, ({ SimulationTimedOut }) => {
+      return `Simulation stopped after running for ${SimulationTimedOut} ms, check its time step.`;
+    })
+    .with({ PointLimitExceeded: P.number }, ({ PointLimitExceeded }) => {
+      return `Simulation stopped after ${PointLimitExceeded} points, check its time step.`;
+    })
+    .with(
+      { SimulationTooLarge: { points: P.number, memory: P.number } },
+      ({ SimulationTooLarge: { points, memory } }) => {
+        return `Simulation not run, about ${points} points and ${formatBytes(memory)} estimated.`;
+      }
+    )
+    .with("ExternalSourcesInWorker", () => {
+      return `External sources can only be co-simulated with ngspice loaded in the app.`;
+    })
+    .with("FailedToSaveRun", () => {
+      return `Failed to save the simulation results next to the project.`;
+    })
+    .with({ RunNotFound: P.string }, ({ RunNotFound }) => {
+      return `No saved run ${RunNotFound} for this project.`;
+    })
+    .with({ IncomparableRuns: P.string }, ({ IncomparableRuns }) => {
+      return `Cannot compare ${IncomparableRuns}, both runs must have the same analysis.`;
+    })
+    .with({ NoResultsToExport: P.string }, ({ NoResultsToExport }) => {
+      return `No results of ${NoResultsToExport} to export, run the simulation first.`;
+    })
+    .with("FailedToExportResults", () => {
+      return `Failed to export the simulation results.`;
+    })
+    .with({ IncompleteAnalysis: P.string }, ({ IncompleteAnalysis }) => {
+      return `Run ${IncompleteAnalysis} gave no results to combine, the analysis was stopped.`;
+    })
+    .with({ InvalidLoopBreak: P.string }, ({ InvalidLoopBreak }) => {
+      return `Cannot break the loop at ${InvalidLoopBreak}, the element must be connected to a non ground node.`;
+    })
     .with(
       { MalformedSimulationConfig: P.string },
       ({ MalformedSimulationConfig }) => {